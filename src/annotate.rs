@@ -3,6 +3,28 @@
 use crate::query::Query;
 use crate::{Album, Artist, Client, Error, Result, Song};
 
+/// The artists, albums and songs the current user has starred.
+///
+/// Each entry records when it was starred; see, for example,
+/// [`Song::is_starred`].
+///
+/// [`Song::is_starred`]: ./song/struct.Song.html#method.is_starred
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Starred {
+    /// Starred artists.
+    #[serde(rename = "artist")]
+    #[serde(default)]
+    pub artists: Vec<Artist>,
+    /// Starred albums.
+    #[serde(rename = "album")]
+    #[serde(default)]
+    pub albums: Vec<Album>,
+    /// Starred songs.
+    #[serde(rename = "song")]
+    #[serde(default)]
+    pub songs: Vec<Song>,
+}
+
 /// Allows starring, rating, and scrobbling media.
 pub trait Annotatable {
     /// Attaches a star to the content.
@@ -29,6 +51,13 @@ pub trait Annotatable {
     ///
     /// `time` should be a valid ISO8601 timestamp. In the future, this will be
     /// validated.
+    ///
+    /// Note that `now_playing` is the inverse of the API's `submission`
+    /// parameter. For songs, [`Client::now_playing_notify`] and
+    /// [`Client::scrobble_play`] make the intent explicit.
+    ///
+    /// [`Client::now_playing_notify`]: ./struct.Client.html#method.now_playing_notify
+    /// [`Client::scrobble_play`]: ./struct.Client.html#method.scrobble_play
     fn scrobble<'a, B, T>(&self, client: &Client, time: T, now_playing: B) -> Result<()>
     where
         B: Into<Option<bool>>,
@@ -37,12 +66,12 @@ pub trait Annotatable {
 
 impl Annotatable for Artist {
     fn star(&self, client: &Client) -> Result<()> {
-        client.get("star", Query::with("artistId", self.id))?;
+        client.get_value("star", Query::with("artistId", self.id.as_str()))?;
         Ok(())
     }
 
     fn unstar(&self, client: &Client) -> Result<()> {
-        client.get("unstar", Query::with("artistId", self.id))?;
+        client.get_value("unstar", Query::with("artistId", self.id.as_str()))?;
         Ok(())
     }
 
@@ -51,8 +80,10 @@ impl Annotatable for Artist {
             return Err(Error::Other("rating must be between 0 and 5 inclusive"));
         }
 
-        let args = Query::with("id", self.id).arg("rating", rating).build();
-        client.get("setRating", args)?;
+        let args = Query::with("id", self.id.as_str())
+            .arg("rating", rating)
+            .build();
+        client.get_value("setRating", args)?;
         Ok(())
     }
 
@@ -61,23 +92,23 @@ impl Annotatable for Artist {
         B: Into<Option<bool>>,
         T: Into<Option<&'a str>>,
     {
-        let args = Query::with("id", self.id)
+        let args = Query::with("id", self.id.as_str())
             .arg("time", time.into())
             .arg("submission", now_playing.into().map(|b| !b))
             .build();
-        client.get("scrobble", args)?;
+        client.get_value("scrobble", args)?;
         Ok(())
     }
 }
 
 impl Annotatable for Album {
     fn star(&self, client: &Client) -> Result<()> {
-        client.get("star", Query::with("albumId", self.id))?;
+        client.get_value("star", Query::with("albumId", self.id.as_str()))?;
         Ok(())
     }
 
     fn unstar(&self, client: &Client) -> Result<()> {
-        client.get("unstar", Query::with("albumId", self.id))?;
+        client.get_value("unstar", Query::with("albumId", self.id.as_str()))?;
         Ok(())
     }
 
@@ -86,8 +117,10 @@ impl Annotatable for Album {
             return Err(Error::Other("rating must be between 0 and 5 inclusive"));
         }
 
-        let args = Query::with("id", self.id).arg("rating", rating).build();
-        client.get("setRating", args)?;
+        let args = Query::with("id", self.id.as_str())
+            .arg("rating", rating)
+            .build();
+        client.get_value("setRating", args)?;
         Ok(())
     }
 
@@ -96,23 +129,23 @@ impl Annotatable for Album {
         B: Into<Option<bool>>,
         T: Into<Option<&'a str>>,
     {
-        let args = Query::with("id", self.id)
+        let args = Query::with("id", self.id.as_str())
             .arg("time", time.into())
             .arg("submission", now_playing.into().map(|b| !b))
             .build();
-        client.get("scrobble", args)?;
+        client.get_value("scrobble", args)?;
         Ok(())
     }
 }
 
 impl Annotatable for Song {
     fn star(&self, client: &Client) -> Result<()> {
-        client.get("star", Query::with("id", self.id))?;
+        client.get_value("star", Query::with("id", self.id.as_str()))?;
         Ok(())
     }
 
     fn unstar(&self, client: &Client) -> Result<()> {
-        client.get("unstar", Query::with("id", self.id))?;
+        client.get_value("unstar", Query::with("id", self.id.as_str()))?;
         Ok(())
     }
 
@@ -121,8 +154,10 @@ impl Annotatable for Song {
             return Err(Error::Other("rating must be between 0 and 5 inclusive"));
         }
 
-        let args = Query::with("id", self.id).arg("rating", rating).build();
-        client.get("setRating", args)?;
+        let args = Query::with("id", self.id.as_str())
+            .arg("rating", rating)
+            .build();
+        client.get_value("setRating", args)?;
         Ok(())
     }
 
@@ -131,11 +166,11 @@ impl Annotatable for Song {
         B: Into<Option<bool>>,
         T: Into<Option<&'a str>>,
     {
-        let args = Query::with("id", self.id)
+        let args = Query::with("id", self.id.as_str())
             .arg("time", time.into())
             .arg("submission", now_playing.into().map(|b| !b))
             .build();
-        client.get("scrobble", args)?;
+        client.get_value("scrobble", args)?;
         Ok(())
     }
 }
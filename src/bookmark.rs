@@ -0,0 +1,129 @@
+//! Bookmark APIs.
+
+use crate::query::Query;
+use crate::{Client, Id, MediaItem, Result};
+
+/// A saved playback position in a song or video.
+///
+/// Bookmarks let a user resume long media, such as audiobooks or podcasts,
+/// where they left off, including from another client. Each user has at most
+/// one bookmark per song or video.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    /// The bookmarked position, in milliseconds from the start of the media.
+    pub position: u64,
+    /// The user the bookmark belongs to.
+    pub username: String,
+    /// A comment left with the bookmark.
+    pub comment: Option<String>,
+    /// When the bookmark was created (as an ISO8601 timestamp).
+    pub created: String,
+    /// When the bookmark was last changed (as an ISO8601 timestamp).
+    pub changed: String,
+    /// The bookmarked song or video.
+    pub entry: MediaItem,
+}
+
+impl Bookmark {
+    /// Lists all of the current user's bookmarks.
+    pub fn list(client: &Client) -> Result<Vec<Bookmark>> {
+        client.get_list("getBookmarks", Query::none(), "bookmarks", "bookmark")
+    }
+
+    /// Bookmarks a position in the song or video with the given ID, in
+    /// milliseconds from its start. An existing bookmark on the media is
+    /// replaced.
+    pub fn create<'a, I, S>(client: &Client, id: I, position: u64, comment: S) -> Result<()>
+    where
+        I: Into<Id>,
+        S: Into<Option<&'a str>>,
+    {
+        let args = Query::with("id", id.into())
+            .arg("position", position)
+            .arg("comment", comment.into())
+            .build();
+        client.get_value("createBookmark", args)?;
+        Ok(())
+    }
+
+    /// Removes the bookmark from the server.
+    pub fn delete(&self, client: &Client) -> Result<()> {
+        Bookmark::delete_id(client, self.entry.id())
+    }
+
+    /// Removes the bookmark on the song or video with the given ID.
+    pub fn delete_id<I: Into<Id>>(client: &Client, id: I) -> Result<()> {
+        client.get_value("deleteBookmark", Query::with("id", id.into()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn list_bookmarks() {
+        let body = format!(
+            r#"{{"subsonic-response": {{
+                "status": "ok",
+                "version": "1.14.0",
+                "bookmarks": {{ "bookmark": [{}] }}
+            }}}}"#,
+            raw()
+        );
+        let site = test_util::mock_server(&[test_util::json_response(&body)]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let bookmarks = Bookmark::list(&cli).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].position, 92_000);
+        assert_eq!(bookmarks[0].comment.as_deref(), Some("Chapter 3"));
+        assert_eq!(bookmarks[0].entry.id(), "27");
+    }
+
+    #[test]
+    fn create_and_delete() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok.clone(), ok]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        Bookmark::create(&cli, 27, 92_000, "Chapter 3").unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/createBookmark?"));
+        assert!(request.contains("id=27&position=92000&comment=Chapter"));
+
+        let bookmark = serde_json::from_str::<Bookmark>(&raw()).unwrap();
+        bookmark.delete(&cli).unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/deleteBookmark?"));
+        assert!(request.contains("id=27"));
+    }
+
+    fn raw() -> String {
+        r#"{
+            "position" : 92000,
+            "username" : "user",
+            "comment" : "Chapter 3",
+            "created" : "2017-06-01T19:48:25.635Z",
+            "changed" : "2017-06-02T08:12:05.112Z",
+            "entry" : {
+                "id" : "27",
+                "parent" : "25",
+                "isDir" : false,
+                "title" : "Bellevue Avenue",
+                "size" : 5400185,
+                "contentType" : "audio/mpeg",
+                "suffix" : "mp3",
+                "duration" : 198,
+                "path" : "Misteur Valaire/Bellevue/01 - Misteur Valaire - Bellevue Avenue.mp3",
+                "type" : "music"
+            }
+        }"#
+        .to_string()
+    }
+}
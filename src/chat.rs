@@ -0,0 +1,69 @@
+//! Chat APIs.
+
+use crate::query::Query;
+use crate::{Client, Result};
+
+/// A message posted to the server's chat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// The user who posted the message.
+    pub username: String,
+    /// When the message was posted, in milliseconds since the Unix epoch.
+    pub time: u64,
+    /// The text of the message.
+    pub message: String,
+}
+
+impl ChatMessage {
+    /// Lists the messages in the chat.
+    ///
+    /// If `since` is given (in milliseconds since the Unix epoch), only
+    /// messages posted after it are returned, which suits polling for new
+    /// messages.
+    pub fn list<U>(client: &Client, since: U) -> Result<Vec<ChatMessage>>
+    where
+        U: Into<Option<u64>>,
+    {
+        client.get_list(
+            "getChatMessages",
+            Query::with("since", since.into()),
+            "chatMessages",
+            "chatMessage",
+        )
+    }
+
+    /// Posts a message to the chat as the current user.
+    pub fn send(client: &Client, message: &str) -> Result<()> {
+        client.get_value("addChatMessage", Query::with("message", message))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn list_messages() {
+        let body = r#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.14.0",
+            "chatMessages": { "chatMessage": [
+                { "username": "admin", "time": 1496345525635, "message": "Hi" },
+                { "username": "guest", "time": 1496345530112, "message": "Hello" }
+            ] }
+        }}"#;
+        let (site, requests) = test_util::recording_mock_server(&[test_util::json_response(body)]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let messages = ChatMessage::list(&cli, 1_496_345_500_000).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].username, "guest");
+        assert_eq!(messages[1].time, 1_496_345_530_112);
+        assert_eq!(messages[1].message, "Hello");
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/getChatMessages?"));
+        assert!(request.contains("since=1496345500000"));
+    }
+}
@@ -1,19 +1,53 @@
-use std::io::Read;
+use std::fmt;
+use std::io::{Read, Write};
 use std::iter;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 use md5;
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, CONTENT_DISPOSITION, CONTENT_RANGE, CONTENT_TYPE, HOST,
+    LOCATION, RANGE, RETRY_AFTER, USER_AGENT,
+};
 use reqwest::Client as ReqwestClient;
+use reqwest::Proxy;
+use reqwest::RedirectPolicy;
+use reqwest::StatusCode;
 use reqwest::Url;
+use serde::de::DeserializeOwned;
 use serde_json;
 
-use crate::media::NowPlaying;
-use crate::query::Query;
-use crate::response::Response;
-use crate::search::{SearchPage, SearchResult};
-use crate::{Error, Genre, Hls, Lyrics, MusicFolder, Result, UrlError, Version};
+use crate::media::format::ImageFormat;
+use crate::media::podcast::Episode;
+use crate::media::{CoverArt, HlsSegments, NowPlaying, StreamOptions};
+use crate::query::{encode, Query};
+use crate::response::{self, ListStream, Response};
+use crate::search::{FolderSearchResult, SearchPage, SearchResult};
+use crate::transfer::{
+    self, MediaReader, ProgressFn, ProgressReader, ThrottledReader, TransferOptions,
+};
+use crate::{
+    ApiError, Error, Genre, Hls, HlsPlaylist, Id, Indexes, Lyrics, MusicFolder, Result, Role, Song,
+    Starred, UrlError, User, Version,
+};
 
-const SALT_SIZE: usize = 36; // Minimum 6 characters.
+const SALT_SIZE: usize = 36;
+const MAX_REDIRECTS: usize = 10;
+const METADATA_TIMEOUT: Duration = Duration::from_secs(10);
+const MIN_SALT_SIZE: usize = 6;
+/// Whether a client given both a password and an API key authenticates with
+/// the key; it is unknown until the server is asked which it supports.
+const API_KEY_UNKNOWN: u8 = 0;
+const API_KEY_UNUSED: u8 = 1;
+const API_KEY_USED: u8 = 2;
+/// How long to wait before checking for API key support again after a check
+/// failed.
+const API_KEY_RETRY_DELAY: Duration = Duration::from_secs(60);
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
 
 /// A client to make requests to a Subsonic instance.
 ///
@@ -55,353 +89,3579 @@ pub struct Client {
     url: Url,
     auth: SubsonicAuth,
     reqclient: ReqwestClient,
+    transfer_client: ReqwestClient,
+    foreign_client: ReqwestClient,
+    max_redirects: usize,
+    cross_host_redirects: bool,
+    retries: usize,
+    max_transfer_rate: Option<u64>,
+    default_folder: Option<Id>,
+    progress: Option<ProgressFn>,
+    view_suffix: bool,
+    placeholder_digests: Vec<String>,
+    server_ver: Mutex<Option<Version>>,
+    negotiate: bool,
     /// Version that the `Client` supports.
     pub ver: Version,
     /// Version that the `Client` is targeting; currently only has an effect on
-    /// the authentication method.
-    pub target_ver: Version,
+    /// the authentication method. Unless set with [`with_target`], requests
+    /// target the lower of this and the server's version, once known.
+    ///
+    /// [`with_target`]: #method.with_target
+    target_ver: Mutex<Version>,
+}
+
+/// How a client proves the user's password to the server.
+///
+/// By default, the method is picked from the targeted API version: token
+/// authentication from 1.13.0, and plain passwords before that. See
+/// [`ClientBuilder::auth_method`] to pick one explicitly.
+///
+/// [`ClientBuilder::auth_method`]: ./struct.ClientBuilder.html#method.auth_method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+    /// Sends an MD5 hash of the password and a random salt, rather than the
+    /// password itself. Requires API version 1.13.0.
+    Token,
+    /// Sends the password as-is.
+    Plain,
+    /// Sends the password hex-encoded, with an `enc:` prefix.
+    ///
+    /// This keeps the password from being readable at a glance, such as in
+    /// server logs, but is trivially reversed; it is no more secure than
+    /// [`Plain`].
+    ///
+    /// [`Plain`]: #variant.Plain
+    HexEncoded,
+}
+
+/// A callback producing a token and salt for each request.
+#[derive(Clone)]
+struct TokenFn(Arc<dyn Fn() -> (String, String) + Send + Sync>);
+
+impl fmt::Debug for TokenFn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("TokenFn")
+    }
+}
+
+/// What the client authenticates the user with.
+#[derive(Debug)]
+enum Secret {
+    Password(String),
+    Token { token: String, salt: String },
+    TokenFn(TokenFn),
 }
 
 #[derive(Debug)]
 struct SubsonicAuth {
     user: String,
-    password: String,
+    secret: Secret,
+    method: Option<AuthMethod>,
+    api_key: Option<String>,
+    api_key_state: AtomicU8,
+    /// When a failed API key check may be tried again. Held while checking,
+    /// so that other requests wait for the result.
+    api_key_retry: Mutex<Option<Instant>>,
+    salt_size: usize,
+    client_name: String,
 }
 
 impl SubsonicAuth {
-    fn new(user: &str, password: &str) -> SubsonicAuth {
+    fn new(
+        user: &str,
+        secret: Secret,
+        method: Option<AuthMethod>,
+        api_key: Option<String>,
+    ) -> SubsonicAuth {
+        // Without a password there is nothing to fall back to, so the key is
+        // used straight away.
+        let api_key_state = match (&api_key, &secret) {
+            (None, _) => API_KEY_UNUSED,
+            (Some(_), Secret::Password(password)) if password.is_empty() => API_KEY_USED,
+            (Some(_), _) => API_KEY_UNKNOWN,
+        };
         SubsonicAuth {
             user: user.into(),
-            password: password.into(),
+            secret,
+            method,
+            api_key,
+            api_key_state: AtomicU8::new(api_key_state),
+            api_key_retry: Mutex::new(None),
+            salt_size: SALT_SIZE,
+            client_name: env!("CARGO_PKG_NAME").into(),
         }
     }
 
-    fn to_url(&self, ver: Version) -> String {
-        // First md5 support.
-        let auth = if ver >= "1.13.0".into() {
-            let mut rng = thread_rng();
-            let salt: String = iter::repeat(())
-                .map(|()| rng.sample(Alphanumeric))
-                .take(SALT_SIZE)
-                .collect();
-            let pre_t = self.password.to_string() + &salt;
-            let token = format!("{:x}", md5::compute(pre_t.as_bytes()));
+    fn uses_api_key(&self) -> bool {
+        self.api_key_state.load(Ordering::SeqCst) == API_KEY_USED
+    }
+
+    fn method(&self, ver: Version) -> AuthMethod {
+        self.method.unwrap_or_else(|| {
+            // First md5 support.
+            if ver >= "1.13.0".into() {
+                AuthMethod::Token
+            } else {
+                AuthMethod::Plain
+            }
+        })
+    }
 
-            format!("u={u}&t={t}&s={s}", u = self.user, t = token, s = salt)
+    /// Builds the common query string, authenticating as appropriate for
+    /// `ver` and advertising `advertised` as the API version.
+    fn to_url(&self, ver: Version, advertised: Version) -> String {
+        let auth = if let (true, Some(key)) = (self.uses_api_key(), self.api_key.as_ref()) {
+            // OpenSubsonic API keys replace the username entirely.
+            format!("apiKey={}", encode(key))
         } else {
-            format!("u={u}&p={p}", u = self.user, p = self.password)
+            match self.secret {
+                Secret::Token {
+                    ref token,
+                    ref salt,
+                } => format!(
+                    "u={u}&t={t}&s={s}",
+                    u = encode(&self.user),
+                    t = encode(token),
+                    s = encode(salt)
+                ),
+                Secret::TokenFn(ref f) => {
+                    let (token, salt) = (f.0)();
+                    format!(
+                        "u={u}&t={t}&s={s}",
+                        u = encode(&self.user),
+                        t = encode(&token),
+                        s = encode(&salt)
+                    )
+                }
+                Secret::Password(ref password) => self.password_auth(password, ver),
+            }
         };
 
         let format = "json";
-        let crate_name = env!("CARGO_PKG_NAME");
 
         format!(
             "{auth}&v={v}&c={c}&f={f}",
             auth = auth,
-            v = ver,
-            c = crate_name,
+            v = advertised,
+            c = encode(&self.client_name),
             f = format
         )
     }
+
+    fn password_auth(&self, password: &str, ver: Version) -> String {
+        match self.method(ver) {
+            AuthMethod::Token => {
+                // A new salt is generated for every request.
+                let mut rng = thread_rng();
+                let salt: String = iter::repeat(())
+                    .map(|()| rng.sample(Alphanumeric))
+                    .take(self.salt_size)
+                    .collect();
+                let pre_t = password.to_string() + &salt;
+                let token = format!("{:x}", md5::compute(pre_t.as_bytes()));
+
+                format!(
+                    "u={u}&t={t}&s={s}",
+                    u = encode(&self.user),
+                    t = token,
+                    s = salt
+                )
+            }
+            AuthMethod::HexEncoded => {
+                let hex: String = password.bytes().map(|b| format!("{:02x}", b)).collect();
+                format!("u={u}&p=enc:{p}", u = encode(&self.user), p = hex)
+            }
+            AuthMethod::Plain => {
+                format!("u={u}&p={p}", u = encode(&self.user), p = encode(password))
+            }
+        }
+    }
 }
 
-impl Client {
-    /// Constructs a client to interact with a Subsonic instance.
-    pub fn new(url: &str, user: &str, password: &str) -> Result<Client> {
-        let auth = SubsonicAuth::new(user, password);
-        let url = url.parse::<Url>()?;
-        let ver = Version::from("1.14.0");
-        let target_ver = ver;
+/// A builder for a [`Client`] that needs more configuration than
+/// [`Client::new`] provides.
+///
+/// # Examples
+///
+/// Connecting to a server by IP address that presents a certificate for its
+/// hostname:
+///
+/// ```no_run
+/// use sunk::Client;
+/// # fn run() -> sunk::Result<()> {
+///
+/// let client = Client::builder("https://192.168.1.20", "user", "password")
+///     .host_header("music.example.com")
+///     .danger_accept_invalid_hostnames(true)
+///     .build()?;
+/// client.ping()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Client`]: ./struct.Client.html
+/// [`Client::new`]: ./struct.Client.html#method.new
+#[derive(Debug)]
+pub struct ClientBuilder {
+    url: String,
+    user: String,
+    secret: Secret,
+    auth_method: Option<AuthMethod>,
+    api_key: Option<String>,
+    host: Option<String>,
+    headers: HeaderMap,
+    invalid_header: Option<&'static str>,
+    user_agent: Option<String>,
+    client_name: Option<String>,
+    proxies: Vec<String>,
+    root_certificates: Vec<Vec<u8>>,
+    accept_invalid_certs: bool,
+    accept_invalid_hostnames: bool,
+    max_redirects: usize,
+    cross_host_redirects: bool,
+    retries: usize,
+    connect_timeout: Option<Duration>,
+    metadata_timeout: Option<Duration>,
+    transfer_timeout: Option<Duration>,
+    max_transfer_rate: Option<u64>,
+    http_client: Option<ReqwestClient>,
+    default_folder: Option<Id>,
+}
 
-        let reqclient = ReqwestClient::builder().build()?;
+impl ClientBuilder {
+    /// Starts building a client for the Subsonic instance at `url`.
+    pub fn new(url: &str, user: &str, password: &str) -> ClientBuilder {
+        ClientBuilder {
+            url: url.into(),
+            user: user.into(),
+            secret: Secret::Password(password.into()),
+            auth_method: None,
+            api_key: None,
+            host: None,
+            headers: HeaderMap::new(),
+            invalid_header: None,
+            user_agent: None,
+            client_name: None,
+            proxies: Vec::new(),
+            root_certificates: Vec::new(),
+            accept_invalid_certs: false,
+            accept_invalid_hostnames: false,
+            default_folder: None,
+            max_redirects: MAX_REDIRECTS,
+            cross_host_redirects: false,
+            retries: 0,
+            connect_timeout: None,
+            metadata_timeout: Some(METADATA_TIMEOUT),
+            transfer_timeout: None,
+            max_transfer_rate: None,
+            http_client: None,
+        }
+    }
 
-        Ok(Client {
-            url,
-            auth,
-            reqclient,
-            ver,
-            target_ver,
-        })
+    /// Sets how the password is sent to the server, rather than picking a
+    /// method from the targeted API version.
+    ///
+    /// Servers older than 1.13.0 don't support [`AuthMethod::Token`], and
+    /// some servers backed by LDAP can't check tokens at all, so need one of
+    /// the other methods.
+    ///
+    /// [`AuthMethod::Token`]: ./enum.AuthMethod.html#variant.Token
+    pub fn auth_method(mut self, method: AuthMethod) -> ClientBuilder {
+        self.auth_method = Some(method);
+        self
     }
 
-    /// Adjusts the client to target a specific version.
+    /// Authenticates with a precomputed token and salt, rather than the
+    /// password given to the builder.
     ///
-    /// By default, the client will target version 1.14.0, as built by `sunk`.
-    /// However, this means that any servers that don't implement advanced
-    /// features that `sunk` does automatically, such as token-based
-    /// authentication, will be incompatible. The target version allows setting
-    /// an override on these features by making the client limit itself to
-    /// features that the target will support.
+    /// The token is the MD5 hash of the password followed by the salt, as a
+    /// lowercase hex string. This lets the password itself stay out of the
+    /// application, such as in a keyring. The same salt is sent with every
+    /// request; servers that refuse reused salts need [`token_fn`] instead.
     ///
-    /// Note that (currently) the client does not provide any sanity-checking
-    /// on which methods are called; attempting to access an endpoint not
-    /// supported by the server will fail after the call, not before.
-    pub fn with_target(self, ver: Version) -> Client {
-        let mut cli = self;
-        cli.target_ver = ver;
-        cli
+    /// Token authentication requires API version 1.13.0, and is used
+    /// regardless of the targeted version or [`auth_method`].
+    ///
+    /// [`token_fn`]: #method.token_fn
+    /// [`auth_method`]: #method.auth_method
+    pub fn token(mut self, token: &str, salt: &str) -> ClientBuilder {
+        self.secret = Secret::Token {
+            token: token.into(),
+            salt: salt.into(),
+        };
+        self
     }
 
-    /// Internal helper function to construct a URL when the actual fetching is
-    /// not required.
-    #[cfg_attr(feature = "cargo-clippy", allow(clippy::needless_pass_by_value))]
-    pub(crate) fn build_url(&self, query: &str, args: Query) -> Result<String> {
-        let scheme = self.url.scheme();
-        let addr = self.url.host_str().ok_or(Error::Url(UrlError::Address))?;
-        let path = self.url.path();
+    /// Authenticates with a token and salt produced by `f` for each request,
+    /// rather than the password given to the builder.
+    ///
+    /// `f` returns the token and salt as described in [`token`]. It is
+    /// called whenever a request URL is built, so it can hand out a fresh
+    /// salt each time, hashing the password wherever it is stored.
+    ///
+    /// [`token`]: #method.token
+    pub fn token_fn<F>(mut self, f: F) -> ClientBuilder
+    where
+        F: Fn() -> (String, String) + Send + Sync + 'static,
+    {
+        self.secret = Secret::TokenFn(TokenFn(Arc::new(f)));
+        self
+    }
 
-        let mut url = [scheme, "://", addr, path, "/rest/"].concat();
-        url.push_str(query);
-        url.push('?');
-        url.push_str(&self.auth.to_url(self.target_ver));
-        url.push('&');
-        url.push_str(&args.to_string());
+    /// Authenticates with an OpenSubsonic API key.
+    ///
+    /// If the builder was given no password, the key is always used, and the
+    /// username is not sent. Otherwise, the client asks the server which
+    /// extensions it supports before its first request, and switches to the
+    /// key if the server advertises API key authentication. Until then, and
+    /// on servers without it, the username and password are used, including
+    /// in URLs built without a request, such as by `stream_url`. See
+    /// [`Client::detect_auth`].
+    ///
+    /// [`Client::detect_auth`]: ./struct.Client.html#method.detect_auth
+    pub fn api_key(mut self, key: &str) -> ClientBuilder {
+        self.api_key = Some(key.into());
+        self
+    }
 
-        Ok(url)
+    /// Sends `host` as the `Host` header of every request, rather than the
+    /// host in the server URL.
+    ///
+    /// This is useful when a reverse proxy routes on the hostname but the
+    /// server is reached by IP address. The header is not part of URLs
+    /// returned by methods such as `stream_url`, so players fetching those
+    /// URLs themselves will not send it.
+    ///
+    /// The TLS handshake still uses the host in the server URL; pair this with
+    /// [`danger_accept_invalid_hostnames`] if the proxy's certificate is only
+    /// valid for `host`.
+    ///
+    /// [`danger_accept_invalid_hostnames`]: #method.danger_accept_invalid_hostnames
+    pub fn host_header(mut self, host: &str) -> ClientBuilder {
+        self.host = Some(host.into());
+        self
     }
 
-    /// Issues a request to the Subsonic server.
+    /// Sends a header with every request.
     ///
-    /// A query should be one documented in the [official API].
+    /// This is useful for deployments behind a proxy that expects extra
+    /// headers, such as `X-Forwarded-For` or a token for an authenticating
+    /// proxy, or for adding tracing headers. Setting the same header again
+    /// replaces its value.
     ///
-    /// [official API]: http://www.subsonic.org/pages/api.jsp
+    /// Subsonic authentication is still sent in the query string of each
+    /// request; these headers are sent in addition to it. As with
+    /// [`host_header`], they are not part of URLs returned for other programs
+    /// to fetch, such as from `stream_url`.
     ///
     /// # Errors
     ///
-    /// Will return an error if any of the following occurs:
+    /// An invalid header name or value causes [`build`] to fail.
     ///
-    /// - server is built with an incomplete URL
-    /// - connecting to the server fails
-    /// - the server returns an API error
-    pub(crate) fn get(&self, query: &str, args: Query) -> Result<serde_json::Value> {
-        let uri: Url = self.build_url(query, args)?.parse().unwrap();
-
-        info!("Connecting to {}", uri);
-        let mut res = self.reqclient.get(uri).send()?;
-
-        if res.status().is_success() {
-            let response = res.json::<Response>()?;
-            if response.is_ok() {
-                Ok(match response.into_value() {
-                    Some(v) => v,
-                    None => serde_json::Value::Null,
-                })
-            } else {
-                Err(response
-                    .into_error()
-                    .map(|e| e.into())
-                    .ok_or(Error::Other("unable to retrieve error"))?)
+    /// [`host_header`]: #method.host_header
+    /// [`build`]: #method.build
+    pub fn default_header(mut self, name: &str, value: &str) -> ClientBuilder {
+        let name = match HeaderName::from_bytes(name.as_bytes()) {
+            Ok(name) => name,
+            Err(_) => {
+                self.invalid_header = Some("invalid header name");
+                return self;
             }
-        } else {
-            Err(Error::Connection(res.status()))
+        };
+        match HeaderValue::from_str(value) {
+            Ok(value) => {
+                self.headers.insert(name, value);
+            }
+            Err(_) => self.invalid_header = Some("invalid header value"),
         }
+        self
     }
 
-    /// Fetches an unprocessed response from the server rather than a JSON- or
-    /// XML-parsed one.
-    pub(crate) fn get_raw(&self, query: &str, args: Query) -> Result<String> {
-        let uri: Url = self.build_url(query, args)?.parse().unwrap();
-        let mut res = self.reqclient.get(uri).send()?;
-        Ok(res.text()?)
+    /// Sends all of `headers` with every request.
+    ///
+    /// This behaves as calling [`default_header`] for each header in the map,
+    /// and suits headers that are already built, such as basic
+    /// authentication for a proxy in front of the server. The headers are
+    /// sent with every request made through the client, including streams
+    /// and cover art.
+    ///
+    /// [`default_header`]: #method.default_header
+    pub fn default_headers(mut self, headers: HeaderMap) -> ClientBuilder {
+        let mut name = None;
+        for (key, value) in headers {
+            // Repeated values of a header come without a name.
+            if let Some(key) = key {
+                self.headers.remove(&key);
+                name = Some(key);
+            }
+            if let Some(ref name) = name {
+                self.headers.append(name.clone(), value);
+            }
+        }
+        self
     }
 
-    /// Returns a response as a vector of bytes rather than serialising it.
-    pub(crate) fn get_bytes(&self, query: &str, args: Query) -> Result<Vec<u8>> {
-        let uri: Url = self.build_url(query, args)?.parse().unwrap();
-        let res = self.reqclient.get(uri).send()?;
-        Ok(res.bytes().map(|b| b.unwrap()).collect())
+    /// Sets the `User-Agent` header sent with every request.
+    ///
+    /// Servers such as Navidrome show the user agent when listing active
+    /// players, so apps may want to identify themselves here.
+    pub fn user_agent(mut self, agent: &str) -> ClientBuilder {
+        self.user_agent = Some(agent.into());
+        self
     }
 
-    /// Returns the raw bytes of a HLS slice.
-    pub fn hls_bytes(&self, hls: &Hls) -> Result<Vec<u8>> {
-        let url: Url = self.url.join(&hls.url)?;
-        let res = self.reqclient.get(url).send()?;
-        Ok(res.bytes().map(|b| b.unwrap()).collect())
+    /// Sets the client name sent as the `c` parameter of every request.
+    /// Defaults to `sunk`.
+    ///
+    /// See [`Client::with_client_name`].
+    ///
+    /// [`Client::with_client_name`]: ./struct.Client.html#method.with_client_name
+    pub fn client_name(mut self, name: &str) -> ClientBuilder {
+        self.client_name = Some(name.into());
+        self
     }
 
-    /// Tests a connection with the server.
-    pub fn ping(&self) -> Result<()> {
-        self.get("ping", Query::none())?;
-        Ok(())
+    /// Sends all requests, whether `http` or `https`, through the proxy at
+    /// `url`. If more than one proxy is added, each request goes through the
+    /// first one added that matches the scheme of its URL; the others are not
+    /// tried if it fails.
+    ///
+    /// Proxies given in the `HTTP_PROXY` family of environment variables are
+    /// not used unless set here.
+    ///
+    /// # Errors
+    ///
+    /// An invalid proxy URL causes [`build`] to fail.
+    ///
+    /// [`build`]: #method.build
+    pub fn proxy(mut self, url: &str) -> ClientBuilder {
+        self.proxies.push(url.into());
+        self
     }
 
-    /// Get details about the software license. Note that access to the REST API
-    /// requires that the server has a valid license (after a 30-day trial
-    /// period). To get a license key you must upgrade to Subsonic Premium.
+    /// Trusts a PEM-encoded certificate authority in addition to the system's
+    /// trusted roots.
     ///
-    /// Forks of Subsonic (Libresonic, Airsonic, etc.) do not require licenses;
-    /// this method will always return a valid license and trial when attempting
-    /// to connect to these services.
-    pub fn check_license(&self) -> Result<License> {
-        let res = self.get("getLicense", Query::none())?;
-        Ok(serde_json::from_value::<License>(res)?)
+    /// This is the safe way to connect to a server with a self-signed
+    /// certificate: pass the certificate itself (or the private authority
+    /// that signed it) here, rather than disabling verification.
+    ///
+    /// # Errors
+    ///
+    /// An invalid certificate causes [`build`] to fail, as does building
+    /// without either TLS feature enabled.
+    ///
+    /// [`build`]: #method.build
+    pub fn add_root_certificate(mut self, pem: &[u8]) -> ClientBuilder {
+        self.root_certificates.push(pem.to_vec());
+        self
     }
 
-    /// Initiates a rescan of the media libraries.
+    /// Controls whether invalid TLS certificates are accepted.
     ///
-    /// # Note
+    /// This disables certificate verification entirely: expired, self-signed
+    /// and mismatched certificates are all accepted, so anyone able to
+    /// intercept traffic to the server can read the credentials sent with
+    /// every request. Prefer [`add_root_certificate`] for self-signed
+    /// certificates.
     ///
-    /// This method was introduced in version 1.15.0. It will not be supported
-    /// on servers with earlier versions of the Subsonic API.
-    pub fn scan_library(&self) -> Result<()> {
-        self.get("startScan", Query::none())?;
-        Ok(())
+    /// [`add_root_certificate`]: #method.add_root_certificate
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> ClientBuilder {
+        self.accept_invalid_certs = accept;
+        self
     }
 
-    /// Gets the status of a scan. Returns the current status for media library
-    /// scanning.
+    /// Controls whether TLS certificates are accepted when their hostname
+    /// does not match the server URL.
     ///
-    /// # Note
+    /// The certificate must still be signed by a trusted authority, but any
+    /// valid certificate for *any* hostname will be accepted. Anyone able to
+    /// intercept traffic to the server and holding such a certificate can
+    /// read the credentials sent with every request. Only enable this on
+    /// networks you trust, and prefer addressing the server by the hostname
+    /// on its certificate where possible.
     ///
-    /// This method was introduced in version 1.15.0. It will not be supported
-    /// on servers with earlier versions of the Subsonic API.
-    pub fn scan_status(&self) -> Result<(bool, u64)> {
-        let res = self.get("getScanStatus", Query::none())?;
-
-        #[derive(Deserialize)]
-        struct ScanStatus {
-            count: u64,
-            scanning: bool,
-        }
-        let sc = serde_json::from_value::<ScanStatus>(res)?;
-
-        Ok((sc.scanning, sc.count))
+    /// # Errors
+    ///
+    /// Only supported with the `native-tls` feature; [`build`] fails
+    /// otherwise.
+    ///
+    /// [`build`]: #method.build
+    pub fn danger_accept_invalid_hostnames(mut self, accept: bool) -> ClientBuilder {
+        self.accept_invalid_hostnames = accept;
+        self
     }
 
-    /// Returns all configured top-level music folders.
-    pub fn music_folders(&self) -> Result<Vec<MusicFolder>> {
-        #[allow(non_snake_case)]
-        let musicFolder = self.get("getMusicFolders", Query::none())?;
-
-        Ok(get_list_as!(musicFolder, MusicFolder))
+    /// Sets the maximum number of redirects followed for a single request.
+    /// Defaults to 10; setting it to zero disables following redirects.
+    ///
+    /// Redirects are followed by `sunk` rather than the underlying HTTP client
+    /// so that the authentication parameters are carried over when the server
+    /// (or a proxy in front of it) redirects to a new path or from `http` to
+    /// `https`. This only applies to requests made through the `Client`; URLs
+    /// handed out for other programs to fetch, such as from `stream_url`, are
+    /// not followed and will be redirected by whatever fetches them.
+    pub fn max_redirects(mut self, max: usize) -> ClientBuilder {
+        self.max_redirects = max;
+        self
     }
 
-    /// Returns all genres.
-    pub fn genres(&self) -> Result<Vec<Genre>> {
-        let genre = self.get("getGenres", Query::none())?;
-
-        Ok(get_list_as!(genre, Genre))
+    /// Controls whether redirects to a different host are followed.
+    ///
+    /// By default, a redirect away from the server's host is refused with an
+    /// error, as following it would send the user's credentials to the new
+    /// host. Only enable this if you trust where the server redirects to.
+    pub fn danger_follow_cross_host_redirects(mut self, follow: bool) -> ClientBuilder {
+        self.cross_host_redirects = follow;
+        self
     }
 
-    /// Returns all currently playing media on the server.
-    pub fn now_playing(&self) -> Result<Vec<NowPlaying>> {
-        let entry = self.get("getNowPlaying", Query::none())?;
-        Ok(get_list_as!(entry, NowPlaying))
+    /// Sets how many times a request is retried when the server responds with
+    /// `503 Service Unavailable`. Defaults to zero, which disables retrying.
+    ///
+    /// Servers that are rate limited, or busy scanning their library, may
+    /// answer with a `Retry-After` header saying how long to wait, either in
+    /// seconds or as a date. Otherwise, the wait starts at half a second and
+    /// doubles with each retry. Waits are capped at a minute. The request
+    /// blocks while waiting.
+    pub fn max_retries(mut self, retries: usize) -> ClientBuilder {
+        self.retries = retries;
+        self
     }
 
-    /// Searches for lyrics matching the artist and title. Returns `None` if no
-    /// lyrics are found.
-    pub fn lyrics<'a, S>(&self, artist: S, title: S) -> Result<Option<Lyrics>>
+    /// Sets the timeout for connecting to the server, or `None` to only be
+    /// bounded by the timeout of the request being made. Defaults to the
+    /// metadata timeout.
+    pub fn connect_timeout<D>(mut self, timeout: D) -> ClientBuilder
     where
-        S: Into<Option<&'a str>>,
+        D: Into<Option<Duration>>,
     {
-        let args = Query::with("artist", artist.into())
-            .arg("title", title.into())
-            .build();
-        let res = self.get("getLyrics", args)?;
-
-        if res.get("value").is_some() {
-            Ok(Some(serde_json::from_value(res)?))
-        } else {
-            Ok(None)
-        }
+        self.connect_timeout = timeout.into();
+        self
     }
 
-    /// Returns albums, artists and songs matching the given search criteria.
-    /// Supports paging through the result. See the [search module] for
-    /// documentation.
-    ///
-    /// [search module]: ./search/index.html
+    /// Sets the timeout for metadata requests, or `None` to wait indefinitely.
+    /// Defaults to 10 seconds.
     ///
-    /// # Examples
+    /// Metadata requests are those answered with a small JSON or text
+    /// response: every API call returning parsed data, as well as fetching
+    /// captions and HLS playlists.
+    pub fn metadata_timeout<D>(mut self, timeout: D) -> ClientBuilder
+    where
+        D: Into<Option<Duration>>,
+    {
+        self.metadata_timeout = timeout.into();
+        self
+    }
+
+    /// Sets the timeout for media transfers, or `None` to wait indefinitely.
+    /// Defaults to no timeout.
     ///
-    /// Basic usage:
+    /// Transfers are requests for media or images: streaming, downloading,
+    /// cover art, avatars and HLS segments. The timeout covers the entire
+    /// transfer, so should allow for the largest file expected over the
+    /// slowest connection. Connecting to the server is still bounded by the
+    /// metadata timeout, so an unreachable server fails quickly either way.
+    pub fn transfer_timeout<D>(mut self, timeout: D) -> ClientBuilder
+    where
+        D: Into<Option<Duration>>,
+    {
+        self.transfer_timeout = timeout.into();
+        self
+    }
+
+    /// Limits media transfers to `kib_per_sec` KiB per second, or `None` for
+    /// no limit. Defaults to no limit.
     ///
-    /// ```no_run
-    /// use sunk::search::{self, SearchPage};
-    /// use sunk::Client;
+    /// This keeps background transfers, such as syncing a library, from
+    /// saturating the connection. The limit applies to each transfer
+    /// separately, so several running at once (as with a [`Downloader`])
+    /// can use more in total. It covers streaming and downloading media,
+    /// including ranged reads from seekable streams and HLS segments. Images,
+    /// such as cover art and avatars, are never limited, whichever method
+    /// fetches them. A single transfer can set its own limit with
+    /// [`TransferOptions::max_rate`].
     ///
-    /// # fn run() -> sunk::Result<()> {
-    /// # let site = "http://demo.subsonic.org";
-    /// # let user = "guest3";
-    /// # let password = "guest";
-    /// let client = Client::new(site, user, password)?;
+    /// [`Downloader`]: ./transfer/struct.Downloader.html
+    /// [`TransferOptions::max_rate`]: ./transfer/struct.TransferOptions.html#method.max_rate
+    pub fn max_transfer_rate<R>(mut self, kib_per_sec: R) -> ClientBuilder
+    where
+        R: Into<Option<u64>>,
+    {
+        self.max_transfer_rate = kib_per_sec.into().map(|r| r * 1024);
+        self
+    }
+
+    /// Uses an already configured HTTP client for all requests.
     ///
-    /// let search_size = SearchPage::new();
-    /// let ignore = search::NONE;
+    /// This allows configuration that the builder does not offer, such as
+    /// custom root certificates. The client is used as-is: the timeouts,
+    /// headers, user agent, proxies and hostname verification set on this
+    /// builder are ignored.
     ///
-    /// let result = client.search("smile", ignore, ignore, search_size)?;
+    /// `sunk` follows redirects itself (see [`max_redirects`]), so the client
+    /// should be built with `RedirectPolicy::none()`; otherwise it will
+    /// follow redirects without carrying over authentication.
     ///
-    /// assert!(result.artists.is_empty());
-    /// assert!(result.albums.is_empty());
-    /// assert!(!result.songs.is_empty());
-    /// # Ok(())
-    /// # }
-    /// # fn main() { }
-    /// ```
-    pub fn search(
-        &self,
-        query: &str,
-        artist_page: SearchPage,
-        album_page: SearchPage,
-        song_page: SearchPage,
-    ) -> Result<SearchResult> {
-        // FIXME There has to be a way to make this nicer.
-        let args = Query::with("query", query)
-            .arg("artistCount", artist_page.count)
-            .arg("artistOffset", artist_page.offset)
-            .arg("albumCount", album_page.count)
-            .arg("albumOffset", album_page.offset)
-            .arg("songCount", song_page.count)
-            .arg("songOffset", song_page.offset)
-            .build();
-
-        let res = self.get("search3", args)?;
-        Ok(serde_json::from_value::<SearchResult>(res)?)
+    /// [`max_redirects`]: #method.max_redirects
+    pub fn http_client(mut self, client: ReqwestClient) -> ClientBuilder {
+        self.http_client = Some(client);
+        self
     }
 
-    /// Returns a list of all starred artists, albums, and songs.
-    pub fn starred<U>(&self, folder_id: U) -> Result<SearchResult>
+    /// Sets the music folder requests are scoped to by default. See
+    /// [`Client::with_default_folder`] for the endpoints it applies to.
+    ///
+    /// [`Client::with_default_folder`]: ./struct.Client.html#method.with_default_folder
+    pub fn default_folder<U>(mut self, folder_id: U) -> ClientBuilder
     where
-        U: Into<Option<usize>>,
+        U: Into<Option<Id>>,
     {
-        let res = self.get("getStarred", Query::with("musicFolderId", folder_id.into()))?;
-        Ok(serde_json::from_value::<SearchResult>(res)?)
+        self.default_folder = folder_id.into();
+        self
     }
-}
 
-/// A representation of a license associated with a server.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct License {
-    /// Whether the license is valid or not.
-    pub valid: bool,
-    /// The email associated with the email.
-    pub email: String,
-    /// An ISO8601 timestamp of the server's trial expiry.
-    pub trial_expires: Option<String>,
-    /// An ISO8601 timestamp of the server's license expiry. Servers still in
-    /// the trial phase typically will not have this field.
-    pub license_expires: Option<String>,
+    /// Builds the client.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the URL, any header or any proxy is invalid, or if the
+    /// underlying HTTP client cannot be initialised.
+    pub fn build(self) -> Result<Client> {
+        let url = self.url.parse::<Url>()?;
+        let ver = Version::from("1.14.0");
+        let target_ver = ver;
+
+        let (reqclient, transfer_client, foreign_client) = match self.http_client {
+            Some(ref client) => (client.clone(), client.clone(), client.clone()),
+            None => (
+                self.new_http_client(self.metadata_timeout, true)?,
+                self.new_http_client(self.transfer_timeout, true)?,
+                self.new_http_client(self.transfer_timeout, false)?,
+            ),
+        };
+        let mut auth = SubsonicAuth::new(&self.user, self.secret, self.auth_method, self.api_key);
+        if let Some(name) = self.client_name {
+            auth.client_name = name;
+        }
+
+        Ok(Client {
+            url,
+            auth,
+            reqclient,
+            transfer_client,
+            foreign_client,
+            max_redirects: self.max_redirects,
+            cross_host_redirects: self.cross_host_redirects,
+            retries: self.retries,
+            max_transfer_rate: self.max_transfer_rate,
+            default_folder: self.default_folder,
+            progress: None,
+            view_suffix: false,
+            placeholder_digests: Vec::new(),
+            server_ver: Mutex::new(None),
+            negotiate: true,
+            ver,
+            target_ver: Mutex::new(target_ver),
+        })
+    }
+
+    /// Builds an HTTP client for the server, or, without `for_server`, one for
+    /// other hosts that leaves out the host override and default headers and
+    /// follows redirects itself.
+    fn new_http_client(
+        &self,
+        timeout: Option<Duration>,
+        for_server: bool,
+    ) -> Result<ReqwestClient> {
+        let redirects = if for_server {
+            RedirectPolicy::none()
+        } else {
+            RedirectPolicy::limited(self.max_redirects)
+        };
+        let mut builder = ReqwestClient::builder()
+            .redirect(redirects)
+            .timeout(timeout);
+        if let Some(connect) = self.connect_timeout.or(self.metadata_timeout) {
+            builder = builder.connect_timeout(connect);
+        }
+        for proxy in &self.proxies {
+            builder = builder.proxy(Proxy::all(proxy.as_str())?);
+        }
+        if let Some(err) = self.invalid_header {
+            return Err(Error::Other(err));
+        }
+        let mut headers = if for_server {
+            self.headers.clone()
+        } else {
+            HeaderMap::new()
+        };
+        if let (true, Some(host)) = (for_server, &self.host) {
+            let value =
+                HeaderValue::from_str(host).map_err(|_| Error::Other("invalid host header"))?;
+            headers.insert(HOST, value);
+        }
+        if let Some(ref agent) = self.user_agent {
+            let value =
+                HeaderValue::from_str(agent).map_err(|_| Error::Other("invalid user agent"))?;
+            headers.insert(USER_AGENT, value);
+        }
+        builder = builder.default_headers(headers);
+        builder = self.configure_tls(builder)?;
+        Ok(builder.build()?)
+    }
+
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    fn configure_tls(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        for pem in &self.root_certificates {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(pem)?);
+        }
+        if self.accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        if self.accept_invalid_hostnames {
+            #[cfg(feature = "native-tls")]
+            {
+                builder = builder.danger_accept_invalid_hostnames(true);
+            }
+            #[cfg(not(feature = "native-tls"))]
+            return Err(Error::Other(
+                "accepting invalid hostnames requires the native-tls feature",
+            ));
+        }
+        Ok(builder)
+    }
+
+    #[cfg(not(any(feature = "native-tls", feature = "rustls-tls")))]
+    fn configure_tls(&self, builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        if !self.root_certificates.is_empty()
+            || self.accept_invalid_certs
+            || self.accept_invalid_hostnames
+        {
+            return Err(Error::Other("TLS options require a TLS feature"));
+        }
+        Ok(builder)
+    }
+}
+
+impl Client {
+    /// Constructs a client to interact with a Subsonic instance.
+    pub fn new(url: &str, user: &str, password: &str) -> Result<Client> {
+        ClientBuilder::new(url, user, password).build()
+    }
+
+    /// Constructs a client that authenticates with a precomputed token and
+    /// salt rather than a password.
+    ///
+    /// See [`ClientBuilder::token`] for details.
+    ///
+    /// [`ClientBuilder::token`]: ./struct.ClientBuilder.html#method.token
+    pub fn with_token(url: &str, user: &str, token: &str, salt: &str) -> Result<Client> {
+        ClientBuilder::new(url, user, "").token(token, salt).build()
+    }
+
+    /// Constructs a client that authenticates with an OpenSubsonic API key
+    /// rather than a username and password.
+    ///
+    /// API keys are not part of the original Subsonic API; servers that don't
+    /// support them will reject every request.
+    pub fn with_api_key(url: &str, api_key: &str) -> Result<Client> {
+        ClientBuilder::new(url, "", "").api_key(api_key).build()
+    }
+
+    /// Returns a builder to configure a client before constructing it.
+    pub fn builder(url: &str, user: &str, password: &str) -> ClientBuilder {
+        ClientBuilder::new(url, user, password)
+    }
+
+    /// Adjusts the client to target a specific version.
+    ///
+    /// By default, the client will target version 1.14.0, as built by `sunk`.
+    /// However, this means that any servers that don't implement advanced
+    /// features that `sunk` does automatically, such as token-based
+    /// authentication, will be incompatible. The target version allows setting
+    /// an override on these features by making the client limit itself to
+    /// features that the target will support.
+    ///
+    /// Setting a target turns off version negotiation: the client keeps
+    /// targeting `ver` even after a [`ping`] reports a different server
+    /// version.
+    ///
+    /// Methods calling an endpoint introduced after the target version fail
+    /// with [`Error::Unsupported`] without making a request.
+    ///
+    /// [`ping`]: #method.ping
+    /// [`Error::Unsupported`]: ./enum.Error.html#variant.Unsupported
+    pub fn with_target(self, ver: Version) -> Client {
+        let mut cli = self;
+        cli.target_ver = Mutex::new(ver);
+        cli.negotiate = false;
+        cli
+    }
+
+    /// Returns the API version the server reported in the last [`ping`], if
+    /// the client has pinged it.
+    ///
+    /// [`ping`]: #method.ping
+    pub fn server_version(&self) -> Option<Version> {
+        *self.server_ver.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the newest API version the server is known to support: the
+    /// target set with [`with_target`], or otherwise the version reported by
+    /// the last [`ping`].
+    ///
+    /// [`with_target`]: #method.with_target
+    /// [`ping`]: #method.ping
+    fn supported_version(&self) -> Option<Version> {
+        if self.negotiate {
+            self.server_version()
+        } else {
+            Some(self.target())
+        }
+    }
+
+    /// Returns the version set with [`with_target`] or [`detect_target`], or
+    /// otherwise the version the client supports.
+    ///
+    /// [`with_target`]: #method.with_target
+    /// [`detect_target`]: #method.detect_target
+    fn target(&self) -> Version {
+        *self.target_ver.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the API version requests are made for.
+    ///
+    /// This is the version the client supports, or the one found by
+    /// [`detect_target`], lowered to the server's version once a [`ping`] has
+    /// reported it, unless the target was set with [`with_target`].
+    ///
+    /// [`detect_target`]: #method.detect_target
+    /// [`ping`]: #method.ping
+    /// [`with_target`]: #method.with_target
+    pub fn target_version(&self) -> Version {
+        match self.server_version() {
+            Some(server_ver) if self.negotiate => server_ver.min(self.target()),
+            _ => self.target(),
+        }
+    }
+
+    /// Scopes requests to a music folder by default.
+    ///
+    /// Apps that work within a single library can set its folder here rather
+    /// than passing it to every call. The folder is sent as `musicFolderId` to
+    /// the endpoints that accept one:
+    ///
+    /// - searching (`search2` and `search3`)
+    /// - album lists ([`Album::list`] and [`Album::list_directories`])
+    /// - random songs ([`Song::random`] and [`Song::random_with`])
+    /// - songs by genre ([`Song::list_in_genre`])
+    /// - starred items ([`starred`] and [`starred2`])
+    /// - the folder index ([`indexes`]) and artist index ([`Artist::list`])
+    ///
+    /// A folder passed to one of these methods overrides the default. Other
+    /// endpoints, such as [`genres`], can't be scoped to a folder, and are
+    /// unaffected. Passing `None` clears the default.
+    ///
+    /// [`Album::list`]: ./struct.Album.html#method.list
+    /// [`Album::list_directories`]: ./struct.Album.html#method.list_directories
+    /// [`Song::random`]: ./song/struct.Song.html#method.random
+    /// [`Song::random_with`]: ./song/struct.Song.html#method.random_with
+    /// [`Song::list_in_genre`]: ./song/struct.Song.html#method.list_in_genre
+    /// [`starred`]: #method.starred
+    /// [`starred2`]: #method.starred2
+    /// [`indexes`]: #method.indexes
+    /// [`Artist::list`]: ./struct.Artist.html#method.list
+    /// [`genres`]: #method.genres
+    pub fn with_default_folder<U>(self, folder_id: U) -> Client
+    where
+        U: Into<Option<Id>>,
+    {
+        let mut cli = self;
+        cli.default_folder = folder_id.into();
+        cli
+    }
+
+    /// Returns the music folder requests are scoped to by default, if any.
+    pub fn default_folder(&self) -> Option<&Id> {
+        self.default_folder.as_ref()
+    }
+
+    /// Reports the progress of downloading API responses to a callback.
+    ///
+    /// Responses for large libraries (such as listing every artist or album)
+    /// can take a while to download. The callback is called as each part of a
+    /// response is received, before it is parsed, with the number of bytes
+    /// received so far and the total size of the response. The total is
+    /// `None` if the server didn't report a `Content-Length`.
+    ///
+    /// Only responses to API calls are reported. To follow the progress of
+    /// media transfers, see [`TransferOptions::progress`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sunk::{Client, ListType};
+    /// use sunk::search;
+    /// # fn run() -> sunk::Result<()> {
+    /// # let site = "http://demo.subsonic.org";
+    /// # let user = "guest3";
+    /// # let password = "guest";
+    ///
+    /// let client = Client::new(site, user, password)?.with_response_progress(|got, total| {
+    ///     match total {
+    ///         Some(total) => println!("Downloading library... {}/{} bytes", got, total),
+    ///         None => println!("Downloading library... {} bytes", got),
+    ///     }
+    /// });
+    /// let albums = sunk::Album::list(&client, ListType::AlphaByName, search::ALL, None)?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() { }
+    /// ```
+    ///
+    /// [`TransferOptions::progress`]: ./transfer/struct.TransferOptions.html#method.progress
+    pub fn with_response_progress<F>(self, f: F) -> Client
+    where
+        F: Fn(u64, Option<u64>) + Send + Sync + 'static,
+    {
+        let mut cli = self;
+        cli.progress = Some(ProgressFn::new(f));
+        cli
+    }
+
+    /// Sets whether endpoints are requested with a `.view` suffix, as in
+    /// `/rest/ping.view`.
+    ///
+    /// Modern servers accept endpoints with or without the suffix, but some
+    /// older deployments (such as early Airsonic releases) reject requests
+    /// without it. Disabled by default. Endpoints that already have an
+    /// extension, such as `hls.m3u8`, are left unchanged.
+    pub fn with_view_suffix(self, enabled: bool) -> Client {
+        let mut cli = self;
+        cli.view_suffix = enabled;
+        cli
+    }
+
+    /// Registers an image the server returns in place of missing cover art.
+    ///
+    /// Cover art fetched with [`cover_art`] that is identical to a registered
+    /// image is reported as a placeholder. Servers differ in their
+    /// placeholders (and some resize them to the requested size), so register
+    /// each variant that should be recognised.
+    ///
+    /// [`cover_art`]: #method.cover_art
+    pub fn with_placeholder_image(self, image: &[u8]) -> Client {
+        let mut cli = self;
+        cli.placeholder_digests
+            .push(format!("{:x}", md5::compute(image)));
+        cli
+    }
+
+    /// Returns the given music folder, or the default folder if none is given.
+    pub(crate) fn folder_or_default(&self, folder_id: Option<Id>) -> Option<Id> {
+        folder_id.or_else(|| self.default_folder.clone())
+    }
+
+    /// Sets the length of the random salt used in token authentication.
+    ///
+    /// By default, the client uses a salt of 36 alphanumeric characters. The
+    /// Subsonic API requires a salt of at least six characters; shorter sizes
+    /// are raised to six.
+    pub fn with_salt_size(self, size: usize) -> Client {
+        let mut cli = self;
+        cli.auth.salt_size = size.max(MIN_SALT_SIZE);
+        cli
+    }
+
+    /// Sets the client name sent as the `c` parameter of every request.
+    /// Defaults to `sunk`.
+    ///
+    /// Servers use the name to tell apps apart: Subsonic keeps a player, with
+    /// its own transcoding settings, for each client name a user connects
+    /// with, and other servers show it when listing active players.
+    pub fn with_client_name(self, name: &str) -> Client {
+        let mut cli = self;
+        cli.auth.client_name = name.into();
+        cli
+    }
+
+    /// Returns the client name sent with every request.
+    pub fn client_name(&self) -> &str {
+        &self.auth.client_name
+    }
+
+    /// Queries the server for its API version and adjusts the client to target
+    /// it.
+    ///
+    /// This is a one-call setup helper for when the server's version isn't
+    /// known ahead of time. Servers report their version even when rejecting
+    /// authentication, so this will succeed against an older server that
+    /// doesn't support token authentication. The target is never set higher
+    /// than the version supported by `sunk`.
+    ///
+    /// Returns the version that the client now targets.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sunk::Client;
+    /// # fn run() -> sunk::Result<()> {
+    /// # let site = "http://demo.subsonic.org";
+    /// # let user = "guest3";
+    /// # let password = "guest";
+    ///
+    /// let client = Client::new(site, user, password)?;
+    /// let target = client.detect_target()?;
+    /// println!("Targeting API version {}", target);
+    /// client.ping()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn detect_target(&self) -> Result<Version> {
+        let response = self.get_response("ping", Query::none())?;
+        let server_ver = response
+            .version()
+            .ok_or(Error::Other("server did not report a version"))?;
+
+        let target_ver = server_ver.min(self.ver);
+        *self.target_ver.lock().unwrap_or_else(|e| e.into_inner()) = target_ver;
+        *self.server_ver.lock().unwrap_or_else(|e| e.into_inner()) = Some(server_ver);
+        info!("Targeting API version {}", target_ver);
+        Ok(target_ver)
+    }
+
+    /// Checks whether the server supports OpenSubsonic API keys and, if it
+    /// does, switches the client to authenticating with its key.
+    ///
+    /// This only has an effect on a client given both a password and an API
+    /// key (see [`ClientBuilder::api_key`]). Servers that don't implement
+    /// OpenSubsonic extensions are treated as not supporting API keys.
+    ///
+    /// Such a client checks automatically before its first request, and
+    /// again a minute after a check fails, so this only needs calling to
+    /// check straight away, such as after the server has been upgraded.
+    ///
+    /// Returns whether the client now authenticates with its API key.
+    ///
+    /// [`ClientBuilder::api_key`]: ./struct.ClientBuilder.html#method.api_key
+    pub fn detect_auth(&self) -> Result<bool> {
+        if self.auth.api_key.is_none() || self.auth.uses_api_key() {
+            return Ok(self.auth.uses_api_key());
+        }
+
+        let mut retry_at = self
+            .auth
+            .api_key_retry
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let res = self.check_api_key();
+        *retry_at = match res {
+            Ok(_) => None,
+            Err(_) => Some(Instant::now() + API_KEY_RETRY_DELAY),
+        };
+        res
+    }
+
+    /// Checks for API key support before a request, if the client hasn't
+    /// yet and isn't waiting to retry a failed check.
+    fn check_auth(&self) {
+        let state = &self.auth.api_key_state;
+        if state.load(Ordering::SeqCst) != API_KEY_UNKNOWN {
+            return;
+        }
+
+        // Requests made while another thread checks wait for its result.
+        let mut retry_at = self
+            .auth
+            .api_key_retry
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let waiting = retry_at.is_some_and(|at| Instant::now() < at);
+        if state.load(Ordering::SeqCst) != API_KEY_UNKNOWN || waiting {
+            return;
+        }
+        *retry_at = match self.check_api_key() {
+            Ok(_) => None,
+            Err(e) => {
+                warn!(
+                    "Unable to check for API key support; retrying in {:?}: {}",
+                    API_KEY_RETRY_DELAY, e
+                );
+                Some(Instant::now() + API_KEY_RETRY_DELAY)
+            }
+        };
+    }
+
+    /// Asks the server whether it supports API keys, and records the answer.
+    fn check_api_key(&self) -> Result<bool> {
+        // The key isn't in use yet, so the check is made with the password.
+        let uri = self
+            .build_url("getOpenSubsonicExtensions", Query::none())?
+            .parse()?;
+        let extensions = self
+            .get_url(uri, &["openSubsonicExtensions"])
+            .map(Option::unwrap_or_default);
+        let supported = capabilities_from(extensions)?.supports_api_key_auth;
+        let state = if supported {
+            API_KEY_USED
+        } else {
+            API_KEY_UNUSED
+        };
+        self.auth.api_key_state.store(state, Ordering::SeqCst);
+        info!("API key authentication in use: {}", supported);
+        Ok(supported)
+    }
+
+    /// Constructs the URL of a request about to be sent, checking for API
+    /// key support first if needed.
+    fn request_url(&self, query: &str, args: Query) -> Result<Url> {
+        self.check_auth();
+        Ok(self.build_url(query, args)?.parse()?)
+    }
+
+    /// Internal helper function to construct a URL when the actual fetching is
+    /// not required.
+    #[cfg_attr(feature = "cargo-clippy", allow(clippy::needless_pass_by_value))]
+    pub(crate) fn build_url(&self, query: &str, args: Query) -> Result<String> {
+        self.build_url_at(query, args, self.target_version())
+    }
+
+    /// Constructs a URL as [`build_url`] does, but advertising `advertised`
+    /// as the API version rather than the targeted version.
+    ///
+    /// [`build_url`]: #method.build_url
+    fn build_url_at(&self, query: &str, args: Query, advertised: Version) -> Result<String> {
+        if let (Some(required), Some(actual)) = (required_version(query), self.supported_version())
+        {
+            if actual < required {
+                return Err(Error::Unsupported {
+                    endpoint: query.into(),
+                    required,
+                    actual,
+                });
+            }
+        }
+
+        let scheme = self.url.scheme();
+        let addr = self.url.host_str().ok_or(Error::Url(UrlError::Address))?;
+        let port = self
+            .url
+            .port()
+            .map(|p| format!(":{}", p))
+            .unwrap_or_default();
+        let path = self.url.path().trim_end_matches('/');
+
+        let mut url = [scheme, "://", addr, &port, path, "/rest/"].concat();
+        url.push_str(query);
+        if self.view_suffix && !query.contains('.') {
+            url.push_str(".view");
+        }
+        url.push('?');
+        url.push_str(&self.auth.to_url(self.target_version(), advertised));
+        url.push('&');
+        url.push_str(&args.to_string());
+
+        Ok(url)
+    }
+
+    /// Issues a request to the Subsonic server.
+    ///
+    /// A query should be one documented in the [official API]. The value at
+    /// `path` within the response (e.g. `["album"]` for `getAlbum`) is
+    /// deserialized directly from the response body, and `None` is returned
+    /// if it's missing.
+    ///
+    /// [official API]: http://www.subsonic.org/pages/api.jsp
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if any of the following occurs:
+    ///
+    /// - server is built with an incomplete URL
+    /// - connecting to the server fails
+    /// - the server returns an API error
+    pub(crate) fn get<T>(&self, query: &str, args: Query, path: &[&str]) -> Result<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let uri = self.request_url(query, args)?;
+        self.get_url(uri, path)
+    }
+
+    /// Issues a request to an already built URL, as [`get`] does.
+    ///
+    /// [`get`]: #method.get
+    fn get_url<T>(&self, uri: Url, path: &[&str]) -> Result<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let res = self.send(uri, RequestKind::Metadata)?;
+        let body = self.read_body(res)?;
+        response::from_slice_at(&body, path)
+    }
+
+    /// Issues a request to the Subsonic server for a single item, which must be
+    /// present in the response.
+    ///
+    /// Some servers answer endpoints they don't support with an `ok` status
+    /// but no data. Rather than treating that as a missing value, this returns
+    /// an error; use it for endpoints that always return data on success,
+    /// such as `getAlbum`.
+    ///
+    /// # Errors
+    ///
+    /// Aside from the errors [`get`] may return, the method will return
+    /// `Error::Other("empty response")` if the value at `path` is missing.
+    ///
+    /// [`get`]: #method.get
+    pub(crate) fn get_required<T>(&self, query: &str, args: Query, path: &[&str]) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.get(query, args, path)?
+            .ok_or(Error::Other("empty response"))
+    }
+
+    /// Issues a request to the Subsonic server and deserializes a list from
+    /// the response, such as the `album` list within `albumList2`.
+    ///
+    /// A response without the list (as servers send for empty lists) results
+    /// in an empty `Vec`.
+    pub(crate) fn get_list<T>(
+        &self,
+        query: &str,
+        args: Query,
+        container: &str,
+        item: &str,
+    ) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(self
+            .get(query, args, &[container, item])?
+            .unwrap_or_default())
+    }
+
+    /// Issues a request to the Subsonic server and returns an iterator over a
+    /// list in the response, parsed as it is read.
+    ///
+    /// Connection errors are returned immediately; errors in the response
+    /// itself are returned from the iterator.
+    pub(crate) fn get_stream<T>(
+        &self,
+        query: &str,
+        args: Query,
+        path: &'static [&'static str],
+    ) -> Result<ListStream<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let uri = self.request_url(query, args)?;
+        let res = self.send(uri, RequestKind::Metadata)?;
+        Ok(match self.progress {
+            Some(ref progress) => {
+                let total = res.content_length();
+                ListStream::spawn(ProgressReader::new(res, total, progress.clone()), path)
+            }
+            None => ListStream::spawn(res, path),
+        })
+    }
+
+    /// Issues a request to the Subsonic server, returning the response body as
+    /// an untyped JSON value.
+    ///
+    /// This parses the response twice, once into a [`Response`] and again into
+    /// the final type; prefer [`get`] or [`get_list`] where the location of
+    /// the value in the response is known.
+    ///
+    /// [`Response`]: ./response/struct.Response.html
+    /// [`get`]: #method.get
+    /// [`get_list`]: #method.get_list
+    pub(crate) fn get_value(&self, query: &str, args: Query) -> Result<serde_json::Value> {
+        let response = self.get_response(query, args)?;
+        if response.is_ok() {
+            Ok(match response.into_value_for(query) {
+                Some(v) => v,
+                None => serde_json::Value::Null,
+            })
+        } else {
+            Err(response
+                .into_error()
+                .map(|e| e.into())
+                .ok_or(Error::Other("unable to retrieve error"))?)
+        }
+    }
+
+    /// Calls an endpoint, returning the response as the server sent it.
+    ///
+    /// This is intended for diagnostics, such as inspecting what a server
+    /// returns for an endpoint or including it in a bug report. The
+    /// response's status, version and every other field are available,
+    /// including fields `sunk` doesn't model. An API error in the response is
+    /// not returned as an `Err`; check [`Response::is_ok`] instead.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sunk::query::Query;
+    /// use sunk::Client;
+    /// # fn run() -> sunk::Result<()> {
+    /// # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+    ///
+    /// let res = client.raw_response("getAlbum", Query::with("id", 1))?;
+    /// println!("{} ({:?}): {:?}", res.status(), res.version(), res.values());
+    /// # Ok(())
+    /// # }
+    /// # fn main() { }
+    /// ```
+    ///
+    /// [`Response::is_ok`]: ./response/struct.Response.html#method.is_ok
+    pub fn raw_response(&self, endpoint: &str, args: Query) -> Result<Response> {
+        self.get_response(endpoint, args)
+    }
+
+    /// Calls an endpoint as [`raw_response`] does, but advertising `ver` as
+    /// the API version for this call only.
+    ///
+    /// Some servers gate features on the version a client claims to speak,
+    /// such as OpenSubsonic servers returning extra fields to clients
+    /// advertising a newer version. The client's target version, and the
+    /// authentication method picked from it, are unchanged.
+    ///
+    /// [`raw_response`]: #method.raw_response
+    pub fn raw_response_at(&self, endpoint: &str, args: Query, ver: Version) -> Result<Response> {
+        self.check_auth();
+        let uri: Url = self.build_url_at(endpoint, args, ver)?.parse().unwrap();
+        let res = self.send(uri, RequestKind::Metadata)?;
+        let body = self.read_body(res)?;
+        Ok(serde_json::from_slice::<Response>(&body)?)
+    }
+
+    /// Issues a request to the Subsonic server, returning the parsed response
+    /// whether or not it contains an API error.
+    fn get_response(&self, query: &str, args: Query) -> Result<Response> {
+        self.raw_response_at(query, args, self.target_version())
+    }
+
+    /// Reads the body of an API response, reporting progress if requested.
+    fn read_body(&self, mut res: reqwest::Response) -> Result<Vec<u8>> {
+        let mut body = Vec::new();
+        match self.progress {
+            Some(ref progress) => {
+                let total = res.content_length();
+                ProgressReader::new(res, total, progress.clone()).read_to_end(&mut body)?;
+            }
+            None => {
+                res.copy_to(&mut body)?;
+            }
+        }
+        Ok(body)
+    }
+
+    /// Sends a request to the server.
+    ///
+    /// # Errors
+    ///
+    /// Aside from connection errors, the method will return an
+    /// [`Error::Connection`] if the server responds with a non-success status.
+    /// Without the check, error pages would be treated as a valid response.
+    ///
+    /// [`Error::Connection`]: ./enum.Error.html#variant.Connection
+    fn send(&self, uri: Url, kind: RequestKind) -> Result<reqwest::Response> {
+        let res = self.execute(uri, kind, None)?;
+
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(Error::Connection(res.status()))
+        }
+    }
+
+    /// Sends a GET request, following redirects while keeping the
+    /// authentication parameters of the original request.
+    ///
+    /// The status of the final response is not checked.
+    fn execute(
+        &self,
+        mut uri: Url,
+        kind: RequestKind,
+        range: Option<String>,
+    ) -> Result<reqwest::Response> {
+        let client = match kind {
+            RequestKind::Metadata => &self.reqclient,
+            RequestKind::Transfer => &self.transfer_client,
+        };
+        let host = uri.host_str().map(String::from);
+        let query = uri.query().map(String::from);
+
+        for _ in 0..=self.max_redirects {
+            let res = self.send_with_retries(client, &uri, range.as_deref())?;
+
+            let location = match res.headers().get(LOCATION) {
+                Some(loc) if res.status().is_redirection() => loc
+                    .to_str()
+                    .map_err(|_| Error::Other("invalid redirect location"))?,
+                _ => return Ok(res),
+            };
+            let next = uri.join(location)?;
+            if next.host_str() != host.as_deref() && !self.cross_host_redirects {
+                return Err(Error::Other("server redirected to a different host"));
+            }
+            uri = with_auth_query(next, query.as_deref());
+        }
+
+        Err(Error::Other("too many redirects"))
+    }
+
+    /// Sends a GET request, retrying while the server is unavailable.
+    fn send_with_retries(
+        &self,
+        client: &ReqwestClient,
+        uri: &Url,
+        range: Option<&str>,
+    ) -> Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            info!("Connecting to {}", uri);
+            let mut req = client.get(uri.clone());
+            if let Some(range) = range {
+                req = req.header(RANGE, range);
+            }
+            let res = req.send()?;
+            if res.status() != StatusCode::SERVICE_UNAVAILABLE || attempt >= self.retries {
+                return Ok(res);
+            }
+
+            let delay = retry_after(res.headers())
+                .unwrap_or_else(|| RETRY_DELAY * 2u32.saturating_pow(attempt as u32))
+                .min(MAX_RETRY_DELAY);
+            warn!("Server unavailable; retrying in {:?}", delay);
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    /// Fetches an unprocessed response from the server rather than a JSON- or
+    /// XML-parsed one.
+    pub(crate) fn get_raw(&self, query: &str, args: Query) -> Result<String> {
+        let uri = self.request_url(query, args)?;
+        let mut res = self.send(uri, RequestKind::Metadata)?;
+        Ok(res.text()?)
+    }
+
+    /// Returns a response as a vector of bytes rather than serialising it.
+    ///
+    /// A JSON response is checked for an API error instead of being returned
+    /// as bytes.
+    pub(crate) fn get_bytes(&self, query: &str, args: Query) -> Result<Vec<u8>> {
+        self.fetch_bytes(query, args, self.max_transfer_rate)
+    }
+
+    /// Returns an image, such as cover art or an avatar, as a vector of bytes.
+    ///
+    /// Unlike [`get_bytes`], images aren't held to the client's transfer rate
+    /// limit, as they are small and usually needed straight away.
+    ///
+    /// [`get_bytes`]: #method.get_bytes
+    pub(crate) fn get_image(&self, query: &str, args: Query) -> Result<Vec<u8>> {
+        self.fetch_bytes(query, args, None)
+    }
+
+    fn fetch_bytes(&self, query: &str, args: Query, rate: Option<u64>) -> Result<Vec<u8>> {
+        let uri = self.request_url(query, args)?;
+        let mut res = self.send(uri, RequestKind::Transfer)?;
+
+        check_binary(&mut res)?;
+
+        let mut bytes = Vec::new();
+        ThrottledReader::new(res, rate).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Copies the body of a binary response into a writer, returning the
+    /// number of bytes written.
+    ///
+    /// The body is copied in chunks; if the `CancellationToken` is cancelled
+    /// between chunks, the transfer stops and returns [`Error::Cancelled`].
+    ///
+    /// [`Error::Cancelled`]: ./enum.Error.html#variant.Cancelled
+    pub(crate) fn copy_url_to<W: Write + ?Sized>(
+        &self,
+        url: &str,
+        writer: &mut W,
+        options: &TransferOptions,
+    ) -> Result<u64> {
+        let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+        let uri: Url = url.parse()?;
+        let mut res = self.send(uri, RequestKind::Transfer)?;
+        check_binary(&mut res)?;
+        let total = res.content_length();
+        let mut reader = ThrottledReader::new(res, options.max_rate.or(self.max_transfer_rate));
+        match options.progress {
+            Some(ref progress) => {
+                let mut reader = ProgressReader::new(reader, total, progress.clone());
+                transfer::copy(&mut reader, writer, options.cancel.as_ref(), deadline)
+            }
+            None => transfer::copy(&mut reader, writer, options.cancel.as_ref(), deadline),
+        }
+    }
+
+    /// Opens a binary URL for reading as the body arrives.
+    pub(crate) fn open_url(&self, url: &str) -> Result<MediaReader> {
+        let uri: Url = url.parse()?;
+        let mut res = self.send(uri, RequestKind::Transfer)?;
+        check_binary(&mut res)?;
+        Ok(MediaReader::new(res, self.max_transfer_rate))
+    }
+
+    /// Fetches an inclusive range of bytes from a binary URL.
+    ///
+    /// Returns the bytes along with the total length of the resource, as
+    /// reported by the server.
+    ///
+    /// # Errors
+    ///
+    /// Aside from the usual connection errors, the method will error if the
+    /// server does not support ranged requests for the resource. Subsonic
+    /// servers typically do not support them for transcoded streams.
+    pub(crate) fn get_range(&self, url: &str, start: u64, end: u64) -> Result<(Vec<u8>, u64)> {
+        let uri: Url = url.parse()?;
+        info!("Fetching bytes {}-{} of {}", start, end, uri);
+        let res = self.execute(
+            uri,
+            RequestKind::Transfer,
+            Some(format!("bytes={}-{}", start, end)),
+        )?;
+
+        if res.status() != StatusCode::PARTIAL_CONTENT {
+            return Err(if res.status().is_success() {
+                Error::Other("server does not support ranged requests")
+            } else {
+                Error::Connection(res.status())
+            });
+        }
+
+        // Content-Range: bytes <start>-<end>/<total>
+        let total = res
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|r| r.to_str().ok())
+            .and_then(|r| r.rsplit('/').next())
+            .and_then(|t| t.parse::<u64>().ok())
+            .ok_or(Error::Other("missing content range"))?;
+
+        let mut bytes = Vec::new();
+        ThrottledReader::new(res, self.max_transfer_rate).read_to_end(&mut bytes)?;
+        Ok((bytes, total))
+    }
+
+    /// Fetches an image from a URL provided by the server, such as those in
+    /// [`ArtistInfo`] and [`AlbumInfo`].
+    ///
+    /// These are usually absolute URLs to last.fm, but some servers (such as
+    /// Navidrome) return paths on the server itself instead. Relative URLs are
+    /// resolved against the server's address before fetching.
+    ///
+    /// Images on the server are fetched like any other request. Images on
+    /// other hosts are fetched without the [host override] and [default
+    /// headers], so neither leaks to third parties.
+    ///
+    /// [`ArtistInfo`]: ./struct.ArtistInfo.html
+    /// [`AlbumInfo`]: ./struct.AlbumInfo.html
+    /// [host override]: ./struct.ClientBuilder.html#method.host_header
+    /// [default headers]: ./struct.ClientBuilder.html#method.default_header
+    pub fn image_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        let url = self.url.join(url)?;
+        let mut res = if url.origin() == self.url.origin() {
+            self.send(url, RequestKind::Transfer)?
+        } else {
+            info!("Connecting to {}", url);
+            let res = self.foreign_client.get(url).send()?;
+            if !res.status().is_success() {
+                return Err(Error::Connection(res.status()));
+            }
+            res
+        };
+
+        let mut bytes = Vec::new();
+        res.copy_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Fetches the cover art with the given ID, optionally scaled to a size.
+    ///
+    /// Unlike [`Media::cover_art`], the returned [`CoverArt`] notes whether the
+    /// image appears to be the server's placeholder for missing art. See
+    /// [`CoverArt`] for how the placeholder is detected.
+    ///
+    /// [`Media::cover_art`]: ./trait.Media.html#tymethod.cover_art
+    /// [`CoverArt`]: ./struct.CoverArt.html
+    pub fn cover_art<U: Into<Option<usize>>>(&self, id: &str, size: U) -> Result<CoverArt> {
+        let args = Query::with("id", id).arg("size", size.into()).build();
+        self.fetch_cover_art(args)
+    }
+
+    /// Returns an authenticated URL for streaming the media with the given ID.
+    ///
+    /// The URL can be handed to anything that plays from a URL, such as an
+    /// external player or a cast device, without `sunk` doing the transfer.
+    /// Note that the URL carries the user's credentials.
+    pub fn stream_url<I: Into<Id>>(&self, id: I, options: &StreamOptions) -> Result<String> {
+        self.build_url("stream", options.to_args(&id.into()))
+    }
+
+    /// Returns an authenticated URL for downloading the original file of the
+    /// media with the given ID, without transcoding.
+    pub fn download_url<I: Into<Id>>(&self, id: I) -> Result<String> {
+        self.build_url("download", Query::with("id", id.into()))
+    }
+
+    /// Returns an authenticated URL for the cover art with the given ID,
+    /// optionally scaled to a size.
+    pub fn cover_art_url<U: Into<Option<usize>>>(&self, id: &str, size: U) -> Result<String> {
+        self.build_url(
+            "getCoverArt",
+            Query::with("id", id).arg("size", size.into()).build(),
+        )
+    }
+
+    /// Fetches the cover art with the given ID, asking for it in a format.
+    ///
+    /// The Subsonic API (and OpenSubsonic) has no way to choose the format of
+    /// cover art, so the format is sent as a `format` hint that only some
+    /// servers honour. Servers that don't recognise it ignore it and return
+    /// the image as usual, so the format of the returned image is not
+    /// guaranteed; check the bytes if it matters.
+    pub fn cover_art_as<U>(&self, id: &str, size: U, format: ImageFormat) -> Result<CoverArt>
+    where
+        U: Into<Option<usize>>,
+    {
+        let args = Query::with("id", id)
+            .arg("size", size.into())
+            .arg("format", format)
+            .build();
+        self.fetch_cover_art(args)
+    }
+
+    fn fetch_cover_art(&self, args: Query) -> Result<CoverArt> {
+        let uri = self.request_url("getCoverArt", args)?;
+        let mut res = self.send(uri, RequestKind::Transfer)?;
+        check_binary(&mut res)?;
+
+        let hinted = res
+            .headers()
+            .get(CONTENT_DISPOSITION)
+            .and_then(|d| d.to_str().ok())
+            .is_some_and(|d| d.to_lowercase().contains("placeholder"));
+        let mut bytes = Vec::new();
+        res.copy_to(&mut bytes)?;
+
+        let digest = format!("{:x}", md5::compute(&bytes));
+        let is_placeholder = hinted || self.placeholder_digests.contains(&digest);
+        Ok(CoverArt {
+            bytes,
+            is_placeholder,
+        })
+    }
+
+    /// Returns the raw bytes of a HLS slice.
+    pub fn hls_bytes(&self, hls: &Hls) -> Result<Vec<u8>> {
+        let url: Url = self.url.join(&hls.url)?;
+        let res = self.send(url, RequestKind::Transfer)?;
+
+        let mut bytes = Vec::new();
+        ThrottledReader::new(res, self.max_transfer_rate).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Returns an iterator fetching the slices of a HLS playlist in order.
+    ///
+    /// Each slice is only requested once the previous one has been consumed,
+    /// so a player can feed a decoder continuously without holding the entire
+    /// stream in memory.
+    ///
+    /// If the playlist is a master playlist (as created when requesting
+    /// multiple bit rates from [`Song::hls`]), a variant is selected and its
+    /// playlist is fetched first. The highest bandwidth variant that does not
+    /// exceed `max_bandwidth` is used, falling back to the lowest bandwidth
+    /// variant available.
+    ///
+    /// [`Song::hls`]: ./song/struct.Song.html#method.hls
+    pub fn hls_segment_stream<U>(
+        &self,
+        playlist: &HlsPlaylist,
+        max_bandwidth: U,
+    ) -> Result<HlsSegments<'_>>
+    where
+        U: Into<Option<usize>>,
+    {
+        match playlist.select_variant(max_bandwidth) {
+            Some(variant) => {
+                let url: Url = self.url.join(&variant.url)?;
+                let mut res = self.send(url, RequestKind::Metadata)?;
+                let media = res.text()?.parse::<HlsPlaylist>()?;
+                Ok(HlsSegments::new(self, media.into_iter().collect()))
+            }
+            None => Ok(HlsSegments::new(self, playlist.hls.clone())),
+        }
+    }
+
+    /// Lists the OpenSubsonic extensions the server supports.
+    ///
+    /// # Errors
+    ///
+    /// Servers that don't implement OpenSubsonic will return an error; use
+    /// [`capabilities`] to treat them as supporting nothing instead.
+    ///
+    /// [`capabilities`]: #method.capabilities
+    pub fn extensions(&self) -> Result<Vec<Extension>> {
+        Ok(self
+            .get(
+                "getOpenSubsonicExtensions",
+                Query::none(),
+                &["openSubsonicExtensions"],
+            )?
+            .unwrap_or_default())
+    }
+
+    /// Returns which optional features the server supports, based on the
+    /// OpenSubsonic extensions it lists.
+    ///
+    /// Servers that don't implement OpenSubsonic, and so can't list any
+    /// extensions, are reported as supporting none of them. Such servers are
+    /// recognised by answering with an API error or a `404 Not Found`.
+    ///
+    /// # Errors
+    ///
+    /// Any other error, such as the server being unreachable or failing with
+    /// a `5xx` status, is returned rather than taken as a lack of support.
+    pub fn capabilities(&self) -> Result<Capabilities> {
+        capabilities_from(self.extensions())
+    }
+
+    /// Tests a connection with the server, returning what the server reported
+    /// about itself.
+    ///
+    /// The server's API version is recorded, and later requests target it if
+    /// it is older than the client's target (see [`target_version`]). This
+    /// lets the client fall back to features older servers support, such as
+    /// password authentication before 1.13.0. A server rejecting the ping
+    /// because of this is pinged again once its version is known.
+    ///
+    /// [`target_version`]: #method.target_version
+    pub fn ping(&self) -> Result<ServerInfo> {
+        let before = self.target_version();
+        match self.ping_once() {
+            Err(Error::Api(_)) if self.target_version() != before => self.ping_once(),
+            res => res,
+        }
+    }
+
+    fn ping_once(&self) -> Result<ServerInfo> {
+        let response = self.get_response("ping", Query::none())?;
+        if let Some(ver) = response.version() {
+            *self.server_ver.lock().unwrap_or_else(|e| e.into_inner()) = Some(ver);
+            if self.negotiate && ver < self.target() {
+                info!("Server reports API version {}; targeting it", ver);
+            }
+        }
+        let info = ServerInfo {
+            version: response.version(),
+            server_type: response.server_type().map(String::from),
+            server_version: response.server_version().map(String::from),
+            open_subsonic: response.is_open_subsonic(),
+        };
+        match response.into_error() {
+            Some(err) => Err(err.into()),
+            None => Ok(info),
+        }
+    }
+
+    /// Measures the round trip time of a [`ping`] to the server.
+    ///
+    /// The time covers the whole request, from connecting to parsing the
+    /// response, and is only returned if the server answers the ping
+    /// successfully.
+    ///
+    /// [`ping`]: #method.ping
+    pub fn ping_latency(&self) -> Result<Duration> {
+        let start = Instant::now();
+        self.ping()?;
+        Ok(start.elapsed())
+    }
+
+    /// Get details about the software license. Note that access to the REST API
+    /// requires that the server has a valid license (after a 30-day trial
+    /// period). To get a license key you must upgrade to Subsonic Premium.
+    ///
+    /// Forks of Subsonic (Libresonic, Airsonic, etc.) do not require licenses;
+    /// this method will always return a valid license and trial when attempting
+    /// to connect to these services.
+    pub fn check_license(&self) -> Result<License> {
+        let res = self.get_value("getLicense", Query::none())?;
+        Ok(serde_json::from_value::<License>(res)?)
+    }
+
+    /// Initiates a rescan of the media libraries.
+    ///
+    /// # Note
+    ///
+    /// This method was introduced in version 1.15.0. It will not be supported
+    /// on servers with earlier versions of the Subsonic API, and fails with
+    /// [`Error::Unsupported`] if the server is known to be older.
+    ///
+    /// [`Error::Unsupported`]: ./enum.Error.html#variant.Unsupported
+    pub fn scan_library(&self) -> Result<()> {
+        self.get_value("startScan", Query::none())?;
+        Ok(())
+    }
+
+    /// Gets the status of a scan. Returns the current status for media library
+    /// scanning.
+    ///
+    /// # Note
+    ///
+    /// This method was introduced in version 1.15.0. It will not be supported
+    /// on servers with earlier versions of the Subsonic API, and fails with
+    /// [`Error::Unsupported`] if the server is known to be older.
+    ///
+    /// [`Error::Unsupported`]: ./enum.Error.html#variant.Unsupported
+    pub fn scan_status(&self) -> Result<(bool, u64)> {
+        let res = self.get_value("getScanStatus", Query::none())?;
+
+        #[derive(Deserialize)]
+        struct ScanStatus {
+            count: u64,
+            scanning: bool,
+        }
+        let sc = serde_json::from_value::<ScanStatus>(res)?;
+
+        Ok((sc.scanning, sc.count))
+    }
+
+    /// Grants or revokes some of a user's roles, leaving their other roles and
+    /// settings untouched.
+    ///
+    /// Unlike [`User::update`], only the roles given are sent to the server.
+    ///
+    /// # Errors
+    ///
+    /// Changing a user's roles requires administrative rights.
+    ///
+    /// [`User::update`]: ./struct.User.html#method.update
+    pub fn set_user_roles(&self, username: &str, roles: &[(Role, bool)]) -> Result<()> {
+        let mut update = User::edit(username);
+        for &(role, enabled) in roles {
+            update.role(role, enabled);
+        }
+        update.update(self)
+    }
+
+    /// Returns all configured top-level music folders.
+    pub fn music_folders(&self) -> Result<Vec<MusicFolder>> {
+        self.get_list(
+            "getMusicFolders",
+            Query::none(),
+            "musicFolders",
+            "musicFolder",
+        )
+    }
+
+    /// Returns the index of artists (or top-level folders), grouped by the
+    /// letter they are indexed under.
+    ///
+    /// This follows the server's folder structure rather than ID3 tags; see
+    /// [`Artist::list`] for the tag-based equivalent. If `folder_id` is
+    /// `None`, the client's default folder is used, if set, and otherwise all
+    /// folders are indexed.
+    ///
+    /// `if_modified_since` is a timestamp in milliseconds since the Unix
+    /// epoch, such as the [`last_modified`] of an index fetched before. If the
+    /// index hasn't changed since then, the server returns it without any
+    /// entries.
+    ///
+    /// [`Artist::list`]: ./struct.Artist.html#method.list
+    /// [`last_modified`]: ./struct.Indexes.html#structfield.last_modified
+    pub fn indexes<U, M>(&self, folder_id: U, if_modified_since: M) -> Result<Indexes>
+    where
+        U: Into<Option<Id>>,
+        M: Into<Option<u64>>,
+    {
+        let args = Query::with("musicFolderId", self.folder_or_default(folder_id.into()))
+            .arg("ifModifiedSince", if_modified_since.into())
+            .build();
+        self.get_required("getIndexes", args, &["indexes"])
+    }
+
+    /// Returns all genres.
+    pub fn genres(&self) -> Result<Vec<Genre>> {
+        self.get_list("getGenres", Query::none(), "genres", "genre")
+    }
+
+    /// Returns all currently playing media on the server.
+    pub fn now_playing(&self) -> Result<Vec<NowPlaying>> {
+        self.get_list("getNowPlaying", Query::none(), "nowPlaying", "entry")
+    }
+
+    /// Reports that a song has started playing, without counting it as played.
+    ///
+    /// The song appears in [`now_playing`] and, if the user has configured
+    /// last.fm, as their "now playing" track. This is `scrobble` with
+    /// `submission=false`; use [`scrobble_play`] once the song has been
+    /// listened to.
+    ///
+    /// [`now_playing`]: #method.now_playing
+    /// [`scrobble_play`]: #method.scrobble_play
+    pub fn now_playing_notify<I: Into<Id>>(&self, id: I) -> Result<()> {
+        let args = Query::with("id", id.into())
+            .arg("submission", false)
+            .build();
+        self.get_value("scrobble", args)?;
+        Ok(())
+    }
+
+    /// Records that a song was played, updating its play count and scrobbling
+    /// it to last.fm if the user has configured it.
+    ///
+    /// `time` is when the song was listened to, in milliseconds since the
+    /// Unix epoch; if `None`, the server uses the current time. This is
+    /// `scrobble` with `submission=true`. Call it once per play, after the
+    /// song has been listened to.
+    pub fn scrobble_play<I, U>(&self, id: I, time: U) -> Result<()>
+    where
+        I: Into<Id>,
+        U: Into<Option<u64>>,
+    {
+        let args = Query::with("id", id.into())
+            .arg("time", time.into())
+            .arg("submission", true)
+            .build();
+        self.get_value("scrobble", args)?;
+        Ok(())
+    }
+
+    /// Asks the server to check all the podcasts it subscribes to for new
+    /// episodes.
+    ///
+    /// The check happens in the background; new episodes appear once it
+    /// finishes.
+    ///
+    /// # Errors
+    ///
+    /// The user needs the podcast role to refresh podcasts.
+    pub fn refresh_podcasts(&self) -> Result<()> {
+        self.get_value("refreshPodcasts", Query::none())?;
+        Ok(())
+    }
+
+    /// Returns the newest episodes of all podcasts the server subscribes to.
+    /// Optionally takes a number of episodes to maximally return.
+    ///
+    /// This is an alias for [`Episode::newest`]; see its documentation for the
+    /// limits on `count`.
+    ///
+    /// [`Episode::newest`]: ./podcast/struct.Episode.html#method.newest
+    pub fn newest_episodes<U>(&self, count: U) -> Result<Vec<Episode>>
+    where
+        U: Into<Option<usize>>,
+    {
+        Episode::newest(self, count)
+    }
+
+    /// Searches for lyrics matching the artist and title. Returns `None` if no
+    /// lyrics are found.
+    pub fn lyrics<'a, S>(&self, artist: S, title: S) -> Result<Option<Lyrics>>
+    where
+        S: Into<Option<&'a str>>,
+    {
+        let args = Query::with("artist", artist.into())
+            .arg("title", title.into())
+            .build();
+        let res = self.get_value("getLyrics", args)?;
+
+        if res.get("value").is_some() {
+            Ok(Some(serde_json::from_value(res)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns albums, artists and songs matching the given search criteria.
+    /// Supports paging through the result. See the [search module] for
+    /// documentation.
+    ///
+    /// [search module]: ./search/index.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use sunk::search::{self, SearchPage};
+    /// use sunk::Client;
+    ///
+    /// # fn run() -> sunk::Result<()> {
+    /// # let site = "http://demo.subsonic.org";
+    /// # let user = "guest3";
+    /// # let password = "guest";
+    /// let client = Client::new(site, user, password)?;
+    ///
+    /// let search_size = SearchPage::new();
+    /// let ignore = search::NONE;
+    ///
+    /// let result = client.search("smile", ignore, ignore, search_size)?;
+    ///
+    /// assert!(result.artists.is_empty());
+    /// assert!(result.albums.is_empty());
+    /// assert!(!result.songs.is_empty());
+    /// # Ok(())
+    /// # }
+    /// # fn main() { }
+    /// ```
+    pub fn search(
+        &self,
+        query: &str,
+        artist_page: SearchPage,
+        album_page: SearchPage,
+        song_page: SearchPage,
+    ) -> Result<SearchResult> {
+        self.search_with("search3", query, artist_page, album_page, song_page)
+    }
+
+    /// Searches the server's folder structure rather than its ID3 tags.
+    /// Supports paging through the result, as with [`search`].
+    ///
+    /// This uses `search2`, which finds media in libraries that aren't
+    /// tagged. The results are folder-based: artists and albums are the
+    /// directories that matched, and their IDs are directory IDs rather than
+    /// the tag-based IDs returned by [`search`]. Matching songs and videos are
+    /// listed together as [`Child`] entries.
+    ///
+    /// [`search`]: #method.search
+    /// [`Child`]: ./enum.Child.html
+    pub fn search2(
+        &self,
+        query: &str,
+        artist_page: SearchPage,
+        album_page: SearchPage,
+        song_page: SearchPage,
+    ) -> Result<FolderSearchResult> {
+        self.search_with("search2", query, artist_page, album_page, song_page)
+    }
+
+    /// Searches using the newest search method the server supports.
+    ///
+    /// Tries `search3` (ID3 tags, since API 1.8.0) first. If the server
+    /// rejects it due to an incompatible protocol version, falls back to
+    /// `search2` (file structure, since API 1.4.0), and then to the legacy
+    /// `search`. Results of each method are normalized into a
+    /// [`SearchResult`], leaving out any videos `search2` finds; the legacy
+    /// method only supports searching songs, so the artist and album pages are
+    /// ignored if it is used.
+    ///
+    /// [`SearchResult`]: ./search/struct.SearchResult.html
+    pub fn search_compat(
+        &self,
+        query: &str,
+        artist_page: SearchPage,
+        album_page: SearchPage,
+        song_page: SearchPage,
+    ) -> Result<SearchResult> {
+        let unsupported = |res: &Result<_>, endpoint| match res {
+            Err(Error::Api(ApiError::ServerMustUpgrade))
+            | Err(Error::Api(ApiError::ClientMustUpgrade))
+            | Err(Error::Unsupported { .. }) => {
+                info!("Server does not support {}", endpoint);
+                true
+            }
+            _ => {
+                info!("Searched using {}", endpoint);
+                false
+            }
+        };
+
+        let res = self.search(query, artist_page, album_page, song_page);
+        if !unsupported(&res, "search3") {
+            return res;
+        }
+        let res = self
+            .search2(query, artist_page, album_page, song_page)
+            .map(SearchResult::from);
+        if !unsupported(&res, "search2") {
+            return res;
+        }
+
+        #[derive(Deserialize)]
+        struct LegacyResult {
+            #[serde(rename = "match")]
+            #[serde(default)]
+            matches: Vec<Song>,
+        }
+
+        let args = Query::with("any", query)
+            .arg("count", song_page.count)
+            .arg("offset", song_page.result_offset())
+            .build();
+        let res = serde_json::from_value::<LegacyResult>(self.get_value("search", args)?)?;
+        info!("Searched using search");
+
+        Ok(SearchResult {
+            artists: Vec::new(),
+            albums: Vec::new(),
+            songs: res.matches,
+        })
+    }
+
+    fn search_with<T>(
+        &self,
+        endpoint: &str,
+        query: &str,
+        artist_page: SearchPage,
+        album_page: SearchPage,
+        song_page: SearchPage,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned + Default,
+    {
+        // FIXME There has to be a way to make this nicer.
+        let args = Query::with("query", query)
+            .arg("artistCount", artist_page.count)
+            .arg("artistOffset", artist_page.result_offset())
+            .arg("albumCount", album_page.count)
+            .arg("albumOffset", album_page.result_offset())
+            .arg("songCount", song_page.count)
+            .arg("songOffset", song_page.result_offset())
+            .arg("musicFolderId", self.default_folder.clone())
+            .build();
+
+        // search2 responds with `searchResult2`, search3 with `searchResult3`.
+        let key = format!("searchResult{}", endpoint.trim_start_matches("search"));
+        Ok(self.get(endpoint, args, &[&key])?.unwrap_or_default())
+    }
+
+    /// Returns all starred artists, albums, and songs, organised by the
+    /// server's folder structure.
+    ///
+    /// This uses `getStarred`; see [`starred2`] for starred items organised by
+    /// ID3 tags.
+    ///
+    /// [`starred2`]: #method.starred2
+    pub fn starred<U>(&self, folder_id: U) -> Result<Starred>
+    where
+        U: Into<Option<Id>>,
+    {
+        self.starred_with("getStarred", &["starred"], folder_id.into())
+    }
+
+    /// Returns all starred artists, albums, and songs, organised by ID3 tags.
+    ///
+    /// This uses `getStarred2`, whose IDs match those of other tag-based
+    /// methods such as [`Album::get`].
+    ///
+    /// [`Album::get`]: ./struct.Album.html#method.get
+    pub fn starred2<U>(&self, folder_id: U) -> Result<Starred>
+    where
+        U: Into<Option<Id>>,
+    {
+        self.starred_with("getStarred2", &["starred2"], folder_id.into())
+    }
+
+    fn starred_with(
+        &self,
+        endpoint: &str,
+        path: &[&str],
+        folder_id: Option<Id>,
+    ) -> Result<Starred> {
+        let folder_id = self.folder_or_default(folder_id);
+        let args = Query::with("musicFolderId", folder_id);
+        Ok(self.get(endpoint, args, path)?.unwrap_or_default())
+    }
+}
+
+/// Takes the capabilities of a server from the extensions it lists, treating
+/// servers that can't list them as supporting nothing.
+fn capabilities_from(extensions: Result<Vec<Extension>>) -> Result<Capabilities> {
+    match extensions {
+        Ok(extensions) => Ok(Capabilities::new(extensions)),
+        Err(Error::Api(_)) => Ok(Capabilities::default()),
+        Err(Error::Connection(StatusCode::NOT_FOUND)) => Ok(Capabilities::default()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether a request fetches metadata or transfers media, which determines
+/// the timeout applied to it.
+#[derive(Clone, Copy, Debug)]
+enum RequestKind {
+    Metadata,
+    Transfer,
+}
+
+/// Returns the API version `endpoint` was introduced in, if it is newer than
+/// the first version of the API.
+///
+/// OpenSubsonic endpoints aren't tied to an API version, so aren't listed.
+fn required_version(endpoint: &str) -> Option<Version> {
+    let ver = match endpoint {
+        "changePassword" | "createUser" => "1.1.0",
+        "addChatMessage" | "deletePlaylist" | "getAlbumList" | "getChatMessages" | "getLyrics"
+        | "getRandomSongs" | "jukeboxControl" => "1.2.0",
+        "deleteUser" | "getUser" => "1.3.0",
+        "search2" => "1.4.0",
+        "scrobble" => "1.5.0",
+        "createShare" | "deleteShare" | "getPodcasts" | "getShares" | "setRating"
+        | "updateShare" => "1.6.0",
+        "getAlbum" | "getAlbumList2" | "getArtist" | "getArtists" | "getAvatar" | "getSong"
+        | "getStarred" | "getStarred2" | "getUsers" | "getVideos" | "hls" | "hls.m3u8"
+        | "search3" | "star" | "unstar" | "updatePlaylist" => "1.8.0",
+        "createBookmark"
+        | "createPodcastChannel"
+        | "deleteBookmark"
+        | "deletePodcastChannel"
+        | "deletePodcastEpisode"
+        | "downloadPodcastEpisode"
+        | "getBookmarks"
+        | "getGenres"
+        | "getInternetRadioStations"
+        | "getSongsByGenre"
+        | "refreshPodcasts" => "1.9.0",
+        "updateUser" => "1.10.1",
+        "getArtistInfo" | "getArtistInfo2" | "getSimilarSongs" | "getSimilarSongs2" => "1.11.0",
+        "getPlayQueue" | "savePlayQueue" => "1.12.0",
+        "getNewestPodcasts" | "getTopSongs" => "1.13.0",
+        "getAlbumInfo" | "getAlbumInfo2" | "getCaptions" | "getVideoInfo" => "1.14.0",
+        "getScanStatus" | "startScan" => "1.15.0",
+        "createInternetRadioStation"
+        | "deleteInternetRadioStation"
+        | "updateInternetRadioStation" => "1.16.0",
+        _ => return None,
+    };
+    Some(Version::from(ver))
+}
+
+/// Re-attaches the original query to a redirect target that has dropped the
+/// authentication parameters.
+fn with_auth_query(mut uri: Url, query: Option<&str>) -> Url {
+    let query = match query {
+        Some(q) => q,
+        None => return uri,
+    };
+    if uri.query_pairs().any(|(k, _)| k == "u" || k == "apiKey") {
+        return uri;
+    }
+    let merged = match uri.query() {
+        Some(q) if !q.is_empty() => format!("{}&{}", q, query),
+        _ => query.to_string(),
+    };
+    uri.set_query(Some(&merged));
+    uri
+}
+
+/// Reads how long the server asked to wait from a `Retry-After` header.
+///
+/// The header holds either a number of seconds or an HTTP date; a date in
+/// the past means no wait.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::from_secs(0)),
+    )
+}
+
+/// Checks that a response to a binary request is not a JSON response.
+///
+/// Binary endpoints (such as `getCoverArt` or `stream`) report failures as a
+/// regular JSON response rather than through the HTTP status.
+fn check_binary(res: &mut reqwest::Response) -> Result<()> {
+    let is_json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|t| t.to_str().ok())
+        .is_some_and(|t| t.starts_with("application/json"));
+    if is_json {
+        let response = res.json::<Response>()?;
+        return Err(response
+            .into_error()
+            .map(|e| e.into())
+            .unwrap_or(Error::Other("unexpected response to binary request")));
+    }
+    Ok(())
+}
+
+/// Details a server reports about itself, returned by [`Client::ping`].
+///
+/// [`Client::ping`]: ./struct.Client.html#method.ping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    /// The API version the server implements.
+    pub version: Option<Version>,
+    /// The name of the server software, such as `"navidrome"`. Only provided
+    /// by OpenSubsonic servers.
+    pub server_type: Option<String>,
+    /// The version of the server software. Only provided by OpenSubsonic
+    /// servers.
+    pub server_version: Option<String>,
+    /// Whether the server supports OpenSubsonic extensions to the API.
+    pub open_subsonic: bool,
+}
+
+/// An OpenSubsonic extension to the API, as listed by
+/// [`Client::extensions`].
+///
+/// [`Client::extensions`]: ./struct.Client.html#method.extensions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Extension {
+    /// The name of the extension, such as `"songLyrics"`.
+    pub name: String,
+    /// The versions of the extension the server supports.
+    #[serde(default)]
+    pub versions: Vec<u32>,
+}
+
+/// The optional features a server supports, as returned by
+/// [`Client::capabilities`].
+///
+/// [`Client::capabilities`]: ./struct.Client.html#method.capabilities
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Whether the server lists OpenSubsonic extensions at all.
+    pub open_subsonic: bool,
+    /// Whether the server accepts API keys in place of a username and
+    /// password (`apiKeyAuthentication`).
+    pub supports_api_key_auth: bool,
+    /// Whether the server accepts parameters in a POST body (`formPost`).
+    pub supports_form_post: bool,
+    /// Whether the server can fetch a single podcast episode
+    /// (`getPodcastEpisode`).
+    pub supports_podcast_episode: bool,
+    /// Whether the server can save play queues by index (`indexBasedQueue`).
+    pub supports_index_based_queue: bool,
+    /// Whether the server provides structured and synced lyrics
+    /// (`songLyrics`).
+    pub supports_song_lyrics: bool,
+    /// Whether the server can start transcoded streams part way through
+    /// (`transcodeOffset`).
+    pub supports_transcode_offset: bool,
+    /// Whether the server can negotiate transcoding with the client
+    /// (`transcoding`).
+    pub supports_transcoding: bool,
+    extensions: Vec<Extension>,
+}
+
+impl Capabilities {
+    fn new(extensions: Vec<Extension>) -> Capabilities {
+        let has = |name: &str| extensions.iter().any(|ext| ext.name == name);
+        Capabilities {
+            open_subsonic: true,
+            supports_api_key_auth: has("apiKeyAuthentication"),
+            supports_form_post: has("formPost"),
+            supports_podcast_episode: has("getPodcastEpisode"),
+            supports_index_based_queue: has("indexBasedQueue"),
+            supports_song_lyrics: has("songLyrics"),
+            supports_transcode_offset: has("transcodeOffset"),
+            supports_transcoding: has("transcoding"),
+            extensions,
+        }
+    }
+
+    /// Returns whether the server lists the extension `name`, including
+    /// extensions without a field of their own.
+    pub fn supports(&self, name: &str) -> bool {
+        self.extensions.iter().any(|ext| ext.name == name)
+    }
+
+    /// Returns the extensions the server lists.
+    pub fn extensions(&self) -> &[Extension] {
+        &self.extensions
+    }
+}
+
+/// A representation of a license associated with a server.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct License {
+    /// Whether the license is valid or not.
+    pub valid: bool,
+    /// The email associated with the email.
+    pub email: String,
+    /// An ISO8601 timestamp of the server's trial expiry.
+    pub trial_expires: Option<String>,
+    /// An ISO8601 timestamp of the server's license expiry. Servers still in
+    /// the trial phase typically will not have this field.
+    pub license_expires: Option<String>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::test_util;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use crate::ImageSize;
+
+    #[test]
+    fn test_token_auth() {
+        let cli = test_util::demo_site().unwrap();
+        let token_addr = cli.build_url("ping", Query::none()).unwrap();
+        let legacy_cli = cli.with_target("1.8.0".into());
+        let legacy_addr = legacy_cli.build_url("ping", Query::none()).unwrap();
+
+        assert!(token_addr != legacy_addr);
+        assert_eq!(
+            legacy_addr,
+            "http://demo.subsonic.org/rest/ping?u=guest3&p=guest&v=1.8.0&c=sunk&f=json&"
+        );
+    }
+
+    #[test]
+    fn explicit_auth_method() {
+        let addr = |method| {
+            Client::builder("http://demo.subsonic.org", "guest3", "guest")
+                .auth_method(method)
+                .build()
+                .unwrap()
+                .build_url("ping", Query::none())
+                .unwrap()
+        };
+
+        assert!(addr(AuthMethod::Token).contains("&t="));
+        assert!(addr(AuthMethod::Plain).contains("u=guest3&p=guest&"));
+        assert!(addr(AuthMethod::HexEncoded).contains("u=guest3&p=enc:6775657374&"));
+
+        let legacy = Client::builder("http://demo.subsonic.org", "guest3", "guest")
+            .auth_method(AuthMethod::Token)
+            .build()
+            .unwrap()
+            .with_target("1.8.0".into());
+        let legacy_addr = legacy.build_url("ping", Query::none()).unwrap();
+        assert!(legacy_addr.contains("&t="));
+    }
+
+    #[test]
+    fn stored_token_auth() {
+        let cli = Client::with_token(
+            "http://demo.subsonic.org",
+            "guest3",
+            "26719a1196d2a940705a59634eb18eab",
+            "c19b2d",
+        )
+        .unwrap()
+        .with_target("1.8.0".into());
+        let addr = cli.build_url("ping", Query::none()).unwrap();
+        assert!(addr.contains("u=guest3&t=26719a1196d2a940705a59634eb18eab&s=c19b2d&"));
+
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let cli = Client::builder("http://demo.subsonic.org", "guest3", "")
+            .token_fn(move || {
+                let n = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                (format!("token{}", n), format!("salt{}", n))
+            })
+            .build()
+            .unwrap();
+        assert!(cli
+            .build_url("ping", Query::none())
+            .unwrap()
+            .contains("t=token0&s=salt0"));
+        assert!(cli
+            .build_url("ping", Query::none())
+            .unwrap()
+            .contains("t=token1&s=salt1"));
+    }
+
+    #[test]
+    fn api_key_auth() {
+        let cli = Client::with_api_key("http://demo.subsonic.org", "s3cr3t").unwrap();
+        let addr = cli.build_url("ping", Query::none()).unwrap();
+        assert_eq!(
+            addr,
+            "http://demo.subsonic.org/rest/ping?apiKey=s3cr3t&v=1.14.0&c=sunk&f=json&"
+        );
+    }
+
+    #[test]
+    fn media_urls() {
+        let cli = Client::builder("http://demo.subsonic.org", "guest3", "guest")
+            .auth_method(AuthMethod::Plain)
+            .build()
+            .unwrap();
+        let prefix = "http://demo.subsonic.org/rest/";
+        let auth = "?u=guest3&p=guest&v=1.14.0&c=sunk&f=json&";
+
+        let mut options = StreamOptions::new();
+        assert_eq!(
+            cli.stream_url("27", &options).unwrap(),
+            [prefix, "stream", auth, "id=27"].concat()
+        );
+        options
+            .max_bit_rate(128)
+            .format("mp3")
+            .time_offset(30)
+            .size(640, 480)
+            .estimate_content_length(true);
+        assert_eq!(
+            cli.stream_url(27, &options).unwrap(),
+            [
+                prefix,
+                "stream",
+                auth,
+                "id=27&maxBitRate=128&format=mp3&timeOffset=30&size=640x480&estimateContentLength=true"
+            ]
+            .concat()
+        );
+        assert_eq!(
+            cli.download_url("27").unwrap(),
+            [prefix, "download", auth, "id=27"].concat()
+        );
+        assert_eq!(
+            cli.cover_art_url("al-27", 300).unwrap(),
+            [prefix, "getCoverArt", auth, "id=al-27&size=300"].concat()
+        );
+    }
+
+    #[test]
+    fn credentials_encoded() {
+        let cli = Client::builder("http://demo.subsonic.org", "Björk", "p&ss=w+rd")
+            .auth_method(AuthMethod::Plain)
+            .client_name("my player")
+            .build()
+            .unwrap();
+        let addr = cli
+            .build_url("search3", Query::with("query", "AC/DC"))
+            .unwrap();
+        assert_eq!(
+            addr,
+            "http://demo.subsonic.org/rest/search3?u=Bj%C3%B6rk&p=p%26ss%3Dw%2Brd\
+             &v=1.14.0&c=my+player&f=json&query=AC%2FDC"
+        );
+    }
+
+    #[test]
+    fn client_name_and_version_override() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.16.1"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok]);
+        let cli = Client::builder(&site, "user", "pass")
+            .client_name("my-player")
+            .build()
+            .unwrap();
+        assert_eq!(cli.client_name(), "my-player");
+        assert!(cli
+            .build_url("ping", Query::none())
+            .unwrap()
+            .contains("&v=1.14.0&c=my-player&f=json"));
+
+        let res = cli
+            .raw_response_at("ping", Query::none(), Version::from("1.16.1"))
+            .unwrap();
+        assert!(res.is_ok());
+        let request = requests.recv().unwrap();
+        assert!(request.contains("&t="));
+        assert!(request.contains("&v=1.16.1&c=my-player&f=json"));
+
+        let cli = cli.with_client_name("other");
+        assert!(cli
+            .build_url("ping", Query::none())
+            .unwrap()
+            .contains("&v=1.14.0&c=other&f=json"));
+    }
+
+    #[test]
+    fn api_key_detected() {
+        let extensions = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.16.1",
+                "openSubsonic": true,
+                "openSubsonicExtensions": [
+                    { "name": "apiKeyAuthentication", "versions": [1] }
+                ]
+            }}"#,
+        );
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.16.1"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[extensions, ok]);
+        let cli = Client::builder(&site, "user", "pass")
+            .api_key("s3cr3t")
+            .build()
+            .unwrap();
+
+        // Building a URL doesn't contact the server.
+        let addr = cli.build_url("ping", Query::none()).unwrap();
+        assert!(addr.contains("u=user"));
+        assert!(requests.try_recv().is_err());
+
+        // The first request checks for support, using the password.
+        cli.ping().unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/getOpenSubsonicExtensions?"));
+        assert!(request.contains("u=user"));
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/ping?apiKey=s3cr3t&"));
+        assert!(!request.contains("u=user"));
+
+        assert!(cli.detect_auth().unwrap());
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[test]
+    fn api_key_checked_once_across_threads() {
+        let extensions = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.16.1",
+                "openSubsonic": true,
+                "openSubsonicExtensions": [
+                    { "name": "apiKeyAuthentication", "versions": [1] }
+                ]
+            }}"#,
+        );
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.16.1"}}"#,
+        );
+        let (site, requests) =
+            test_util::recording_mock_server(&[extensions, ok.clone(), ok.clone(), ok]);
+        let cli = Arc::new(
+            Client::builder(&site, "user", "pass")
+                .api_key("s3cr3t")
+                .build()
+                .unwrap(),
+        );
+
+        let threads = (0..3)
+            .map(|_| {
+                let cli = cli.clone();
+                thread::spawn(move || cli.ping().unwrap())
+            })
+            .collect::<Vec<_>>();
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        let sent = requests.try_iter().collect::<Vec<_>>();
+        assert_eq!(sent.len(), 4);
+        assert!(sent[0].starts_with("GET /rest/getOpenSubsonicExtensions?"));
+        // No request goes out with the password while the check is running.
+        assert!(sent[1..].iter().all(|r| r.contains("apiKey=s3cr3t")));
+    }
 
     #[test]
-    fn test_token_auth() {
-        let cli = test_util::demo_site().unwrap();
-        let token_addr = cli.build_url("ping", Query::none()).unwrap();
-        let legacy_cli = cli.with_target("1.8.0".into());
-        let legacy_addr = legacy_cli.build_url("ping", Query::none()).unwrap();
+    fn api_key_not_advertised() {
+        let failed = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "failed",
+                "version": "1.16.1",
+                "error": { "code": 0, "message": "Unknown method" }
+            }}"#,
+        );
+        let down = "HTTP/1.1 503 Service Unavailable\r\n\
+                    Content-Length: 0\r\nConnection: close\r\n\r\n";
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.16.1"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[
+            down.to_string(),
+            ok.clone(),
+            ok.clone(),
+            failed.clone(),
+            ok.clone(),
+            failed,
+        ]);
+        let cli = Client::builder(&site, "user", "pass")
+            .api_key("s3cr3t")
+            .build()
+            .unwrap();
+        let path = |request: String| request.split('?').next().unwrap().to_string();
 
-        assert!(token_addr != legacy_addr);
+        // A failed check falls back to the password, and isn't tried again on
+        // every request.
+        cli.ping().unwrap();
+        cli.ping().unwrap();
+        let sent = requests.try_iter().map(path).collect::<Vec<_>>();
         assert_eq!(
-            legacy_addr,
-            "http://demo.subsonic.org/rest/ping?u=guest3&p=guest&v=1.8.0&c=sunk&f=json&"
+            sent,
+            vec![
+                "GET /rest/getOpenSubsonicExtensions",
+                "GET /rest/ping",
+                "GET /rest/ping"
+            ]
+        );
+
+        // Once the server is known not to support keys, it isn't asked again.
+        assert!(!cli.detect_auth().unwrap());
+        cli.ping().unwrap();
+        let sent = requests.try_iter().map(path).collect::<Vec<_>>();
+        assert_eq!(
+            sent,
+            vec!["GET /rest/getOpenSubsonicExtensions", "GET /rest/ping"]
+        );
+        assert!(!cli.detect_auth().unwrap());
+        assert!(requests.recv().is_ok());
+    }
+
+    #[test]
+    fn redirect_keeps_api_key_auth() {
+        let moved = "HTTP/1.1 301 Moved Permanently\r\nLocation: /moved/rest/ping\r\n\
+                     Content-Length: 0\r\nConnection: close\r\n\r\n";
+        let kept = "HTTP/1.1 302 Found\r\nLocation: /again/rest/ping?apiKey=s3cr3t&v=1.14.0&c=sunk&f=json\r\n\
+                    Content-Length: 0\r\nConnection: close\r\n\r\n";
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) =
+            test_util::recording_mock_server(&[moved.to_string(), kept.to_string(), ok]);
+        let cli = Client::with_api_key(&site, "s3cr3t").unwrap();
+
+        cli.ping().unwrap();
+        let _ = requests.recv().unwrap();
+        let redirected = requests.recv().unwrap();
+        let query = redirected.split_whitespace().nth(1).unwrap();
+        assert!(query.starts_with("/moved/rest/ping?"));
+        assert_eq!(query.matches("apiKey=").count(), 1);
+        assert_eq!(query.matches("v=").count(), 1);
+
+        let again = requests.recv().unwrap();
+        let query = again.split_whitespace().nth(1).unwrap();
+        assert_eq!(
+            query,
+            "/again/rest/ping?apiKey=s3cr3t&v=1.14.0&c=sunk&f=json"
+        );
+    }
+
+    #[test]
+    fn view_suffix() {
+        let cli = test_util::demo_site().unwrap().with_view_suffix(true);
+
+        let ping = cli.build_url("ping", Query::none()).unwrap();
+        assert!(ping.starts_with("http://demo.subsonic.org/rest/ping.view?"));
+        let hls = cli.build_url("hls.m3u8", Query::none()).unwrap();
+        assert!(hls.starts_with("http://demo.subsonic.org/rest/hls.m3u8?"));
+    }
+
+    #[test]
+    fn cover_art_placeholder() {
+        let image = |disposition: &str, body: &str| {
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n{}\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                disposition,
+                body.len(),
+                body
+            )
+        };
+        let site = test_util::mock_server(&[
+            image("", "ART!"),
+            image(
+                "Content-Disposition: inline; filename=placeholder.png\r\n",
+                "PNG!",
+            ),
+            image("", "NONE"),
+        ]);
+        let cli = Client::new(&site, "user", "pass")
+            .unwrap()
+            .with_placeholder_image(b"NONE");
+
+        let art = cli.cover_art("al-1", 300).unwrap();
+        assert_eq!(art.bytes, b"ART!".to_vec());
+        assert!(!art.is_placeholder);
+        assert!(cli.cover_art("al-2", 300).unwrap().is_placeholder);
+        assert!(cli.cover_art("al-3", None).unwrap().is_placeholder);
+    }
+
+    #[test]
+    fn retry_after_unavailable() {
+        let unavailable = "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 2\r\n\
+                           Content-Length: 0\r\nConnection: close\r\n\r\n";
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let site = test_util::mock_server(&[unavailable.to_string(), ok]);
+        let cli = Client::builder(&site, "user", "pass")
+            .max_retries(1)
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        cli.ping().unwrap();
+        assert!(start.elapsed() >= Duration::from_secs(2));
+
+        let site = test_util::mock_server(&[unavailable]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        assert!(matches!(
+            cli.ping(),
+            Err(Error::Connection(StatusCode::SERVICE_UNAVAILABLE))
+        ));
+    }
+
+    #[test]
+    fn parse_retry_after() {
+        let header = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+            retry_after(&headers)
+        };
+
+        assert_eq!(header("120"), Some(Duration::from_secs(120)));
+        assert_eq!(
+            header("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::from_secs(0))
+        );
+        let later = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(30));
+        assert!(header(&later).unwrap() > Duration::from_secs(25));
+        assert_eq!(header("soon"), None);
+    }
+
+    #[test]
+    fn images_not_throttled() {
+        let body = |content_type: &str| {
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\n\
+                 Content-Length: 3072\r\nConnection: close\r\n\r\n{}",
+                content_type,
+                "x".repeat(3072)
+            )
+        };
+        let image = body("image/png");
+        let site =
+            test_util::mock_server(&[image.clone(), image.clone(), image, body("audio/mpeg")]);
+        let cli = Client::builder(&site, "user", "pass")
+            .max_transfer_rate(1)
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        cli.get_image("getCoverArt", Query::with("id", 1)).unwrap();
+        cli.cover_art("1", None).unwrap();
+        cli.get_image("getAvatar", Query::with("username", "guest"))
+            .unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        let start = Instant::now();
+        cli.get_bytes("stream", Query::with("id", 1)).unwrap();
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn cover_art_format_hint() {
+        let image = "HTTP/1.1 200 OK\r\nContent-Type: image/jpeg\r\n\
+                     Content-Length: 4\r\nConnection: close\r\n\r\nJPG!";
+        let (site, requests) = test_util::recording_mock_server(&[image]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let art = cli
+            .cover_art_as("al-1", ImageSize::Small, ImageFormat::Jpeg)
+            .unwrap();
+        assert_eq!(art.bytes, b"JPG!".to_vec());
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/getCoverArt?"));
+        assert!(request.contains("size=300"));
+        assert!(request.contains("format=jpeg"));
+    }
+
+    #[test]
+    fn scrobble_submission() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok.clone(), ok]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        cli.now_playing_notify(27).unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/scrobble?"));
+        assert!(request.contains("id=27&submission=false"));
+
+        cli.scrobble_play(27, 1_500_000_000_000).unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.contains("id=27&time=1500000000000&submission=true"));
+    }
+
+    #[test]
+    fn get_indexes() {
+        let body = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.14.0",
+                "indexes": {
+                    "lastModified": 1237646148000,
+                    "ignoredArticles": "The El La",
+                    "index": [ { "name": "A", "artist": [ { "id": "1", "name": "ABBA" } ] } ]
+                }
+            }}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[body]);
+        let cli = Client::new(&site, "user", "pass")
+            .unwrap()
+            .with_default_folder(Id::from(3));
+
+        let indexes = cli.indexes(None, 1_237_646_000_000).unwrap();
+        assert_eq!(indexes.ignored_articles, "The El La");
+        assert_eq!(indexes.index[0].artists[0].name, "ABBA");
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/getIndexes?"));
+        assert!(request.contains("musicFolderId=3&ifModifiedSince=1237646000000"));
+    }
+
+    #[test]
+    fn search_folders() {
+        let body = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.14.0",
+                "searchResult2": {
+                    "artist": [ { "id": "1", "name": "Misteur Valaire" } ],
+                    "album": [ { "id": "11", "parent": "1", "isDir": true, "title": "Bellevue" } ],
+                    "song": [ {
+                        "id": "27",
+                        "parent": "11",
+                        "isDir": false,
+                        "title": "Bellevue Avenue",
+                        "size": 5400185,
+                        "contentType": "audio/mpeg",
+                        "suffix": "mp3",
+                        "path": "Misteur Valaire/Bellevue/01 - Bellevue Avenue.mp3",
+                        "type": "music"
+                    }, {
+                        "id": "460",
+                        "parent": "11",
+                        "isDir": false,
+                        "title": "Bellevue (Live)",
+                        "size": 52464391,
+                        "contentType": "video/mp4",
+                        "suffix": "mp4",
+                        "duration": 281,
+                        "bitRate": 1488,
+                        "path": "Misteur Valaire/Bellevue/Live.mp4",
+                        "isVideo": true,
+                        "created": "2017-03-12T11:06:30.000Z",
+                        "type": "video"
+                    } ]
+                }
+            }}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[body]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let page = SearchPage::new();
+        let result = cli.search2("bellevue", page, page, page).unwrap();
+        assert_eq!(result.artists[0].name, "Misteur Valaire");
+        assert_eq!(result.albums[0].name, "Bellevue");
+        assert!(result.songs[0].is_song());
+        assert_eq!(result.songs[1].as_video().unwrap().title, "Bellevue (Live)");
+        assert_eq!(SearchResult::from(result).songs.len(), 1);
+        assert!(requests.recv().unwrap().starts_with("GET /rest/search2?"));
+    }
+
+    #[test]
+    fn starred_endpoints() {
+        let starred = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.14.0",
+                "starred": {
+                    "artist": [ { "id": "1", "name": "Misteur Valaire",
+                                  "starred": "2017-06-01T19:48:25.635Z" } ]
+                }
+            }}"#,
+        );
+        let starred2 = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.14.0",
+                "starred2": {
+                    "album": [ { "id": "1", "name": "Bellevue",
+                                 "starred": "2017-06-01T19:48:25.635Z" } ]
+                }
+            }}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[starred, starred2]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let result = cli.starred(None).unwrap();
+        assert!(result.artists[0].is_starred());
+        assert!(result.albums.is_empty());
+        assert!(requests
+            .recv()
+            .unwrap()
+            .starts_with("GET /rest/getStarred?"));
+
+        let result = cli.starred2(None).unwrap();
+        assert!(result.albums[0].is_starred());
+        assert!(result.artists.is_empty());
+        assert!(requests
+            .recv()
+            .unwrap()
+            .starts_with("GET /rest/getStarred2?"));
+    }
+
+    #[test]
+    fn builder_default_folder() {
+        let starred = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0", "starred": {}}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[starred.clone(), starred]);
+        let cli = Client::builder(&site, "user", "pass")
+            .default_folder(Id::from(3))
+            .build()
+            .unwrap();
+        assert_eq!(cli.default_folder(), Some(&Id::from(3)));
+
+        cli.starred(None).unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/getStarred?"));
+        assert!(request.contains("musicFolderId=3"));
+
+        cli.starred(Id::from(5)).unwrap();
+        assert!(requests.recv().unwrap().contains("musicFolderId=5"));
+
+        let cli = cli.with_default_folder(None);
+        assert_eq!(cli.default_folder(), None);
+    }
+
+    #[test]
+    fn salt_is_not_reused() {
+        let cli = test_util::demo_site().unwrap().with_salt_size(8);
+        let salt = |url: &str| {
+            url.split('&')
+                .find_map(|a| a.strip_prefix("s="))
+                .unwrap()
+                .to_string()
+        };
+
+        let first = cli.build_url("ping", Query::none()).unwrap();
+        let second = cli.build_url("ping", Query::none()).unwrap();
+        assert_ne!(first, second);
+        assert_ne!(salt(&first), salt(&second));
+        assert_eq!(salt(&first).len(), 8);
+
+        let cli = cli.with_salt_size(1);
+        let url = cli.build_url("ping", Query::none()).unwrap();
+        assert_eq!(salt(&url).len(), MIN_SALT_SIZE);
+    }
+
+    #[test]
+    fn error_status_is_not_success() {
+        let site = test_util::mock_server(&[
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 9\r\n\r\nnot found",
+            "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 5\r\n\r\noops!",
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let bytes = cli.get_bytes("stream", Query::with("id", 1));
+        assert!(matches!(bytes, Err(Error::Connection(s)) if s.as_u16() == 404));
+        let raw = cli.get_raw("hls", Query::with("id", 1));
+        assert!(matches!(raw, Err(Error::Connection(s)) if s.as_u16() == 500));
+    }
+
+    #[test]
+    fn host_header_override() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok]);
+        let cli = Client::builder(&site, "user", "pass")
+            .host_header("music.example.com")
+            .build()
+            .unwrap();
+
+        cli.ping().unwrap();
+        let request = requests.recv().unwrap().to_lowercase();
+        assert!(request.contains("host: music.example.com\r\n"));
+    }
+
+    #[test]
+    fn foreign_image_skips_server_headers() {
+        let image = "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n\
+                     Content-Length: 4\r\nConnection: close\r\n\r\nPNG!";
+        let (site, server_requests) = test_util::recording_mock_server(&[image]);
+        let (other, other_requests) = test_util::recording_mock_server(&[image]);
+        let cli = Client::builder(&site, "user", "pass")
+            .host_header("music.example.com")
+            .default_header("X-Proxy-Token", "secret")
+            .build()
+            .unwrap();
+
+        cli.image_bytes(&format!("{}/img/1.png", other)).unwrap();
+        let request = other_requests.recv().unwrap().to_lowercase();
+        let host = other.trim_start_matches("http://");
+        assert!(request.contains(&format!("host: {}\r\n", host)));
+        assert!(!request.contains("music.example.com"));
+        assert!(!request.contains("x-proxy-token"));
+
+        cli.image_bytes("/img/1.png").unwrap();
+        let request = server_requests.recv().unwrap().to_lowercase();
+        assert!(request.contains("host: music.example.com\r\n"));
+        assert!(request.contains("x-proxy-token: secret\r\n"));
+    }
+
+    #[test]
+    fn default_headers() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok]);
+        let cli = Client::builder(&site, "user", "pass")
+            .default_header("X-Forwarded-For", "10.0.0.1")
+            .default_header("X-Proxy-Token", "secret")
+            .build()
+            .unwrap();
+
+        cli.ping().unwrap();
+        let request = requests.recv().unwrap().to_lowercase();
+        assert!(request.contains("x-forwarded-for: 10.0.0.1\r\n"));
+        assert!(request.contains("x-proxy-token: secret\r\n"));
+
+        let invalid = Client::builder(&site, "user", "pass")
+            .default_header("Bad Header", "value")
+            .build();
+        assert!(matches!(invalid, Err(Error::Other("invalid header name"))));
+    }
+
+    #[test]
+    fn default_header_map() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok]);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            HeaderValue::from_static("Basic dXNlcjpwYXNz"),
+        );
+        headers.insert("cf-access-token", HeaderValue::from_static("old"));
+        let cli = Client::builder(&site, "user", "pass")
+            .default_header("CF-Access-Token", "replaced")
+            .default_headers(headers)
+            .default_header("CF-Access-Token", "abc")
+            .build()
+            .unwrap();
+
+        cli.ping().unwrap();
+        let request = requests.recv().unwrap().to_lowercase();
+        assert!(request.contains("authorization: basic dxnlcjpwyxnz\r\n"));
+        assert!(request.contains("cf-access-token: abc\r\n"));
+        assert!(!request.contains("cf-access-token: old"));
+    }
+
+    #[test]
+    fn user_agent_and_proxy() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (proxy, requests) = test_util::recording_mock_server(&[ok]);
+        let cli = Client::builder("http://music.example.com", "user", "pass")
+            .user_agent("sunk-tests/1.0")
+            .proxy(&proxy)
+            .build()
+            .unwrap();
+
+        cli.ping().unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET http://music.example.com/rest/ping?"));
+        assert!(request
+            .to_lowercase()
+            .contains("user-agent: sunk-tests/1.0\r\n"));
+    }
+
+    #[test]
+    fn custom_http_client() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok]);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-custom", HeaderValue::from_static("yes"));
+        let http = ReqwestClient::builder()
+            .redirect(RedirectPolicy::none())
+            .default_headers(headers)
+            .build()
+            .unwrap();
+        let cli = Client::builder(&site, "user", "pass")
+            .http_client(http)
+            .build()
+            .unwrap();
+
+        cli.ping().unwrap();
+        assert!(requests.recv().unwrap().contains("x-custom: yes\r\n"));
+    }
+
+    #[test]
+    fn tls_options() {
+        let cli = Client::builder("https://music.example.com", "user", "pass")
+            .danger_accept_invalid_certs(true)
+            .build();
+        assert!(cli.is_ok());
+
+        let invalid = Client::builder("https://music.example.com", "user", "pass")
+            .add_root_certificate(b"not a certificate")
+            .build();
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn redirect_keeps_auth() {
+        let moved = "HTTP/1.1 301 Moved Permanently\r\nLocation: /moved/rest/ping\r\n\
+                     Content-Length: 0\r\nConnection: close\r\n\r\n";
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[moved.to_string(), ok]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        cli.ping().unwrap();
+        let _ = requests.recv().unwrap();
+        let redirected = requests.recv().unwrap();
+        assert!(redirected.starts_with("GET /moved/rest/ping?"));
+        assert!(redirected.contains("u=user"));
+    }
+
+    #[test]
+    fn redirect_to_other_host_refused() {
+        let moved = "HTTP/1.1 302 Found\r\nLocation: http://example.com/rest/ping\r\n\
+                     Content-Length: 0\r\nConnection: close\r\n\r\n";
+        let site = test_util::mock_server(&[moved]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        assert!(matches!(cli.ping(), Err(Error::Other(_))));
+    }
+
+    #[test]
+    fn metadata_timeout() {
+        use std::net::TcpListener;
+        use std::thread;
+
+        // Accepts the connection but never responds.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let site = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let _conn = listener.accept();
+            thread::sleep(Duration::from_secs(5));
+        });
+        let cli = Client::builder(&site, "user", "pass")
+            .metadata_timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        assert!(cli.ping().is_err());
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn ping_latency() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let site = test_util::mock_server(&[ok]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        assert!(cli.ping_latency().unwrap() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn ping_invalid_version() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "unknown"}}"#,
+        );
+        let site = test_util::mock_server(&[ok]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let info = cli.ping().unwrap();
+        assert_eq!(info.version, None);
+        assert_eq!(cli.server_version(), None);
+        assert_eq!(cli.target_version(), Version::from("1.14.0"));
+    }
+
+    #[test]
+    fn ping_server_info() {
+        let subsonic = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.15.0"}}"#,
+        );
+        let navidrome = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.16.1",
+                "type": "navidrome",
+                "serverVersion": "0.53.3 (13af8ed4)",
+                "openSubsonic": true
+            }}"#,
+        );
+        let site = test_util::mock_server(&[subsonic, navidrome]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let info = cli.ping().unwrap();
+        assert_eq!(info.version, Some(Version::from("1.15.0")));
+        assert_eq!(info.server_type, None);
+        assert!(!info.open_subsonic);
+
+        let info = cli.ping().unwrap();
+        assert_eq!(info.version, Some(Version::from("1.16.1")));
+        assert_eq!(info.server_type.as_deref(), Some("navidrome"));
+        assert_eq!(info.server_version.as_deref(), Some("0.53.3 (13af8ed4)"));
+        assert!(info.open_subsonic);
+    }
+
+    #[test]
+    fn version_negotiated_on_ping() {
+        let rejected = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "failed",
+                "version": "1.12.0",
+                "error": { "code": 41, "message": "Token authentication not supported" }
+            }}"#,
+        );
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.12.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[rejected.clone(), ok, rejected]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        assert_eq!(cli.server_version(), None);
+
+        cli.ping().unwrap();
+        assert!(requests.recv().unwrap().contains("&t="));
+        assert!(requests.recv().unwrap().contains("u=user&p=pass&v=1.12.0"));
+        assert_eq!(cli.server_version(), Some(Version::from("1.12.0")));
+        assert_eq!(cli.target_version(), Version::from("1.12.0"));
+
+        // A manually set target is left alone.
+        let cli = Client::new(&site, "user", "pass")
+            .unwrap()
+            .with_target("1.14.0".into());
+        assert!(cli.ping().is_err());
+        assert_eq!(cli.server_version(), Some(Version::from("1.12.0")));
+        assert_eq!(cli.target_version(), Version::from("1.14.0"));
+    }
+
+    #[test]
+    fn detect_target_lowers_target() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.13.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok.clone(), ok]);
+        let cli = Client::new(&site, "user", "pass")
+            .unwrap()
+            .with_target("1.16.1".into());
+
+        assert_eq!(cli.detect_target().unwrap(), Version::from("1.13.0"));
+        assert!(requests.recv().unwrap().contains("&v=1.16.1"));
+        assert_eq!(cli.target_version(), Version::from("1.13.0"));
+        assert!(cli
+            .build_url("getAlbum", Query::with("id", 1))
+            .unwrap()
+            .contains("&v=1.13.0"));
+
+        cli.ping().unwrap();
+        assert!(requests.recv().unwrap().contains("&v=1.13.0"));
+    }
+
+    #[test]
+    fn server_capabilities() {
+        let extensions = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.16.1",
+                "openSubsonic": true,
+                "openSubsonicExtensions": [
+                    { "name": "songLyrics", "versions": [1] },
+                    { "name": "transcodeOffset", "versions": [1] },
+                    { "name": "sonicSimilarity", "versions": [1, 2] }
+                ]
+            }}"#,
+        );
+        let unsupported = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "failed",
+                "version": "1.15.0",
+                "error": { "code": 0, "message": "Unknown method" }
+            }}"#,
+        );
+        let not_found = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let down = "HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let (site, requests) = test_util::recording_mock_server(&[
+            extensions.clone(),
+            extensions,
+            unsupported.clone(),
+            unsupported,
+            not_found.to_string(),
+            down.to_string(),
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let list = cli.extensions().unwrap();
+        assert!(requests
+            .recv()
+            .unwrap()
+            .starts_with("GET /rest/getOpenSubsonicExtensions?"));
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[2].versions, vec![1, 2]);
+
+        let caps = cli.capabilities().unwrap();
+        assert!(caps.open_subsonic);
+        assert!(caps.supports_song_lyrics);
+        assert!(caps.supports_transcode_offset);
+        assert!(!caps.supports_api_key_auth);
+        assert!(caps.supports("sonicSimilarity"));
+
+        assert!(cli.extensions().is_err());
+        assert_eq!(cli.capabilities().unwrap(), Capabilities::default());
+
+        assert_eq!(cli.capabilities().unwrap(), Capabilities::default());
+        assert!(matches!(
+            cli.capabilities(),
+            Err(Error::Connection(StatusCode::BAD_GATEWAY))
+        ));
+    }
+
+    #[test]
+    fn unsupported_endpoint_not_requested() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        // Nothing is known about the server before it is pinged.
+        assert!(cli.build_url("startScan", Query::none()).is_ok());
+
+        cli.ping().unwrap();
+        let _ = requests.recv().unwrap();
+        match cli.scan_library() {
+            Err(Error::Unsupported {
+                endpoint,
+                required,
+                actual,
+            }) => {
+                assert_eq!(endpoint, "startScan");
+                assert_eq!(required, Version::from("1.15.0"));
+                assert_eq!(actual, Version::from("1.14.0"));
+            }
+            res => panic!("expected unsupported endpoint, got {:?}", res),
+        }
+        assert!(requests.try_recv().is_err());
+
+        let legacy = Client::new(&site, "user", "pass")
+            .unwrap()
+            .with_target("1.7.0".into());
+        assert!(matches!(
+            legacy.build_url("search3", Query::none()),
+            Err(Error::Unsupported { .. })
+        ));
+        assert!(legacy.build_url("search2", Query::none()).is_ok());
+
+        let original = Client::new(&site, "user", "pass")
+            .unwrap()
+            .with_target("1.1.0".into());
+        assert!(original.build_url("getNowPlaying", Query::none()).is_ok());
+    }
+
+    #[test]
+    fn required_value_missing() {
+        let empty = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let site = test_util::mock_server(&[empty]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let album = crate::Album::get(&cli, 1);
+        assert!(matches!(album, Err(Error::Other("empty response"))));
+    }
+
+    #[test]
+    fn response_progress() {
+        use std::sync::{Arc, Mutex};
+
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
         );
+        let len = ok.split("\r\n\r\n").nth(1).unwrap().len() as u64;
+        let site = test_util::mock_server(&[ok]);
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let log = reports.clone();
+        let cli = Client::new(&site, "user", "pass")
+            .unwrap()
+            .with_response_progress(move |got, total| log.lock().unwrap().push((got, total)));
+
+        cli.ping().unwrap();
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.last(), Some(&(len, Some(len))));
+    }
+
+    #[test]
+    fn search_falls_back() {
+        let upgrade = |code| {
+            test_util::json_response(&format!(
+                r#"{{"subsonic-response": {{
+                    "status": "failed",
+                    "version": "1.2.0",
+                    "error": {{ "code": {}, "message": "Incompatible protocol" }}
+                }}}}"#,
+                code
+            ))
+        };
+        let song = r#"{
+            "id": "27",
+            "title": "Bellevue Avenue",
+            "size": 5400185,
+            "contentType": "audio/mpeg",
+            "suffix": "mp3",
+            "path": "Misteur Valaire/Bellevue/01 - Bellevue Avenue.mp3",
+            "type": "music"
+        }"#;
+
+        let site = test_util::mock_server(&[
+            upgrade(30),
+            test_util::json_response(&format!(
+                r#"{{"subsonic-response": {{
+                    "status": "ok",
+                    "version": "1.4.0",
+                    "searchResult2": {{
+                        "artist": [ {{ "id": "1", "name": "Misteur Valaire" }} ],
+                        "album": [ {{
+                            "id": "25",
+                            "parent": "1",
+                            "isDir": true,
+                            "title": "Bellevue",
+                            "artist": "Misteur Valaire"
+                        }} ],
+                        "song": [ {} ]
+                    }}
+                }}}}"#,
+                song
+            )),
+            upgrade(30),
+            upgrade(20),
+            test_util::json_response(&format!(
+                r#"{{"subsonic-response": {{
+                    "status": "ok",
+                    "version": "1.2.0",
+                    "searchResult": {{
+                        "offset": 0,
+                        "totalHits": 1,
+                        "match": [ {} ]
+                    }}
+                }}}}"#,
+                song
+            )),
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let s = SearchPage::new();
+
+        let r = cli.search_compat("bellevue", s, s, s).unwrap();
+        assert_eq!(r.artists[0].name, "Misteur Valaire");
+        assert_eq!(r.albums[0].name, "Bellevue");
+        assert_eq!(r.songs[0].id, "27");
+
+        let r = cli.search_compat("bellevue", s, s, s).unwrap();
+        assert!(r.artists.is_empty());
+        assert!(r.albums.is_empty());
+        assert_eq!(r.songs[0].id, "27");
     }
 
     #[test]
@@ -433,15 +3693,90 @@ mod tests {
         let s = SearchPage::new().with_size(1);
         let r = cli.search("dada", s, s, s).unwrap();
 
-        assert_eq!(r.artists[0].id, 14);
+        assert_eq!(r.artists[0].id, "14");
         assert_eq!(r.artists[0].name, String::from("The Dada Weatherman"));
         assert_eq!(r.artists[0].album_count, 4);
 
-        assert_eq!(r.albums[0].id, 23);
+        assert_eq!(r.albums[0].id, "23");
         assert_eq!(r.albums[0].name, String::from("The Green Waltz"));
 
-        assert_eq!(r.songs[0].id, 222);
+        assert_eq!(r.songs[0].id, "222");
 
         // etc.
     }
+
+    fn text_response(body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    fn media_playlist(segments: &[&str]) -> String {
+        let mut playlist = String::from("#EXTM3U\n#EXT-X-VERSION:1\n#EXT-X-TARGETDURATION:10\n");
+        for segment in segments {
+            playlist.push_str(&format!("#EXTINF:10,\n{}\n", segment));
+        }
+        playlist.push_str("#EXT-X-ENDLIST\n");
+        playlist
+    }
+
+    #[test]
+    fn hls_segments_from_master() {
+        let master = "#EXTM3U
+#EXT-X-VERSION:1
+#EXT-X-STREAM-INF:PROGRAM-ID=1,BANDWIDTH=256000
+/rest/hls.m3u8?id=1&bitRate=256
+#EXT-X-STREAM-INF:PROGRAM-ID=1,BANDWIDTH=128000
+/rest/hls.m3u8?id=1&bitRate=128
+"
+        .parse::<HlsPlaylist>()
+        .unwrap();
+        let (site, requests) = test_util::recording_mock_server(&[
+            text_response(&media_playlist(&["/seg/0.ts", "/seg/1.ts"])),
+            text_response("first"),
+            text_response("second"),
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let mut segments = cli.hls_segment_stream(&master, 200_000).unwrap();
+        assert!(requests
+            .recv()
+            .unwrap()
+            .starts_with("GET /rest/hls.m3u8?id=1&bitRate=128 "));
+        assert_eq!(segments.size_hint(), (2, Some(2)));
+
+        assert_eq!(segments.next().unwrap().unwrap(), b"first");
+        assert!(requests.recv().unwrap().starts_with("GET /seg/0.ts "));
+        assert_eq!(segments.next().unwrap().unwrap(), b"second");
+        assert!(requests.recv().unwrap().starts_with("GET /seg/1.ts "));
+        assert!(segments.next().is_none());
+    }
+
+    #[test]
+    fn hls_segment_error_surfaced() {
+        let playlist = media_playlist(&["/seg/0.ts", "/seg/1.ts", "/seg/2.ts"])
+            .parse::<HlsPlaylist>()
+            .unwrap();
+        let site = test_util::mock_server(&[
+            text_response("first"),
+            "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_string(),
+            text_response("third"),
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let segments = cli
+            .hls_segment_stream(&playlist, None)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].as_ref().unwrap(), b"first");
+        assert!(matches!(
+            segments[1],
+            Err(Error::Connection(StatusCode::INTERNAL_SERVER_ERROR))
+        ));
+        assert_eq!(segments[2].as_ref().unwrap(), b"third");
+    }
 }
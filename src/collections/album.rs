@@ -3,11 +3,12 @@
 use std::{fmt, result};
 
 use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
 use serde_json;
 
-use crate::query::{Arg, IntoArg, Query};
+use crate::query::{self, Arg, IntoArg, Query};
 use crate::search::SearchPage;
-use crate::{Client, Error, Media, Result, Song};
+use crate::{Client, Error, Id, Media, Result, Song};
 
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy)]
@@ -55,16 +56,42 @@ impl IntoArg for ListType {
 #[derive(Debug, Clone)]
 #[readonly::make]
 pub struct Album {
-    pub id: u64,
+    pub id: Id,
     pub name: String,
     pub artist: Option<String>,
-    pub artist_id: Option<u64>,
+    pub artist_id: Option<Id>,
     pub cover_id: Option<String>,
     pub duration: u64,
     pub year: Option<u64>,
     pub genre: Option<String>,
+    pub musicbrainz_id: Option<String>,
     pub song_count: u64,
     pub songs: Vec<Song>,
+    /// When the current user starred the album (as an ISO8601 timestamp), if
+    /// they have.
+    pub starred: Option<String>,
+    /// Labels the album was released on. Only provided by OpenSubsonic
+    /// servers.
+    pub record_labels: Vec<String>,
+    /// Moods associated with the album. Only provided by OpenSubsonic
+    /// servers.
+    pub moods: Vec<String>,
+    /// The kinds of release the album is, such as "Album", "EP" or "Single".
+    /// Only provided by OpenSubsonic servers.
+    pub release_types: Vec<String>,
+    /// The date the album was originally released, for reissues. Only
+    /// provided by OpenSubsonic servers.
+    pub original_release_date: Option<ReleaseDate>,
+}
+
+/// A possibly partial release date. Any part may be missing if it is not
+/// known.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseDate {
+    pub year: Option<u32>,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
 }
 
 impl Album {
@@ -74,24 +101,71 @@ impl Album {
     ///
     /// Aside from errors the `Client` may cause, the method will error if
     /// there is no album matching the provided ID.
-    pub fn get(client: &Client, id: usize) -> Result<Album> {
-        self::get_album(client, id as u64)
+    pub fn get<I: Into<Id>>(client: &Client, id: I) -> Result<Album> {
+        self::get_album(client, id.into())
+    }
+
+    /// Returns whether the current user has starred the album.
+    pub fn is_starred(&self) -> bool {
+        self.starred.is_some()
+    }
+
+    /// Returns when the current user starred the album, if they have.
+    #[cfg(feature = "chrono")]
+    pub fn starred_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.starred.as_deref().and_then(|s| s.parse().ok())
     }
 
     /// Lists all albums on the server. Supports paging.
-    pub fn list(
+    ///
+    /// The server returns at most 500 albums at a time; a larger page size is
+    /// clamped to 500. Use paging to list more albums than that.
+    ///
+    /// If `folder` is `None`, the client's default folder is used, if set.
+    pub fn list<U>(
+        client: &Client,
+        list_type: ListType,
+        page: SearchPage,
+        folder: U,
+    ) -> Result<Vec<Album>>
+    where
+        U: Into<Option<Id>>,
+    {
+        let (size, offset) = (Some(page.count), Some(page.result_offset()));
+        self::get_albums(client, list_type, size, offset, folder.into())
+    }
+
+    /// Lists album directories on the server, for libraries organised by
+    /// folder rather than by tags. Supports paging.
+    ///
+    /// This is the folder-based counterpart of [`list`], and takes the same
+    /// arguments. Each album returned is a directory: its ID is the
+    /// directory's, its name is the directory's title, and it holds no songs.
+    ///
+    /// [`list`]: #method.list
+    pub fn list_directories<U>(
         client: &Client,
         list_type: ListType,
         page: SearchPage,
-        folder: usize,
-    ) -> Result<Vec<Album>> {
-        self::get_albums(client, list_type, page.count, page.offset, folder)
+        folder: U,
+    ) -> Result<Vec<Album>>
+    where
+        U: Into<Option<Id>>,
+    {
+        let folder_id = client.folder_or_default(folder.into());
+        let args = list_args(
+            list_type,
+            Some(page.count),
+            Some(page.result_offset()),
+            folder_id,
+        );
+        client.get_list("getAlbumList", args, "albumList", "album")
     }
 
     /// Returns all songs in the album.
     pub fn songs(&self, client: &Client) -> Result<Vec<Song>> {
         if self.songs.len() as u64 != self.song_count {
-            Ok(self::get_album(client, self.id)?.songs)
+            Ok(self::get_album(client, self.id.clone())?.songs)
         } else {
             Ok(self.songs.clone())
         }
@@ -99,7 +173,7 @@ impl Album {
 
     /// Returns detailed information about the album.
     pub fn info(&self, client: &Client) -> Result<AlbumInfo> {
-        let res = client.get("getArtistInfo", Query::with("id", self.id))?;
+        let res = client.get_value("getArtistInfo", Query::with("id", self.id.as_str()))?;
         Ok(serde_json::from_value(res)?)
     }
 }
@@ -130,37 +204,125 @@ impl<'de> Deserialize<'de> for Album {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Album {
-            id: String,
+            id: Id,
+            // Directory-based results (such as `search2`) use `title`.
+            #[serde(alias = "title")]
             name: String,
             artist: Option<String>,
-            artist_id: Option<String>,
+            artist_id: Option<Id>,
             cover_art: Option<String>,
+            #[serde(default)]
             song_count: u64,
+            #[serde(default)]
             duration: u64,
             // created: String,
             year: Option<u64>,
             genre: Option<String>,
+            music_brainz_id: Option<String>,
+            starred: Option<String>,
             #[serde(default)]
             song: Vec<Song>,
+            // OpenSubsonic extensions.
+            #[serde(default)]
+            genres: Vec<Named>,
+            #[serde(default)]
+            record_labels: Vec<Named>,
+            #[serde(default)]
+            moods: Vec<String>,
+            #[serde(default)]
+            release_types: Vec<String>,
+            original_release_date: Option<ReleaseDate>,
+        }
+
+        #[derive(Deserialize)]
+        struct Named {
+            name: String,
         }
 
         let raw = _Album::deserialize(de)?;
+        // OpenSubsonic servers may only list genres in `genres`.
+        let genre = raw
+            .genre
+            .or_else(|| raw.genres.into_iter().next().map(|g| g.name));
 
         Ok(Album {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             name: raw.name,
             artist: raw.artist,
-            artist_id: raw.artist_id.map(|i| i.parse().unwrap()),
+            artist_id: raw.artist_id,
             cover_id: raw.cover_art,
             duration: raw.duration,
             year: raw.year,
-            genre: raw.genre,
+            genre,
+            musicbrainz_id: raw.music_brainz_id,
             song_count: raw.song_count,
             songs: raw.song,
+            starred: raw.starred,
+            record_labels: raw.record_labels.into_iter().map(|l| l.name).collect(),
+            moods: raw.moods,
+            release_types: raw.release_types,
+            original_release_date: raw.original_release_date,
         })
     }
 }
 
+impl Serialize for Album {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Album<'a> {
+            id: &'a Id,
+            name: &'a str,
+            artist: &'a Option<String>,
+            artist_id: &'a Option<Id>,
+            cover_art: &'a Option<String>,
+            song_count: u64,
+            duration: u64,
+            year: Option<u64>,
+            genre: &'a Option<String>,
+            music_brainz_id: &'a Option<String>,
+            starred: &'a Option<String>,
+            song: &'a [Song],
+            record_labels: Vec<Named<'a>>,
+            moods: &'a [String],
+            release_types: &'a [String],
+            original_release_date: Option<ReleaseDate>,
+        }
+
+        #[derive(Serialize)]
+        struct Named<'a> {
+            name: &'a str,
+        }
+
+        _Album {
+            id: &self.id,
+            name: &self.name,
+            artist: &self.artist,
+            artist_id: &self.artist_id,
+            cover_art: &self.cover_id,
+            song_count: self.song_count,
+            duration: self.duration,
+            year: self.year,
+            genre: &self.genre,
+            music_brainz_id: &self.musicbrainz_id,
+            starred: &self.starred,
+            song: &self.songs,
+            record_labels: self
+                .record_labels
+                .iter()
+                .map(|name| Named { name })
+                .collect(),
+            moods: &self.moods,
+            release_types: &self.release_types,
+            original_release_date: self.original_release_date,
+        }
+        .serialize(se)
+    }
+}
+
 impl Media for Album {
     fn has_cover_art(&self) -> bool {
         self.cover_id.is_some()
@@ -174,14 +336,12 @@ impl Media for Album {
         let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
         let query = Query::with("id", cover).arg("size", size.into()).build();
 
-        client.get_bytes("getCoverArt", query)
+        client.get_image("getCoverArt", query)
     }
 
     fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String> {
         let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
-
-        client.build_url("getCoverArt", query)
+        client.cover_art_url(cover, size)
     }
 }
 
@@ -225,30 +385,70 @@ impl<'de> Deserialize<'de> for AlbumInfo {
     }
 }
 
-fn get_album(client: &Client, id: u64) -> Result<Album> {
-    let res = client.get("getAlbum", Query::with("id", id))?;
-    Ok(serde_json::from_value::<Album>(res)?)
+impl Serialize for AlbumInfo {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _AlbumInfo<'a> {
+            notes: &'a str,
+            music_brainz_id: &'a str,
+            last_fm_url: &'a str,
+            small_image_url: &'a str,
+            medium_image_url: &'a str,
+            large_image_url: &'a str,
+        }
+        _AlbumInfo {
+            notes: &self.notes,
+            music_brainz_id: &self.musicbrainz_id,
+            last_fm_url: &self.lastfm_url,
+            small_image_url: &self.image_urls.0,
+            medium_image_url: &self.image_urls.1,
+            large_image_url: &self.image_urls.2,
+        }
+        .serialize(se)
+    }
+}
+
+fn get_album(client: &Client, id: Id) -> Result<Album> {
+    client.get_required("getAlbum", Query::with("id", id), &["album"])
 }
 
-fn get_albums<U>(
+/// The maximum number of albums the server will return in a single list.
+const MAX_LIST_SIZE: usize = 500;
+
+fn get_albums<U, F>(
     client: &Client,
     list_type: ListType,
     size: U,
     offset: U,
-    folder_id: U,
+    folder_id: F,
 ) -> Result<Vec<Album>>
 where
     U: Into<Option<usize>>,
+    F: Into<Option<Id>>,
 {
-    let args = Query::new()
-        .arg("type", list_type)
-        .arg("size", size.into())
-        .arg("offset", offset.into())
-        .arg("musicFolderId", folder_id.into())
-        .build();
+    let folder_id = client.folder_or_default(folder_id.into());
+    let args = list_args(list_type, size.into(), offset.into(), folder_id);
+    client.get_list("getAlbumList2", args, "albumList2", "album")
+}
+
+fn list_args(
+    list_type: ListType,
+    size: Option<usize>,
+    offset: Option<usize>,
+    folder_id: Option<Id>,
+) -> Query {
+    let size = size.map(|size| query::clamp_count("albums", size, MAX_LIST_SIZE));
 
-    let album = client.get("getAlbumList2", args)?;
-    Ok(get_list_as!(album, Album))
+    Query::new()
+        .arg("type", list_type)
+        .arg("size", size)
+        .arg("offset", offset)
+        .arg("musicFolderId", folder_id)
+        .build()
 }
 
 #[cfg(test)]
@@ -256,6 +456,57 @@ mod tests {
     use super::*;
     use crate::test_util;
 
+    #[test]
+    fn list_uses_default_folder() {
+        let list = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0", "albumList2": {}}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[list.clone(), list]);
+        let cli = Client::new(&site, "user", "pass")
+            .unwrap()
+            .with_default_folder(Id::from(2));
+
+        let page = SearchPage::new();
+        assert!(Album::list(&cli, ListType::Random, page, None)
+            .unwrap()
+            .is_empty());
+        assert!(requests.recv().unwrap().contains("musicFolderId=2"));
+
+        Album::list(&cli, ListType::Random, page, Id::from(5)).unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.contains("musicFolderId=5"));
+        assert!(!request.contains("musicFolderId=2"));
+    }
+
+    #[test]
+    fn list_album_directories() {
+        let list = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.14.0",
+                "albumList": { "album": [ {
+                    "id": "11",
+                    "parent": "1",
+                    "isDir": true,
+                    "title": "Bellevue",
+                    "artist": "Misteur Valaire",
+                    "coverArt": "11"
+                } ] }
+            }}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[list]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let albums =
+            Album::list_directories(&cli, ListType::Newest, SearchPage::new(), None).unwrap();
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].id, "11");
+        assert_eq!(albums[0].name, "Bellevue");
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/getAlbumList?"));
+        assert!(request.contains("type=newest"));
+    }
+
     #[test]
     fn demo_get_albums() {
         let srv = test_util::demo_site().unwrap();
@@ -264,20 +515,99 @@ mod tests {
         assert!(!albums.is_empty())
     }
 
+    #[test]
+    fn list_size_clamped() {
+        let args = list_args(ListType::Newest, Some(1000), Some(0), None);
+        assert!(args.to_string().contains("size=500"));
+
+        let args = list_args(ListType::Newest, Some(20), None, None);
+        assert!(args.to_string().contains("size=20"));
+    }
+
     #[test]
     fn parse_album() {
         let parsed = serde_json::from_value::<Album>(raw()).unwrap();
 
-        assert_eq!(parsed.id, 1);
+        assert_eq!(parsed.id, "1");
         assert_eq!(parsed.name, String::from("Bellevue"));
         assert_eq!(parsed.song_count, 9);
+        assert_eq!(parsed.musicbrainz_id, None);
+    }
+
+    #[test]
+    fn parse_album_musicbrainz_id() {
+        let mut raw = raw();
+        raw["musicBrainzId"] = "0e2b3b9b-29b3-4b55-9d4e-3b1e6e36b3a1".into();
+        let parsed = serde_json::from_value::<Album>(raw).unwrap();
+
+        assert_eq!(
+            parsed.musicbrainz_id.as_deref(),
+            Some("0e2b3b9b-29b3-4b55-9d4e-3b1e6e36b3a1")
+        );
+    }
+
+    #[test]
+    fn parse_album_opensubsonic() {
+        let parsed = serde_json::from_value::<Album>(raw()).unwrap();
+        assert!(parsed.record_labels.is_empty());
+        assert_eq!(parsed.original_release_date, None);
+
+        let mut raw = raw();
+        raw.as_object_mut().unwrap().remove("genre");
+        raw["genres"] = serde_json::json!([{ "name": "Electronic" }, { "name": "Jazz" }]);
+        raw["recordLabels"] = serde_json::json!([{ "name": "Mr Label" }]);
+        raw["moods"] = serde_json::json!(["upbeat"]);
+        raw["releaseTypes"] = serde_json::json!(["Album", "Remixes"]);
+        raw["originalReleaseDate"] = serde_json::json!({ "year": 2010, "month": 3 });
+        let parsed = serde_json::from_value::<Album>(raw).unwrap();
+
+        assert_eq!(parsed.genre.as_deref(), Some("Electronic"));
+        assert_eq!(parsed.record_labels, vec!["Mr Label"]);
+        assert_eq!(parsed.moods, vec!["upbeat"]);
+        assert_eq!(parsed.release_types, vec!["Album", "Remixes"]);
+        assert_eq!(
+            parsed.original_release_date,
+            Some(ReleaseDate {
+                year: Some(2010),
+                month: Some(3),
+                day: None
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_album_roundtrip() {
+        let mut raw = raw();
+        raw["recordLabels"] = serde_json::json!([{ "name": "Mr Label" }]);
+        let parsed = serde_json::from_value::<Album>(raw).unwrap();
+
+        let value = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(value["coverArt"], "al-1");
+        assert_eq!(value["recordLabels"][0]["name"], "Mr Label");
+
+        let reparsed = serde_json::from_value::<Album>(value.clone()).unwrap();
+        assert_eq!(reparsed.record_labels, vec!["Mr Label"]);
+        assert_eq!(reparsed.songs.len(), parsed.songs.len());
+        assert_eq!(serde_json::to_value(&reparsed).unwrap(), value);
+    }
+
+    #[test]
+    fn parse_album_starred() {
+        let parsed = serde_json::from_value::<Album>(raw()).unwrap();
+        assert!(!parsed.is_starred());
+        assert!(parsed.songs[0].is_starred());
+
+        let mut raw = raw();
+        raw["starred"] = "2017-06-01T19:48:25.635Z".into();
+        let parsed = serde_json::from_value::<Album>(raw).unwrap();
+        assert!(parsed.is_starred());
     }
 
     #[test]
     fn parse_album_deep() {
         let parsed = serde_json::from_value::<Album>(raw()).unwrap();
 
-        assert_eq!(parsed.songs[0].id, 27);
+        assert_eq!(parsed.songs[0].id, "27");
         assert_eq!(parsed.songs[0].title, String::from("Bellevue Avenue"));
         assert_eq!(parsed.songs[0].duration, Some(198));
     }
@@ -3,20 +3,26 @@
 use std::{fmt, result};
 
 use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
 use serde_json;
 
 use crate::query::Query;
-use crate::{Album, Client, Error, Media, Result, Song};
+use crate::{Album, Client, Error, Id, Index, Media, Result, Song};
 
 /// Basic information about an artist.
 #[allow(missing_docs)]
 #[derive(Debug, Clone)]
 pub struct Artist {
-    pub id: usize,
+    pub id: Id,
     pub name: String,
     cover_id: Option<String>,
     albums: Vec<Album>,
+    albums_loaded: bool,
     pub album_count: usize,
+    pub musicbrainz_id: Option<String>,
+    /// When the current user starred the artist (as an ISO8601 timestamp), if
+    /// they have.
+    pub starred: Option<String>,
 }
 
 /// Detailed information about an artist.
@@ -36,22 +42,55 @@ pub struct ArtistInfo {
 
 impl Artist {
     #[allow(missing_docs)]
-    pub fn get(client: &Client, id: usize) -> Result<Artist> {
-        self::get_artist(client, id)
+    pub fn get<I: Into<Id>>(client: &Client, id: I) -> Result<Artist> {
+        self::get_artist(client, id.into())
+    }
+
+    /// Returns whether the current user has starred the artist.
+    pub fn is_starred(&self) -> bool {
+        self.starred.is_some()
+    }
+
+    /// Returns when the current user starred the artist, if they have.
+    #[cfg(feature = "chrono")]
+    pub fn starred_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.starred.as_deref().and_then(|s| s.parse().ok())
+    }
+
+    /// Lists all artists on the server, grouped by the letter they are
+    /// indexed under.
+    ///
+    /// Artists are read from ID3 tags; see [`Client::indexes`] for the
+    /// folder-based equivalent. If `folder_id` is `None`, the client's default
+    /// folder is used, if set.
+    ///
+    /// [`Client::indexes`]: ./struct.Client.html#method.indexes
+    pub fn list<U>(client: &Client, folder_id: U) -> Result<Vec<Index>>
+    where
+        U: Into<Option<Id>>,
+    {
+        let args = Query::with("musicFolderId", client.folder_or_default(folder_id.into()));
+        client.get_list("getArtists", args, "artists", "index")
     }
 
     /// Returns a list of albums released by the artist.
+    ///
+    /// Artists listed in an index don't include their albums, so these are
+    /// fetched from the server. Artists fetched directly (such as through
+    /// [`Artist::get`]) already hold their albums, and no request is made.
+    ///
+    /// [`Artist::get`]: #method.get
     pub fn albums(&self, client: &Client) -> Result<Vec<Album>> {
-        if self.albums.len() != self.album_count {
-            Ok(self::get_artist(client, self.id)?.albums)
-        } else {
+        if self.albums_loaded {
             Ok(self.albums.clone())
+        } else {
+            Ok(self::get_artist(client, self.id.clone())?.albums)
         }
     }
 
     /// Queries last.fm for more information about the artist.
     pub fn info(&self, client: &Client) -> Result<ArtistInfo> {
-        let res = client.get("getArtistInfo", Query::with("id", self.id))?;
+        let res = client.get_value("getArtistInfo", Query::with("id", self.id.as_str()))?;
         Ok(serde_json::from_value(res)?)
     }
 
@@ -71,11 +110,11 @@ impl Artist {
         B: Into<Option<bool>>,
         U: Into<Option<usize>>,
     {
-        let args = Query::with("id", self.id)
+        let args = Query::with("id", self.id.as_str())
             .arg("count", count.into())
             .arg("includeNotPresent", include_not_present.into())
             .build();
-        let res = serde_json::from_value::<ArtistInfo>(client.get("getArtistInfo", args)?)?;
+        let res = serde_json::from_value::<ArtistInfo>(client.get_value("getArtistInfo", args)?)?;
         Ok(res.similar_artists)
     }
 
@@ -84,12 +123,11 @@ impl Artist {
     where
         U: Into<Option<usize>>,
     {
-        let args = Query::with("id", self.id)
+        let args = Query::with("id", self.id.as_str())
             .arg("count", count.into())
             .build();
 
-        let song = client.get("getTopSongs", args)?;
-        Ok(get_list_as!(song, Song))
+        client.get_list("getTopSongs", args, "topSongs", "song")
     }
 }
 
@@ -101,26 +139,67 @@ impl<'de> Deserialize<'de> for Artist {
         #[derive(Debug, Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Artist {
-            id: String,
+            id: Id,
             name: String,
             cover_art: Option<String>,
-            album_count: usize,
             #[serde(default)]
-            album: Vec<Album>,
+            album_count: usize,
+            music_brainz_id: Option<String>,
+            starred: Option<String>,
+            album: Option<Vec<Album>>,
         }
 
         let raw = _Artist::deserialize(de)?;
 
         Ok(Artist {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             name: raw.name,
             cover_id: raw.cover_art,
             album_count: raw.album_count,
-            albums: raw.album,
+            albums_loaded: raw.album.is_some(),
+            albums: raw.album.unwrap_or_default(),
+            musicbrainz_id: raw.music_brainz_id,
+            starred: raw.starred,
         })
     }
 }
 
+impl Serialize for Artist {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Artist<'a> {
+            id: &'a Id,
+            name: &'a str,
+            cover_art: &'a Option<String>,
+            album_count: usize,
+            music_brainz_id: &'a Option<String>,
+            starred: &'a Option<String>,
+            // Left out unless loaded, so that a partial artist reads back as
+            // one.
+            #[serde(skip_serializing_if = "Option::is_none")]
+            album: Option<&'a [Album]>,
+        }
+        _Artist {
+            id: &self.id,
+            name: &self.name,
+            cover_art: &self.cover_id,
+            album_count: self.album_count,
+            music_brainz_id: &self.musicbrainz_id,
+            starred: &self.starred,
+            album: if self.albums_loaded {
+                Some(&self.albums)
+            } else {
+                None
+            },
+        }
+        .serialize(se)
+    }
+}
+
 impl Media for Artist {
     fn has_cover_art(&self) -> bool {
         self.cover_id.is_some()
@@ -134,14 +213,12 @@ impl Media for Artist {
         let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
         let query = Query::with("id", cover).arg("size", size.into()).build();
 
-        client.get_bytes("getCoverArt", query)
+        client.get_image("getCoverArt", query)
     }
 
     fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String> {
         let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
-
-        client.build_url("getCoverArt", query)
+        client.cover_art_url(cover, size)
     }
 }
 
@@ -184,10 +261,42 @@ impl<'de> Deserialize<'de> for ArtistInfo {
     }
 }
 
+impl Serialize for ArtistInfo {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _ArtistInfo<'a> {
+            biography: &'a str,
+            music_brainz_id: &'a str,
+            last_fm_url: &'a str,
+            small_image_url: &'a str,
+            medium_image_url: &'a str,
+            large_image_url: &'a str,
+            similar_artist: &'a [Artist],
+        }
+        _ArtistInfo {
+            biography: &self.biography,
+            music_brainz_id: &self.musicbrainz_id,
+            last_fm_url: &self.lastfm_url,
+            small_image_url: &self.image_urls.0,
+            medium_image_url: &self.image_urls.1,
+            large_image_url: &self.image_urls.2,
+            similar_artist: &self.similar_artists,
+        }
+        .serialize(se)
+    }
+}
+
 /// Fetches an artist from the Subsonic server.
-fn get_artist(client: &Client, id: usize) -> Result<Artist> {
-    let res = client.get("getArtist", Query::with("id", id))?;
-    Ok(serde_json::from_value::<Artist>(res)?)
+fn get_artist(client: &Client, id: Id) -> Result<Artist> {
+    let mut artist: Artist =
+        client.get_required("getArtist", Query::with("id", id), &["artist"])?;
+    // An artist without albums may be sent without an album list at all.
+    artist.albums_loaded = true;
+    Ok(artist)
 }
 
 #[cfg(test)]
@@ -199,9 +308,64 @@ mod tests {
     fn parse_artist() {
         let parsed = serde_json::from_value::<Artist>(raw()).unwrap();
 
-        assert_eq!(parsed.id, 1);
+        assert_eq!(parsed.id, "1");
         assert_eq!(parsed.name, String::from("Misteur Valaire"));
         assert_eq!(parsed.album_count, 1);
+        assert_eq!(parsed.musicbrainz_id, None);
+    }
+
+    #[test]
+    fn parse_artist_musicbrainz_id() {
+        let mut raw = raw();
+        raw["musicBrainzId"] = "b2d4d3f0-7e1c-4d8a-8a8f-2f5b1b6c9e3d".into();
+        let parsed = serde_json::from_value::<Artist>(raw).unwrap();
+
+        assert_eq!(
+            parsed.musicbrainz_id.as_deref(),
+            Some("b2d4d3f0-7e1c-4d8a-8a8f-2f5b1b6c9e3d")
+        );
+    }
+
+    #[test]
+    fn parse_artist_starred() {
+        let parsed = serde_json::from_value::<Artist>(raw()).unwrap();
+        assert!(!parsed.is_starred());
+
+        let mut raw = raw();
+        raw["starred"] = "2017-06-01T19:48:25.635Z".into();
+        let parsed = serde_json::from_value::<Artist>(raw).unwrap();
+        assert!(parsed.is_starred());
+    }
+
+    #[test]
+    fn list_artists() {
+        let body = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.14.0",
+                "artists": {
+                    "ignoredArticles": "The El La",
+                    "index": [ {
+                        "name": "M",
+                        "artist": [ { "id": "1", "name": "Misteur Valaire", "albumCount": 1 } ]
+                    }, {
+                        "name": "T",
+                        "artist": [ { "id": "2", "name": "The Beatles", "albumCount": 12 } ]
+                    } ]
+                }
+            }}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[body]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let index = Artist::list(&cli, Id::from(2)).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index[1].name, "T");
+        assert_eq!(index[1].artists[0].name, "The Beatles");
+        assert_eq!(index[1].artists[0].album_count, 12);
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/getArtists?"));
+        assert!(request.contains("musicFolderId=2"));
     }
 
     #[test]
@@ -209,18 +373,82 @@ mod tests {
         let parsed = serde_json::from_value::<Artist>(raw()).unwrap();
 
         assert_eq!(parsed.albums.len(), parsed.album_count);
-        assert_eq!(parsed.albums[0].id, 1);
+        assert_eq!(parsed.albums[0].id, "1");
         assert_eq!(parsed.albums[0].name, String::from("Bellevue"));
         assert_eq!(parsed.albums[0].song_count, 9);
     }
 
+    #[test]
+    fn loaded_albums_not_refetched() {
+        // Nothing listens on the discard port, so any request would fail.
+        let cli = Client::new("http://127.0.0.1:9", "user", "pass").unwrap();
+
+        let mut raw = raw();
+        raw["albumCount"] = 2.into();
+        let parsed = serde_json::from_value::<Artist>(raw).unwrap();
+        assert_eq!(parsed.albums(&cli).unwrap().len(), 1);
+
+        let mut raw = self::raw();
+        raw.as_object_mut().unwrap().remove("album");
+        let indexed = serde_json::from_value::<Artist>(raw).unwrap();
+        assert!(indexed.albums(&cli).is_err());
+    }
+
+    #[test]
+    fn serialize_artist_roundtrip() {
+        let parsed = serde_json::from_value::<Artist>(raw()).unwrap();
+        let value = serde_json::to_value(&parsed).unwrap();
+        let reparsed = serde_json::from_value::<Artist>(value.clone()).unwrap();
+        assert!(reparsed.albums_loaded);
+        assert_eq!(reparsed.albums.len(), 1);
+        assert_eq!(serde_json::to_value(&reparsed).unwrap(), value);
+
+        // An artist without its albums must not read back as having none.
+        let mut raw = raw();
+        raw.as_object_mut().unwrap().remove("album");
+        let partial = serde_json::from_value::<Artist>(raw).unwrap();
+        let value = serde_json::to_value(&partial).unwrap();
+        assert!(value.get("album").is_none());
+        assert!(
+            !serde_json::from_value::<Artist>(value)
+                .unwrap()
+                .albums_loaded
+        );
+    }
+
+    #[test]
+    fn relative_image_url() {
+        let image = "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n\
+                     Content-Length: 4\r\nConnection: close\r\n\r\nPNG!";
+        let (site, requests) = test_util::recording_mock_server(&[image]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let info = serde_json::from_str::<ArtistInfo>(
+            r#"{
+            "biography" : "",
+            "musicBrainzId" : "",
+            "lastFmUrl" : "",
+            "smallImageUrl" : "/share/img/ar-1?size=150",
+            "mediumImageUrl" : "/share/img/ar-1?size=300",
+            "largeImageUrl" : "/share/img/ar-1?size=600",
+            "similarArtist" : []
+        }"#,
+        )
+        .unwrap();
+
+        let bytes = cli.image_bytes(&info.image_urls.0).unwrap();
+        assert_eq!(bytes, b"PNG!".to_vec());
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /share/img/ar-1?size=150 "));
+    }
+
     #[test]
     fn remote_artist_album_list() {
         let srv = test_util::demo_site().unwrap();
         let parsed = serde_json::from_value::<Artist>(raw()).unwrap();
         let albums = parsed.albums(&srv).unwrap();
 
-        assert_eq!(albums[0].id, 1);
+        assert_eq!(albums[0].id, "1");
         assert_eq!(albums[0].name, String::from("Bellevue"));
         assert_eq!(albums[0].song_count, 9);
     }
@@ -0,0 +1,112 @@
+//! Directory APIs.
+
+use crate::query::Query;
+use crate::{Child, Client, Id, Result};
+
+/// A directory in the server's folder structure.
+///
+/// Folder-based endpoints list directories alongside songs and videos; see
+/// [`Child`]. A directory usually holds an artist's albums, or an album's
+/// songs.
+///
+/// [`Child`]: ./enum.Child.html
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Directory {
+    /// Unique identifier for the directory.
+    pub id: Id,
+    /// The ID of the directory containing this one, if it isn't at the top
+    /// of a music folder.
+    pub parent: Option<Id>,
+    /// The name of the directory.
+    // `getMusicDirectory` calls this `name`; child entries call it `title`.
+    #[serde(alias = "name")]
+    pub title: String,
+    /// The album the directory holds, if the server recognises one.
+    pub album: Option<String>,
+    /// The artist of the directory's contents, if the server recognises one.
+    pub artist: Option<String>,
+    /// ID of the directory's cover art.
+    #[serde(rename = "coverArt")]
+    pub cover_id: Option<String>,
+    /// When the current user starred the directory (as an ISO8601
+    /// timestamp), if they have.
+    pub starred: Option<String>,
+    /// When the directory was added to the server (as an ISO8601 timestamp).
+    pub created: Option<String>,
+    /// The songs, videos and directories inside the directory.
+    ///
+    /// Only directories fetched with [`Directory::get`] list their contents;
+    /// this is empty for directories listed as entries of another.
+    ///
+    /// [`Directory::get`]: #method.get
+    #[serde(rename = "child", default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Child>,
+}
+
+impl Directory {
+    /// Fetches a directory and its contents.
+    ///
+    /// The ID can be that of a directory entry, or of an artist from the
+    /// server's folder-based indexes.
+    ///
+    /// # Errors
+    ///
+    /// Aside from other errors the `Client` may cause, the server will return
+    /// an error if there is no directory matching the provided ID.
+    pub fn get<I: Into<Id>>(client: &Client, id: I) -> Result<Directory> {
+        client.get_required(
+            "getMusicDirectory",
+            Query::with("id", id.into()),
+            &["directory"],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn get_directory() {
+        let body = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.14.0",
+                "directory": {
+                    "id": "25",
+                    "parent": "1",
+                    "name": "Bellevue",
+                    "child": [ {
+                        "id": "26",
+                        "parent": "25",
+                        "isDir": true,
+                        "title": "Extras"
+                    }, {
+                        "id": "27",
+                        "parent": "25",
+                        "isDir": false,
+                        "title": "Bellevue Avenue",
+                        "size": 5400185,
+                        "contentType": "audio/mpeg",
+                        "suffix": "mp3",
+                        "path": "Misteur Valaire/Bellevue/01 - Bellevue Avenue.mp3",
+                        "type": "music"
+                    } ]
+                }
+            }}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[body]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let dir = Directory::get(&cli, 25).unwrap();
+        assert_eq!(dir.title, "Bellevue");
+        assert_eq!(dir.children.len(), 2);
+        assert!(dir.children[0].as_dir().unwrap().children.is_empty());
+        assert_eq!(dir.children[1].as_song().unwrap().title, "Bellevue Avenue");
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/getMusicDirectory?"));
+        assert!(request.contains("id=25"));
+    }
+}
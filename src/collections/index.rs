@@ -0,0 +1,180 @@
+//! Artist index APIs.
+
+use std::result;
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::{Artist, Song};
+
+/// The top-level index of a music library, as used to browse it from A to Z.
+#[derive(Debug, Clone)]
+pub struct Indexes {
+    /// Articles ignored when sorting artists into the index, separated by
+    /// spaces (for example, "The El La Los Las Le Les").
+    pub ignored_articles: String,
+    /// When the index was last changed, in milliseconds since the epoch.
+    pub last_modified: Option<u64>,
+    /// Artists grouped by the letter they are indexed under.
+    pub index: Vec<Index>,
+    /// Shortcuts to directories configured on the server.
+    pub shortcuts: Vec<Artist>,
+    /// Files at the root of the music folder that aren't under any artist.
+    pub children: Vec<Song>,
+}
+
+/// A group of artists in an [`Indexes`], sharing an index letter.
+///
+/// [`Indexes`]: ./struct.Indexes.html
+#[derive(Debug, Clone)]
+pub struct Index {
+    /// The letter, or other heading, the artists are indexed under.
+    pub name: String,
+    /// The artists in the group.
+    pub artists: Vec<Artist>,
+}
+
+impl<'de> Deserialize<'de> for Indexes {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Indexes {
+            #[serde(default)]
+            ignored_articles: String,
+            last_modified: Option<u64>,
+            #[serde(default)]
+            index: Vec<Index>,
+            #[serde(default)]
+            shortcut: Vec<Artist>,
+            #[serde(default)]
+            child: Vec<Song>,
+        }
+
+        let raw = _Indexes::deserialize(de)?;
+        Ok(Indexes {
+            ignored_articles: raw.ignored_articles,
+            last_modified: raw.last_modified,
+            index: raw.index,
+            shortcuts: raw.shortcut,
+            children: raw.child,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Index {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct _Index {
+            name: String,
+            #[serde(default)]
+            artist: Vec<Artist>,
+        }
+
+        let raw = _Index::deserialize(de)?;
+        Ok(Index {
+            name: raw.name,
+            artists: raw.artist,
+        })
+    }
+}
+
+impl Serialize for Indexes {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Indexes<'a> {
+            ignored_articles: &'a str,
+            last_modified: Option<u64>,
+            index: &'a [Index],
+            shortcut: &'a [Artist],
+            child: &'a [Song],
+        }
+        _Indexes {
+            ignored_articles: &self.ignored_articles,
+            last_modified: self.last_modified,
+            index: &self.index,
+            shortcut: &self.shortcuts,
+            child: &self.children,
+        }
+        .serialize(se)
+    }
+}
+
+impl Serialize for Index {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct _Index<'a> {
+            name: &'a str,
+            artist: &'a [Artist],
+        }
+        _Index {
+            name: &self.name,
+            artist: &self.artists,
+        }
+        .serialize(se)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_indexes() {
+        let parsed = serde_json::from_value::<Indexes>(raw()).unwrap();
+
+        assert_eq!(parsed.ignored_articles, "The El La Los Las Le Les");
+        assert_eq!(parsed.last_modified, Some(1_237_646_148_000));
+        assert_eq!(parsed.index.len(), 2);
+        assert_eq!(parsed.index[1].name, "B");
+        assert_eq!(parsed.index[1].artists[0].name, "Bellevue");
+        assert_eq!(parsed.shortcuts[0].name, "Podcasts");
+        assert_eq!(parsed.children.len(), 1);
+        assert_eq!(parsed.children[0].title, "Loose Track");
+    }
+
+    fn raw() -> serde_json::Value {
+        serde_json::from_str(
+            r#"{
+            "lastModified" : 1237646148000,
+            "ignoredArticles" : "The El La Los Las Le Les",
+            "shortcut" : [ { "id" : "11", "name" : "Podcasts" } ],
+            "index" : [ {
+                "name" : "A",
+                "artist" : [ { "id" : "1", "name" : "ABBA" } ]
+            }, {
+                "name" : "B",
+                "artist" : [
+                    { "id" : "4", "name" : "Bellevue" },
+                    { "id" : "5", "name" : "Bjork" }
+                ]
+            } ],
+            "child" : [ {
+                "id" : "111",
+                "parent" : "11",
+                "isDir" : false,
+                "title" : "Loose Track",
+                "size" : 5400185,
+                "contentType" : "audio/mpeg",
+                "suffix" : "mp3",
+                "duration" : 198,
+                "path" : "Loose Track.mp3",
+                "type" : "music"
+            } ]
+        }"#,
+        )
+        .unwrap()
+    }
+}
@@ -2,23 +2,32 @@
 
 use std::result;
 
-use serde::de::{Deserialize, Deserializer};
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::search;
+use crate::{Client, Id, Result, Song};
 
 pub mod album;
 pub mod artist;
+pub mod directory;
+pub mod index;
 pub mod playlist;
 
-pub use self::album::{Album, AlbumInfo, ListType};
+pub use self::album::{Album, AlbumInfo, ListType, ReleaseDate};
 pub use self::artist::{Artist, ArtistInfo};
+pub use self::directory::Directory;
+pub use self::index::{Index, Indexes};
 pub use self::playlist::Playlist;
 
 /// A representation of a music folder on a Subsonic server.
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct MusicFolder {
-    /// The index number of the folder.
-    pub id: usize,
+    /// The ID of the folder.
+    pub id: Id,
     /// The name assigned to the folder.
     pub name: String,
+    #[serde(skip)]
     _private: bool,
 }
 
@@ -29,13 +38,13 @@ impl<'de> Deserialize<'de> for MusicFolder {
     {
         #[derive(Deserialize)]
         struct _MusicFolder {
-            id: String,
+            id: Id,
             name: String,
         }
 
         let raw = _MusicFolder::deserialize(de)?;
         Ok(MusicFolder {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             name: raw.name,
             _private: false,
         })
@@ -43,8 +52,7 @@ impl<'de> Deserialize<'de> for MusicFolder {
 }
 
 /// A genre contained on a Subsonic server.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug)]
 pub struct Genre {
     /// The name of the genre.
     pub name: String,
@@ -52,6 +60,121 @@ pub struct Genre {
     pub song_count: u64,
     /// The number of albums in the genre.
     pub album_count: u64,
-    #[serde(default)]
     _private: bool,
 }
+
+impl<'de> Deserialize<'de> for Genre {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Genre {
+            // The API names the genre `value`; `name` is accepted as well.
+            value: Option<String>,
+            name: Option<String>,
+            song_count: u64,
+            album_count: u64,
+        }
+
+        let raw = _Genre::deserialize(de)?;
+        Ok(Genre {
+            name: raw
+                .value
+                .or(raw.name)
+                .ok_or_else(|| de::Error::missing_field("value"))?,
+            song_count: raw.song_count,
+            album_count: raw.album_count,
+            _private: false,
+        })
+    }
+}
+
+impl Serialize for Genre {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Genre<'a> {
+            value: &'a str,
+            song_count: u64,
+            album_count: u64,
+        }
+        _Genre {
+            value: &self.name,
+            song_count: self.song_count,
+            album_count: self.album_count,
+        }
+        .serialize(se)
+    }
+}
+
+impl Genre {
+    /// Returns the number of songs in the genre contained in a single music
+    /// folder.
+    ///
+    /// The counts provided by the server through [`Client::genres`] cover
+    /// every music folder. The Subsonic API doesn't provide per-folder counts,
+    /// so this method counts the songs returned by listing the genre in the
+    /// folder instead. This requires at least one additional request, plus
+    /// one for every 500 songs in the genre.
+    ///
+    /// [`Client::genres`]: ../struct.Client.html#method.genres
+    pub fn song_count_in_folder<I: Into<Id>>(&self, client: &Client, folder_id: I) -> Result<u64> {
+        let folder_id = folder_id.into();
+        let mut count = 0;
+        let mut page = search::ALL;
+
+        loop {
+            let songs = Song::list_in_genre(client, &self.name, page, folder_id.clone())?;
+            count += songs.len() as u64;
+            if songs.len() < page.count {
+                return Ok(count);
+            }
+            page.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_music_folder_ids() {
+        let folders = serde_json::from_str::<Vec<MusicFolder>>(
+            r#"[ { "id" : 1, "name" : "Music" },
+                 { "id" : "e40e3b8a", "name" : "Audiobooks" } ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(folders[0].id, "1");
+        assert_eq!(folders[1].id, "e40e3b8a");
+        assert_eq!(folders[1].name, "Audiobooks");
+    }
+
+    #[test]
+    fn parse_genre() {
+        let parsed = serde_json::from_str::<Genre>(
+            r#"{ "value" : "Rock", "songCount" : 90, "albumCount" : 8 }"#,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.name, "Rock");
+        assert_eq!(parsed.song_count, 90);
+        assert_eq!(parsed.album_count, 8);
+    }
+
+    #[test]
+    fn parse_genre_name() {
+        let parsed = serde_json::from_str::<Genre>(
+            r#"{ "name" : "Electronic", "songCount" : 12, "albumCount" : 2 }"#,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.name, "Electronic");
+    }
+}
@@ -5,26 +5,32 @@ use std::result;
 use serde::de::{Deserialize, Deserializer};
 use serde_json;
 
+use crate::id;
 use crate::query::Query;
-use crate::{Client, Error, Media, Result, Song};
+use crate::{Client, Error, Id, Media, Result, Song};
 
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[readonly::make]
 pub struct Playlist {
-    pub id: u64,
+    pub id: Id,
     pub name: String,
     pub duration: u64,
+    #[serde(rename = "coverArt")]
     pub cover_id: String,
     pub song_count: u64,
+    #[serde(rename = "entry")]
     pub songs: Vec<Song>,
 }
 
 impl Playlist {
     /// Fetches the songs contained in a playlist.
+    ///
+    /// Songs are returned in the order they are stored in the playlist.
     pub fn songs(&self, client: &Client) -> Result<Vec<Song>> {
         if self.songs.len() as u64 != self.song_count {
-            Ok(get_playlist(client, self.id)?.songs)
+            Ok(get_playlist(client, self.id.clone())?.songs)
         } else {
             Ok(self.songs.clone())
         }
@@ -39,7 +45,7 @@ impl<'de> Deserialize<'de> for Playlist {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Playlist {
-            id: String,
+            id: Id,
             name: String,
             // #[serde(default)]
             // comment: String,
@@ -49,14 +55,16 @@ impl<'de> Deserialize<'de> for Playlist {
             // created: String,
             // changed: String,
             cover_art: String,
+            // Songs are listed in playlist order.
             #[serde(default)]
+            #[serde(rename = "entry")]
             songs: Vec<Song>,
         }
 
         let raw = _Playlist::deserialize(de)?;
 
         Ok(Playlist {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             name: raw.name,
             duration: raw.duration,
             cover_id: raw.cover_art,
@@ -79,40 +87,44 @@ impl Media for Playlist {
         let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
         let query = Query::with("id", cover).arg("size", size.into()).build();
 
-        client.get_bytes("getCoverArt", query)
+        client.get_image("getCoverArt", query)
     }
 
     fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String> {
         let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
-
-        client.build_url("getCoverArt", query)
+        client.cover_art_url(cover, size)
     }
 }
 
 #[allow(missing_docs)]
 pub fn get_playlists(client: &Client, user: Option<String>) -> Result<Vec<Playlist>> {
-    let playlist = client.get("getPlaylists", Query::with("username", user))?;
-    Ok(get_list_as!(playlist, Playlist))
+    client.get_list(
+        "getPlaylists",
+        Query::with("username", user),
+        "playlists",
+        "playlist",
+    )
 }
 
 #[allow(missing_docs)]
-pub fn get_playlist(client: &Client, id: u64) -> Result<Playlist> {
-    let res = client.get("getPlaylist", Query::with("id", id))?;
-    Ok(serde_json::from_value::<Playlist>(res)?)
+pub fn get_playlist<I: Into<Id>>(client: &Client, id: I) -> Result<Playlist> {
+    client.get_required("getPlaylist", Query::with("id", id.into()), &["playlist"])
 }
 
 /// Creates a playlist with the given name.
 ///
 /// Since API version 1.14.0, the newly created playlist is returned. In earlier
 /// versions, an empty response is returned.
-pub fn create_playlist(client: &Client, name: String, songs: &[u64]) -> Result<Option<Playlist>> {
+pub fn create_playlist<I>(client: &Client, name: String, songs: &[I]) -> Result<Option<Playlist>>
+where
+    I: Into<Id> + Clone,
+{
     let args = Query::new()
         .arg("name", name)
-        .arg_list("songId", songs)
+        .arg_list("songId", &id::collect(songs))
         .build();
 
-    let res = client.get("createPlaylist", args)?;
+    let res = client.get_value("createPlaylist", args)?;
 
     // TODO API is private
     // if client.api >= "1.14.0".into() {
@@ -123,35 +135,37 @@ pub fn create_playlist(client: &Client, name: String, songs: &[u64]) -> Result<O
 }
 
 /// Updates a playlist. Only the owner of the playlist is privileged to do so.
-pub fn update_playlist<'a, B, S>(
+pub fn update_playlist<'a, B, S, I, J>(
     client: &Client,
-    id: u64,
+    id: I,
     name: S,
     comment: S,
     public: B,
-    to_add: &[u64],
+    to_add: &[J],
     to_remove: &[u64],
 ) -> Result<()>
 where
     S: Into<Option<&'a str>>,
     B: Into<Option<bool>>,
+    I: Into<Id>,
+    J: Into<Id> + Clone,
 {
     let args = Query::new()
-        .arg("id", id)
+        .arg("id", id.into())
         .arg("name", name.into())
         .arg("comment", comment.into())
         .arg("public", public.into())
-        .arg_list("songIdToAdd", to_add)
+        .arg_list("songIdToAdd", &id::collect(to_add))
         .arg_list("songIndexToRemove", to_remove)
         .build();
 
-    client.get("updatePlaylist", args)?;
+    client.get_value("updatePlaylist", args)?;
     Ok(())
 }
 
 #[allow(missing_docs)]
-pub fn delete_playlist(client: &Client, id: u64) -> Result<()> {
-    client.get("deletePlaylist", Query::with("id", id))?;
+pub fn delete_playlist<I: Into<Id>>(client: &Client, id: I) -> Result<()> {
+    client.get_value("deletePlaylist", Query::with("id", id.into()))?;
     Ok(())
 }
 
@@ -175,6 +189,58 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn refetched_songs_keep_order() {
+        let song = |id: u64, track: u64| {
+            format!(
+                r#"{{
+                "id" : "{id}",
+                "parent" : "25",
+                "isDir" : false,
+                "title" : "Track {track}",
+                "album" : "Bellevue",
+                "track" : {track},
+                "size" : 5400185,
+                "contentType" : "audio/mpeg",
+                "suffix" : "mp3",
+                "duration" : 198,
+                "bitRate" : 216,
+                "path" : "Misteur Valaire/Bellevue/{track}.mp3",
+                "created" : "2017-03-12T11:07:27.000Z",
+                "type" : "music"
+            }}"#
+            )
+        };
+        let body = format!(
+            r#"{{"subsonic-response": {{
+                "status": "ok",
+                "version": "1.14.0",
+                "playlist": {{
+                    "id" : "1",
+                    "name" : "Sleep Hits",
+                    "songCount" : 3,
+                    "duration" : 594,
+                    "coverArt" : "pl-1",
+                    "entry" : [{}, {}, {}]
+                }}
+            }}}}"#,
+            song(29, 3),
+            song(27, 1),
+            song(28, 2)
+        );
+        let site = test_util::mock_server(&[test_util::json_response(&body)]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let parsed = serde_json::from_value::<Playlist>(raw()).unwrap();
+        let ids = parsed
+            .songs(&cli)
+            .unwrap()
+            .iter()
+            .map(|s| s.id.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["29", "27", "28"]);
+    }
+
     fn raw() -> serde_json::Value {
         serde_json::from_str(
             r#"{
@@ -5,6 +5,8 @@ use reqwest;
 use serde::de::{Deserialize, Deserializer};
 use serde_json;
 
+use crate::Version;
+
 /// An alias for `sunk`'s error result type.
 pub type Result<T> = result::Result<T, self::Error>;
 
@@ -35,6 +37,25 @@ pub enum Error {
     #[fail(display = "Error serialising: {}", _0)]
     Serde(#[cause] serde_json::Error),
 
+    /// The server's API version is too old for the endpoint, so the request
+    /// was not sent.
+    #[fail(
+        display = "{} requires API version {}, but the server supports {}",
+        endpoint, required, actual
+    )]
+    Unsupported {
+        /// The endpoint that was called.
+        endpoint: String,
+        /// The API version the endpoint was introduced in.
+        required: Version,
+        /// The API version the server supports.
+        actual: Version,
+    },
+
+    /// A transfer was cancelled through its `CancellationToken`.
+    #[fail(display = "Transfer was cancelled")]
+    Cancelled,
+
     /// For general, one-off errors.
     #[fail(display = "{}", _0)]
     Other(&'static str),
@@ -73,6 +94,21 @@ pub enum ApiError {
     WrongAuth,
     /// Token authentication is not supported for LDAP users.
     Ldap,
+    /// The server doesn't support the authentication mechanism used, such as
+    /// an API key on a server that only accepts passwords, or a password on
+    /// one that requires an API key.
+    ///
+    /// This is an OpenSubsonic extension.
+    AuthNotSupported,
+    /// More than one authentication mechanism was used in the same request,
+    /// such as both an API key and a username.
+    ///
+    /// This is an OpenSubsonic extension.
+    ConflictingAuth,
+    /// The API key is not valid.
+    ///
+    /// This is an OpenSubsonic extension.
+    InvalidApiKey,
     /// The user is not authorized for the given operation.
     NotAuthorized(String),
     /// The trial period for the Subsonic server is over.
@@ -96,6 +132,9 @@ impl ApiError {
             ServerMustUpgrade => 30,
             WrongAuth => 40,
             Ldap => 41,
+            AuthNotSupported => 42,
+            ConflictingAuth => 43,
+            InvalidApiKey => 44,
             NotAuthorized(_) => 50,
             TrialExpired => 60,
             NotFound => 70,
@@ -129,15 +168,18 @@ impl<'de> Deserialize<'de> for ApiError {
         use self::ApiError::*;
 
         match raw.code {
-            10 => Ok(Generic(raw.message)),
+            10 => Ok(MissingParameter),
             20 => Ok(ClientMustUpgrade),
             30 => Ok(ServerMustUpgrade),
             40 => Ok(WrongAuth),
             41 => Ok(Ldap),
+            42 => Ok(AuthNotSupported),
+            43 => Ok(ConflictingAuth),
+            44 => Ok(InvalidApiKey),
             50 => Ok(NotAuthorized(raw.message)),
             60 => Ok(TrialExpired),
             70 => Ok(NotFound),
-            _ => unimplemented!(),
+            _ => Ok(Generic(raw.message)),
         }
     }
 }
@@ -152,6 +194,9 @@ impl fmt::Display for ApiError {
             ServerMustUpgrade => write!(f, "Incompatible protocol; server must upgrade"),
             WrongAuth => write!(f, "Wrong username or password"),
             Ldap => write!(f, "Token authentication not supported for LDAP users"),
+            AuthNotSupported => write!(f, "Authentication mechanism not supported"),
+            ConflictingAuth => write!(f, "Conflicting authentication mechanisms provided"),
+            InvalidApiKey => write!(f, "Invalid API key"),
             NotAuthorized(ref s) => write!(f, "Not authorized: {}", s),
             TrialExpired => write!(f, "Subsonic trial period has expired"),
             NotFound => write!(f, "Requested data not found"),
@@ -186,3 +231,33 @@ impl From<reqwest::UrlError> for Error {
         Error::Url(err.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(code: u16, message: &str) -> ApiError {
+        let raw = serde_json::json!({ "code": code, "message": message });
+        serde_json::from_value(raw).unwrap()
+    }
+
+    #[test]
+    fn parse_api_errors() {
+        assert!(matches!(parse(0, "oops"), ApiError::Generic(ref s) if s == "oops"));
+        assert!(matches!(parse(10, ""), ApiError::MissingParameter));
+        assert!(matches!(parse(50, "no"), ApiError::NotAuthorized(_)));
+    }
+
+    #[test]
+    fn parse_opensubsonic_auth_errors() {
+        assert!(matches!(parse(42, ""), ApiError::AuthNotSupported));
+        assert!(matches!(parse(43, ""), ApiError::ConflictingAuth));
+        assert!(matches!(parse(44, ""), ApiError::InvalidApiKey));
+        assert_eq!(parse(44, "").as_u16(), 44);
+    }
+
+    #[test]
+    fn unknown_code_is_generic() {
+        assert!(matches!(parse(99, "new"), ApiError::Generic(ref s) if s == "new"));
+    }
+}
@@ -0,0 +1,169 @@
+//! Identifiers for server entities.
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::query::{Arg, IntoArg};
+
+/// An identifier for an item on the server.
+///
+/// Subsonic itself hands out numeric IDs, but the API only promises an opaque
+/// string. Other implementations use hashes or UUIDs (Navidrome, for one,
+/// uses hex strings), so IDs are kept as the server sent them.
+///
+/// Anything that takes an ID accepts `impl Into<Id>`, so integers and strings
+/// can be passed directly:
+///
+/// ```
+/// use sunk::Id;
+///
+/// assert_eq!(Id::from(20), Id::from("20"));
+/// assert_eq!(Id::from("2bc1e8d7").as_u64(), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Id(String);
+
+impl Id {
+    /// Returns the ID as the server sent it.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the ID as a number, if the server uses numeric IDs.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.0.parse().ok()
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Id {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Id {
+    fn from(s: String) -> Id {
+        Id(s)
+    }
+}
+
+impl<'a> From<&'a str> for Id {
+    fn from(s: &'a str) -> Id {
+        Id(s.to_owned())
+    }
+}
+
+impl<'a> From<&'a Id> for Id {
+    fn from(id: &'a Id) -> Id {
+        id.clone()
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Id {
+                fn from(n: $t) -> Id {
+                    Id(n.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i32, i64, u32, u64, usize);
+
+impl PartialEq<str> for Id {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Id {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.0 == *other
+    }
+}
+
+/// Converts a list of anything ID-like for passing as a repeated argument.
+pub(crate) fn collect<I: Into<Id> + Clone>(ids: &[I]) -> Vec<Id> {
+    ids.iter().cloned().map(Into::into).collect()
+}
+
+impl IntoArg for Id {
+    fn into_arg(self) -> Arg {
+        self.0.into_arg()
+    }
+}
+
+impl Serialize for Id {
+    fn serialize<S>(&self, se: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        se.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Id {
+    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct IdVisitor;
+
+        impl<'de> Visitor<'de> for IdVisitor {
+            type Value = Id;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string or integer ID")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> ::std::result::Result<Id, E> {
+                Ok(Id::from(v))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> ::std::result::Result<Id, E> {
+                Ok(Id(v))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> ::std::result::Result<Id, E> {
+                Ok(Id::from(v))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> ::std::result::Result<Id, E> {
+                Ok(Id::from(v))
+            }
+        }
+
+        de.deserialize_any(IdVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_string_and_number() {
+        let ids: Vec<Id> = serde_json::from_str(r#"["12", 12, "al-3f9e"]"#).unwrap();
+        assert_eq!(ids[0], ids[1]);
+        assert_eq!(ids[0].as_u64(), Some(12));
+        assert_eq!(ids[2], "al-3f9e");
+        assert_eq!(ids[2].as_u64(), None);
+    }
+
+    #[test]
+    fn id_as_arg() {
+        let id = Id::from("a1b2");
+        assert_eq!(id.into_arg().to_string(), "a1b2");
+    }
+}
@@ -3,10 +3,12 @@
 use std::result;
 
 use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
 use serde_json;
 
+use crate::id;
 use crate::query::Query;
-use crate::{Client, Result, Song};
+use crate::{ApiError, Client, Error, Id, Result, Song};
 
 /// A wrapper on a `Client` to control just the jukebox.
 ///
@@ -18,7 +20,7 @@ pub struct Jukebox<'a> {
 }
 
 /// A representation of the jukebox's current status.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct JukeboxStatus {
     /// Current index in the playlist (zero-indexed). `-1` means that the
     /// jukebox has had its playlist cleared and has not since been played.
@@ -70,13 +72,32 @@ impl<'de> Deserialize<'de> for JukeboxPlaylist {
     }
 }
 
+impl Serialize for JukeboxPlaylist {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct _Playlist<'a> {
+            #[serde(flatten)]
+            status: &'a JukeboxStatus,
+            entry: &'a [Song],
+        }
+        _Playlist {
+            status: &self.status,
+            entry: &self.songs,
+        }
+        .serialize(se)
+    }
+}
+
 impl<'a> Jukebox<'a> {
     /// Creates a new handler to the jukebox of the client.
     pub fn start(client: &'a Client) -> Jukebox {
         Jukebox { client }
     }
 
-    fn send_action_with<U>(&self, action: &str, index: U, ids: &[usize]) -> Result<JukeboxStatus>
+    fn send_action_with<U>(&self, action: &str, index: U, ids: &[Id]) -> Result<JukeboxStatus>
     where
         U: Into<Option<usize>>,
     {
@@ -84,7 +105,7 @@ impl<'a> Jukebox<'a> {
             .arg("index", index.into())
             .arg_list("id", ids)
             .build();
-        let res = self.client.get("jukeboxControl", args)?;
+        let res = self.client.get_value("jukeboxControl", args)?;
         Ok(serde_json::from_value(res)?)
     }
 
@@ -98,7 +119,7 @@ impl<'a> Jukebox<'a> {
     pub fn playlist(&self) -> Result<JukeboxPlaylist> {
         let res = self
             .client
-            .get("jukeboxControl", Query::with("action", "get"))?;
+            .get_value("jukeboxControl", Query::with("action", "get"))?;
         Ok(serde_json::from_value::<JukeboxPlaylist>(res)?)
     }
 
@@ -107,6 +128,23 @@ impl<'a> Jukebox<'a> {
         self.send_action("status")
     }
 
+    /// Returns whether the user may control the jukebox.
+    ///
+    /// Users without the jukebox role are refused by the server; this checks
+    /// the jukebox's status and reports such a refusal as `false`, so that
+    /// jukebox controls can be disabled rather than reporting an error.
+    ///
+    /// # Errors
+    ///
+    /// Errors other than the user not being authorised are returned as usual.
+    pub fn is_available(&self) -> Result<bool> {
+        match self.status() {
+            Ok(_) => Ok(true),
+            Err(Error::Api(ApiError::NotAuthorized(_))) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Tells the jukebox to start playing.
     pub fn play(&self) -> Result<JukeboxStatus> {
         self.send_action("start")
@@ -128,7 +166,7 @@ impl<'a> Jukebox<'a> {
 
     /// Adds the song to the jukebox's playlist.
     pub fn add(&self, song: &Song) -> Result<JukeboxStatus> {
-        self.send_action_with("add", None, &[song.id as usize])
+        self.send_action_with("add", None, std::slice::from_ref(&song.id))
     }
 
     /// Adds a song matching the provided ID to the playlist.
@@ -137,8 +175,8 @@ impl<'a> Jukebox<'a> {
     ///
     /// The method will return an error if a song matching the provided ID
     /// cannot be found.
-    pub fn add_id(&self, id: usize) -> Result<JukeboxStatus> {
-        self.send_action_with("add", None, &[id])
+    pub fn add_id<I: Into<Id>>(&self, id: I) -> Result<JukeboxStatus> {
+        self.send_action_with("add", None, &[id.into()])
     }
 
     /// Adds all the songs to the jukebox's playlist.
@@ -146,7 +184,7 @@ impl<'a> Jukebox<'a> {
         self.send_action_with(
             "add",
             None,
-            &songs.iter().map(|s| s.id as usize).collect::<Vec<_>>(),
+            &songs.iter().map(|s| s.id.clone()).collect::<Vec<_>>(),
         )
     }
 
@@ -156,8 +194,8 @@ impl<'a> Jukebox<'a> {
     ///
     /// The method will return an error if at least one ID cannot be matched to
     /// a song.
-    pub fn add_all_ids(&self, ids: &[usize]) -> Result<JukeboxStatus> {
-        self.send_action_with("add", None, ids)
+    pub fn add_all_ids<I: Into<Id> + Clone>(&self, ids: &[I]) -> Result<JukeboxStatus> {
+        self.send_action_with("add", None, &id::collect(ids))
     }
 
     /// Clears the jukebox's playlist.
@@ -180,14 +218,58 @@ impl<'a> Jukebox<'a> {
     /// Seting the volume above `1.0` will have no effect.
     pub fn set_volume(&self, volume: f32) -> Result<JukeboxStatus> {
         let args = Query::with("action", "setGain").arg("gain", volume).build();
-        let res = self.client.get("jukeboxControl", args)?;
+        let res = self.client.get_value("jukeboxControl", args)?;
         Ok(serde_json::from_value(res)?)
     }
+
+    /// Sets the jukebox's playback volume as a percentage.
+    ///
+    /// Values above `100` are treated as `100`.
+    pub fn set_volume_percent(&self, pct: u8) -> Result<JukeboxStatus> {
+        self.set_volume(percent_to_gain(pct))
+    }
+}
+
+/// Converts a volume percentage to a jukebox gain, from `0` to `1.0`.
+fn percent_to_gain(pct: u8) -> f32 {
+    f32::from(pct.min(100)) / 100.0
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn jukebox_availability() {
+        let status = r#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.14.0",
+            "jukeboxStatus": { "currentIndex": 0, "playing": false, "gain": 0.5, "position": 0 }
+        }}"#;
+        let refused = r#"{"subsonic-response": {
+            "status": "failed",
+            "version": "1.14.0",
+            "error": { "code": 50, "message": "User is not authorized for jukebox playback" }
+        }}"#;
+        let site = test_util::mock_server(&[
+            test_util::json_response(status),
+            test_util::json_response(refused),
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let jukebox = Jukebox::start(&cli);
+
+        assert!(jukebox.is_available().unwrap());
+        assert!(!jukebox.is_available().unwrap());
+    }
+
+    #[test]
+    fn volume_percent() {
+        assert_eq!(percent_to_gain(0), 0.0);
+        assert_eq!(percent_to_gain(75), 0.75);
+        assert_eq!(percent_to_gain(100), 1.0);
+        assert_eq!(percent_to_gain(250), 1.0);
+    }
 
     #[test]
     fn parse_playlist() {
@@ -88,6 +88,18 @@
 //!
 //! [`log`]: https://doc.rust-lang.org/log/log/index.html
 //!
+//! # Features
+//!
+//! - `chrono`: adds accessors returning timestamps as [`chrono`] types.
+//! - `native-tls` (default): connects over HTTPS using the platform's TLS
+//!   library.
+//! - `rustls-tls`: connects over HTTPS using [`rustls`], which needs no system
+//!   libraries and suits static (such as musl) builds. Disable the default
+//!   features to use it alone.
+//!
+//! [`chrono`]: https://docs.rs/chrono
+//! [`rustls`]: https://docs.rs/rustls
+//!
 //! # Development
 //!
 //! The crate is still under active development. Methods and paths may change,
@@ -100,8 +112,11 @@
 
 #![deny(missing_docs)]
 
+#[cfg(feature = "chrono")]
+extern crate chrono;
 #[macro_use]
 extern crate failure;
+extern crate httpdate;
 #[macro_use]
 extern crate log;
 extern crate md5;
@@ -111,34 +126,52 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
+extern crate url;
 
-#[macro_use]
-mod macros;
 mod client;
 mod error;
 
 pub mod annotate;
+pub mod bookmark;
+pub mod chat;
 pub mod collections;
+pub mod id;
 pub mod jukebox;
 pub mod media;
+pub mod play_queue;
+pub mod pool;
 pub mod query;
 pub mod response;
 pub mod search;
+pub mod share;
+pub mod transfer;
 pub mod user;
 pub mod version;
 
 #[cfg(test)]
 mod test_util;
 
-pub use self::client::Client;
+pub use self::annotate::Starred;
+pub use self::bookmark::Bookmark;
+pub use self::chat::ChatMessage;
+pub use self::client::{AuthMethod, Capabilities, Client, ClientBuilder, Extension, ServerInfo};
+pub use self::collections::Directory;
 pub use self::collections::Playlist;
 pub use self::collections::{Album, AlbumInfo, ListType};
 pub use self::collections::{Artist, ArtistInfo};
 pub use self::collections::{Genre, MusicFolder};
+pub use self::collections::{Index, Indexes};
 pub use self::error::{ApiError, Error, Result, UrlError};
+pub use self::id::Id;
 pub use self::jukebox::{Jukebox, JukeboxPlaylist, JukeboxStatus};
 pub use self::media::{podcast, song, video};
-pub use self::media::{Hls, HlsPlaylist, Media, NowPlaying, RadioStation, Streamable};
+pub use self::media::{
+    Child, CoverArt, Hls, HlsPlaylist, HlsSegments, HlsVariant, ImageSize, Media, MediaItem,
+    NowPlaying, RadioStation, StreamOptions, StreamResponse, Streamable,
+};
+pub use self::play_queue::PlayQueue;
+pub use self::pool::ClientPool;
+pub use self::share::Share;
 use self::song::{Lyrics, Song};
-pub use self::user::{User, UserBuilder};
+pub use self::user::{Role, User, UserBuilder, UserUpdate};
 pub use self::version::Version;
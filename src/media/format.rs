@@ -1,4 +1,4 @@
-//! Audio and video format APIs.
+//! Audio, video and image format APIs.
 
 use std::fmt;
 
@@ -68,3 +68,30 @@ impl IntoArg for VideoFormat {
         self.to_string().into_arg()
     }
 }
+
+/// Image encoding format.
+///
+/// Used to hint at the format cover art should be returned in. See
+/// [`Client::cover_art_as`] for which servers honour the hint.
+///
+/// [`Client::cover_art_as`]: ../../struct.Client.html#method.cover_art_as
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    Webp,
+    Gif,
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl IntoArg for ImageFormat {
+    fn into_arg(self) -> Arg {
+        self.to_string().into_arg()
+    }
+}
@@ -1,14 +1,20 @@
 //! Individual media APIs.
 
+use std::io::{Read, Write};
 use std::ops::Index;
 use std::result;
 use std::str::FromStr;
+use std::time::Duration;
 
 use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
 
-use crate::{Client, Error, Result};
+use crate::query::Query;
+use crate::transfer::{CancellationToken, MediaReader, SeekableStream, TransferOptions};
+use crate::{Client, Directory, Error, Id, Result};
 
 pub mod format;
+mod id3;
 pub mod podcast;
 mod radio;
 pub mod song;
@@ -30,9 +36,33 @@ pub trait Streamable {
     /// provides setting a maximum bit rate and a target transcoding format.
     ///
     /// The method does not provide any information about the encoding of the
-    /// media without evaluating the stream itself.
+    /// media without evaluating the stream itself; see [`stream_response`].
+    ///
+    /// [`stream_response`]: #method.stream_response
     fn stream(&self, client: &Client) -> Result<Vec<u8>>;
 
+    /// Returns the raw bytes of the media, along with the content type the
+    /// server sent them as.
+    ///
+    /// Servers may transcode media on the fly, so the content type tells a
+    /// player which decoder to use without sniffing the bytes. The media is
+    /// requested with the same options as [`stream_url`].
+    ///
+    /// [`stream_url`]: #tymethod.stream_url
+    fn stream_response(&self, client: &Client) -> Result<StreamResponse> {
+        let mut reader = self.stream_reader(client)?;
+        let content_type = reader.content_type().map(String::from);
+        let content_length = reader.content_length();
+
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Ok(StreamResponse {
+            bytes,
+            content_type,
+            content_length,
+        })
+    }
+
     /// Returns a constructed URL for streaming.
     ///
     /// Supports transcoding options specified on the media beforehand. See the
@@ -43,6 +73,53 @@ pub trait Streamable {
     /// take the URI and stream it.
     fn stream_url(&self, client: &Client) -> Result<String>;
 
+    /// Returns a reader over the media, yielding bytes as they arrive.
+    ///
+    /// Unlike [`stream`], the media is never held in memory in its entirety.
+    /// The reader uses the same options as [`stream_url`].
+    ///
+    /// [`stream`]: #tymethod.stream
+    /// [`stream_url`]: #tymethod.stream_url
+    fn stream_reader(&self, client: &Client) -> Result<MediaReader> {
+        client.open_url(&self.stream_url(client)?)
+    }
+
+    /// Returns a reader over the media that fetches bytes as they are read.
+    ///
+    /// The reader supports seeking, fetching only the ranges of the media
+    /// that are actually read. This allows a player to jump within a track, or
+    /// read a header and then skip to the data, without downloading the
+    /// entire file. See [`SeekableStream`] for more.
+    ///
+    /// The reader uses the same options as [`stream_url`]. Note that servers
+    /// typically do not support seeking within transcoded media.
+    ///
+    /// [`SeekableStream`]: ../transfer/struct.SeekableStream.html
+    /// [`stream_url`]: #tymethod.stream_url
+    fn stream_seekable<'a>(&self, client: &'a Client) -> Result<SeekableStream<'a>> {
+        SeekableStream::open(client, self.stream_url(client)?)
+    }
+
+    /// Streams the media directly into a writer, returning the number of
+    /// bytes written.
+    ///
+    /// This uses the same options as [`stream_url`], and behaves as
+    /// [`download_with`] otherwise.
+    ///
+    /// [`stream_url`]: #tymethod.stream_url
+    /// [`download_with`]: #method.download_with
+    fn stream_to<W>(
+        &self,
+        client: &Client,
+        writer: &mut W,
+        options: &TransferOptions,
+    ) -> Result<u64>
+    where
+        W: Write + ?Sized,
+    {
+        client.copy_url_to(&self.stream_url(client)?, writer, options)
+    }
+
     /// Returns the raw bytes of the media.
     ///
     /// The method does not provide any information about the encoding of the
@@ -52,6 +129,72 @@ pub trait Streamable {
     /// Returns a constructed URL for downloading the song.
     fn download_url(&self, client: &Client) -> Result<String>;
 
+    /// Returns a reader over the original media file, yielding bytes as they
+    /// arrive.
+    ///
+    /// Unlike [`download`], the media is never held in memory in its entirety.
+    ///
+    /// [`download`]: #tymethod.download
+    fn download_reader(&self, client: &Client) -> Result<MediaReader> {
+        client.open_url(&self.download_url(client)?)
+    }
+
+    /// Downloads the media directly into a writer, returning the number of
+    /// bytes written.
+    ///
+    /// Unlike [`download`], the media is never held in memory in its entirety,
+    /// and the transfer can be stopped with a [`CancellationToken`]. If the
+    /// token is cancelled, the method returns [`Error::Cancelled`]. Any bytes
+    /// written before cancelling are flushed to the writer and are not rolled
+    /// back; a partially written file should be removed by the caller.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// use sunk::song::Song;
+    /// use sunk::{Client, Streamable};
+    ///
+    /// # fn run() -> sunk::Result<()> {
+    /// # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+    /// let song = Song::get(&client, 27)?;
+    /// let mut file = File::create(format!("{}.{}", song.title, song.suffix))?;
+    /// let n = song.download_to(&client, &mut file, None)?;
+    /// println!("Downloaded {} bytes", n);
+    /// # Ok(())
+    /// # }
+    /// # fn main() { }
+    /// ```
+    ///
+    /// [`download`]: #tymethod.download
+    /// [`CancellationToken`]: ../transfer/struct.CancellationToken.html
+    /// [`Error::Cancelled`]: ../enum.Error.html#variant.Cancelled
+    fn download_to<'a, W, C>(&self, client: &Client, writer: &mut W, cancel: C) -> Result<u64>
+    where
+        W: Write + ?Sized,
+        C: Into<Option<&'a CancellationToken>>,
+    {
+        let options = cancel.into().map(TransferOptions::from).unwrap_or_default();
+        self.download_with(client, writer, &options)
+    }
+
+    /// Downloads the media directly into a writer, as [`download_to`], with
+    /// a timeout, cancellation token or progress callback given in `options`.
+    ///
+    /// [`download_to`]: #method.download_to
+    fn download_with<W>(
+        &self,
+        client: &Client,
+        writer: &mut W,
+        options: &TransferOptions,
+    ) -> Result<u64>
+    where
+        W: Write + ?Sized,
+    {
+        client.copy_url_to(&self.download_url(client)?, writer, options)
+    }
+
     /// Returns the default encoding of the media.
     ///
     /// A Subsonic server is able to transcode media for streaming to reduce
@@ -72,6 +215,16 @@ pub trait Streamable {
     /// of 0 will disable a limit (i.e., use the original bit rate.)
     fn set_max_bit_rate(&mut self, bit_rate: usize);
 
+    /// Returns the bit rate the media streams at, in Kbps, if it is known.
+    ///
+    /// This is the media's own bit rate, lowered to the maximum set with
+    /// [`set_max_bit_rate`], if any.
+    ///
+    /// [`set_max_bit_rate`]: #tymethod.set_max_bit_rate
+    fn bit_rate(&self) -> Option<u64> {
+        None
+    }
+
     /// Sets the transcoding format the media will use when streaming.
     ///
     /// The possible transcoding formats are those defined by the Subsonic
@@ -108,10 +261,15 @@ pub trait Media {
     /// server (as long as the method does not error), but makes no guarantees
     /// on the encoding of the image.
     ///
+    /// The size may be given in pixels, as an [`ImageSize`] preset, or as
+    /// `None` for the original image.
+    ///
     /// # Errors
     ///
     /// Aside from errors that the `Client` may cause, the method will error
     /// if the media does not have an associated cover art.
+    ///
+    /// [`ImageSize`]: ./enum.ImageSize.html
     fn cover_art<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<Vec<u8>>;
 
     /// Returns the URL pointing to the cover art of the media.
@@ -123,23 +281,364 @@ pub trait Media {
     fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String>;
 }
 
+/// An entry in a list that may contain both songs and videos.
+///
+/// Some endpoints, such as bookmarks and play queues, return songs and videos
+/// together. These are parsed in the same way as the entries of a directory
+/// (see [`Child`]), but never hold directories.
+///
+/// [`Child`]: ./enum.Child.html
+pub type MediaItem = Child;
+
+/// An entry in the server's folder structure: a song, a video, or a
+/// directory.
+///
+/// Folder-based endpoints, such as music directories and shares, list these
+/// together as "child" entries. The server marks directories with `isDir`,
+/// and videos with `isVideo` (or a `type` of `"video"`); anything else is
+/// treated as a song.
+#[derive(Debug, Clone)]
+pub enum Child {
+    /// An audio track.
+    Song(Song),
+    /// A video.
+    Video(Video),
+    /// A directory, such as an album or artist folder.
+    Directory(Directory),
+}
+
+impl Child {
+    /// Returns `true` if the entry is a song.
+    pub fn is_song(&self) -> bool {
+        matches!(self, Child::Song(_))
+    }
+
+    /// Returns `true` if the entry is a video.
+    pub fn is_video(&self) -> bool {
+        matches!(self, Child::Video(_))
+    }
+
+    /// Returns `true` if the entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Child::Directory(_))
+    }
+
+    /// Returns the ID of the entry.
+    pub fn id(&self) -> &Id {
+        match self {
+            Child::Song(song) => &song.id,
+            Child::Video(video) => &video.id,
+            Child::Directory(dir) => &dir.id,
+        }
+    }
+
+    /// Returns the title of the entry.
+    pub fn title(&self) -> &str {
+        match self {
+            Child::Song(song) => &song.title,
+            Child::Video(video) => &video.title,
+            Child::Directory(dir) => &dir.title,
+        }
+    }
+
+    /// Returns the entry as a song, or `None` if it is something else.
+    pub fn as_song(&self) -> Option<&Song> {
+        match self {
+            Child::Song(song) => Some(song),
+            _ => None,
+        }
+    }
+
+    /// Returns the entry as a video, or `None` if it is something else.
+    pub fn as_video(&self) -> Option<&Video> {
+        match self {
+            Child::Video(video) => Some(video),
+            _ => None,
+        }
+    }
+
+    /// Returns the entry as a directory, or `None` if it is something else.
+    pub fn as_dir(&self) -> Option<&Directory> {
+        match self {
+            Child::Directory(dir) => Some(dir),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Child {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error as _;
+
+        let raw = serde_json::Value::deserialize(de)?;
+        let child = if raw["isDir"].as_bool().unwrap_or(false) {
+            serde_json::from_value(raw).map(Child::Directory)
+        } else if is_video_entry(&raw) {
+            serde_json::from_value(raw).map(Child::Video)
+        } else {
+            serde_json::from_value(raw).map(Child::Song)
+        };
+        child.map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Child {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Child::Song(song) => song.serialize(se),
+            Child::Video(video) => video.serialize(se),
+            Child::Directory(dir) => {
+                // Mark the entry so it reads back as a directory.
+                #[derive(Serialize)]
+                #[serde(rename_all = "camelCase")]
+                struct _Directory<'a> {
+                    is_dir: bool,
+                    #[serde(flatten)]
+                    dir: &'a Directory,
+                }
+                _Directory { is_dir: true, dir }.serialize(se)
+            }
+        }
+    }
+}
+
+/// Returns the bit rate media with bit rate `own` streams at when limited to
+/// `max` (where `0` means no limit), if either is known.
+pub(crate) fn stream_bit_rate(own: Option<u64>, max: Option<usize>) -> Option<u64> {
+    let max = max.filter(|&max| max > 0).map(|max| max as u64);
+    match (own, max) {
+        (Some(own), Some(max)) => Some(own.min(max)),
+        (own, max) => own.or(max),
+    }
+}
+
+fn is_video_entry(raw: &serde_json::Value) -> bool {
+    raw["isVideo"].as_bool().unwrap_or(false) || raw["type"].as_str() == Some("video")
+}
+
+/// Options for streaming media, used to build stream URLs with
+/// [`Client::stream_url`].
+///
+/// Every option is left to the server's default unless set.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sunk::{Client, StreamOptions};
+/// # fn run() -> sunk::Result<()> {
+/// # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+///
+/// let mut options = StreamOptions::new();
+/// options.max_bit_rate(128).format("mp3").estimate_content_length(true);
+/// let url = client.stream_url("27", &options)?;
+/// // Hand `url` to a player, such as `mpv`.
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+///
+/// [`Client::stream_url`]: ./struct.Client.html#method.stream_url
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamOptions {
+    max_bit_rate: Option<usize>,
+    format: Option<String>,
+    time_offset: Option<usize>,
+    size: Option<(usize, usize)>,
+    estimate_content_length: bool,
+}
+
+impl StreamOptions {
+    /// Creates a set of options using the server's defaults.
+    pub fn new() -> StreamOptions {
+        StreamOptions::default()
+    }
+
+    /// Sets the maximum bit rate in Kbps. See
+    /// [`Streamable::set_max_bit_rate`] for supported values.
+    ///
+    /// [`Streamable::set_max_bit_rate`]: ./trait.Streamable.html#tymethod.set_max_bit_rate
+    pub fn max_bit_rate(&mut self, bit_rate: usize) -> &mut StreamOptions {
+        self.max_bit_rate = Some(bit_rate);
+        self
+    }
+
+    /// Sets the format to transcode to, such as `"mp3"`, or `"raw"` to
+    /// disable transcoding.
+    pub fn format(&mut self, format: &str) -> &mut StreamOptions {
+        self.format = Some(format.into());
+        self
+    }
+
+    /// Sets the offset in seconds to start streaming from. Subsonic only
+    /// supports this for video, and only if transcoding is enabled.
+    pub fn time_offset(&mut self, offset: usize) -> &mut StreamOptions {
+        self.time_offset = Some(offset);
+        self
+    }
+
+    /// Sets the resolution to stream video at.
+    pub fn size(&mut self, width: usize, height: usize) -> &mut StreamOptions {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Asks the server to send a `Content-Length` estimated from the
+    /// duration and bit rate of transcoded media, which some players need
+    /// to show progress or seek.
+    pub fn estimate_content_length(&mut self, estimate: bool) -> &mut StreamOptions {
+        self.estimate_content_length = estimate;
+        self
+    }
+
+    /// Builds the arguments to the `stream` endpoint for the media `id`.
+    pub(crate) fn to_args(&self, id: &Id) -> Query {
+        Query::with("id", id.clone())
+            .arg("maxBitRate", self.max_bit_rate)
+            .arg("format", self.format.clone())
+            .arg("timeOffset", self.time_offset)
+            .arg("size", self.size.map(|(w, h)| format!("{}x{}", w, h)))
+            .arg(
+                "estimateContentLength",
+                Some(true).filter(|_| self.estimate_content_length),
+            )
+            .build()
+    }
+}
+
+/// Cover art fetched from the server, along with whether it appears to be the
+/// server's stand-in for missing art.
+///
+/// Some servers return a generic placeholder image instead of an error when
+/// media has no cover art. Detecting the placeholder is best-effort: the
+/// Subsonic API doesn't report it, so the image is checked against the
+/// placeholders registered with [`Client::with_placeholder_image`], and
+/// against a file name hint the server may send (such as `placeholder.png`).
+/// A placeholder that matches neither is reported as real cover art.
+///
+/// [`Client::with_placeholder_image`]: ./struct.Client.html#method.with_placeholder_image
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverArt {
+    /// The raw bytes of the image.
+    pub bytes: Vec<u8>,
+    /// Whether the image appears to be a placeholder for missing art.
+    pub is_placeholder: bool,
+}
+
+/// Media fetched from the server, along with how the server described it.
+///
+/// Returned by [`Streamable::stream_response`]. To read media as it arrives
+/// rather than all at once, use [`Streamable::stream_reader`], which reports
+/// the same details.
+///
+/// [`Streamable::stream_response`]: ./trait.Streamable.html#method.stream_response
+/// [`Streamable::stream_reader`]: ./trait.Streamable.html#method.stream_reader
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamResponse {
+    /// The raw bytes of the media.
+    pub bytes: Vec<u8>,
+    /// The MIME type of the media, such as `"audio/mpeg"`, if the server
+    /// sent one.
+    pub content_type: Option<String>,
+    /// The length of the media the server reported ahead of sending it, if
+    /// any. Transcoded media usually has no length, unless requested with
+    /// [`StreamOptions::estimate_content_length`].
+    ///
+    /// [`StreamOptions::estimate_content_length`]: ./struct.StreamOptions.html#method.estimate_content_length
+    pub content_length: Option<u64>,
+}
+
+/// Preset sizes for requesting images.
+///
+/// Anything taking an image size in pixels, such as [`Media::cover_art`], also
+/// accepts a preset. Using the presets keeps requests for the same kind of
+/// image (such as thumbnails in a grid) consistent, which also lets servers
+/// that cache scaled images reuse them.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sunk::{Album, Client, ImageSize, Media};
+/// # fn run() -> sunk::Result<()> {
+/// # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+/// let album = Album::get(&client, 1)?;
+/// let thumbnail = album.cover_art(&client, ImageSize::Thumbnail)?;
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+///
+/// [`Media::cover_art`]: ./trait.Media.html#tymethod.cover_art
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageSize {
+    /// 150 pixels.
+    Thumbnail,
+    /// 300 pixels.
+    Small,
+    /// 600 pixels.
+    Medium,
+    /// 1200 pixels.
+    Large,
+    /// The image as it is stored on the server, unscaled.
+    Original,
+}
+
+impl ImageSize {
+    /// Returns the size in pixels, or `None` for the original size.
+    pub fn pixels(self) -> Option<usize> {
+        match self {
+            ImageSize::Thumbnail => Some(150),
+            ImageSize::Small => Some(300),
+            ImageSize::Medium => Some(600),
+            ImageSize::Large => Some(1200),
+            ImageSize::Original => None,
+        }
+    }
+}
+
+impl From<ImageSize> for Option<usize> {
+    fn from(size: ImageSize) -> Option<usize> {
+        size.pixels()
+    }
+}
+
 /// Information about currently playing media.
 ///
 /// Due to the "now playing" information possibly containing both audio and
-/// video, compromises are made. `NowPlaying` only stores the ID, title, and
-/// content type of the media. This is most of the information afforded through
-/// the web interface. For more detailed information, `song_info()` or
-/// `video_info()` gives the full `Song` or `Video` struct, though requires
-/// another web request.
-#[derive(Debug)]
+/// video, compromises are made. `NowPlaying` only stores the basic details of
+/// the media that a client would show in a list of what others are playing.
+/// For more detailed information, `song_info()` or `video_info()` gives the
+/// full `Song` or `Video` struct, though requires another web request.
+///
+/// Servers differ in which details they report; fields that some servers
+/// leave out are optional.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct NowPlaying {
     /// The user streaming the current media.
+    #[serde(rename = "username")]
     pub user: String,
     /// How long ago the user sent an update to the server.
     pub minutes_ago: usize,
     /// The ID of the player.
-    pub player_id: usize,
-    id: usize,
+    pub player_id: Option<usize>,
+    /// The name of the player, as given by the client playing the media.
+    pub player_name: Option<String>,
+    /// The ID of the song or video.
+    pub id: Id,
+    /// The title of the media.
+    pub title: String,
+    /// The album the media belongs to.
+    pub album: Option<String>,
+    /// The credited artist of the media.
+    pub artist: Option<String>,
+    /// The MIME type of the media.
+    pub content_type: Option<String>,
     is_video: bool,
 }
 
@@ -156,7 +655,7 @@ impl NowPlaying {
         if self.is_video {
             Err(Error::Other("Now Playing info is not a song"))
         } else {
-            Song::get(client, self.id as u64)
+            Song::get(client, &self.id)
         }
     }
 
@@ -172,7 +671,7 @@ impl NowPlaying {
         if !self.is_video {
             Err(Error::Other("Now Playing info is not a video"))
         } else {
-            Video::get(client, self.id)
+            Video::get(client, &self.id)
         }
     }
 
@@ -185,18 +684,40 @@ impl NowPlaying {
     pub fn is_video(&self) -> bool {
         self.is_video
     }
+
+    /// Returns how long ago the user sent an update to the server.
+    ///
+    /// The server only reports this to the minute.
+    pub fn since(&self) -> Duration {
+        Duration::from_secs(self.minutes_ago as u64 * 60)
+    }
+
+    /// Returns the approximate time the user sent an update to the server.
+    ///
+    /// The server only reports how many minutes ago the update was, so this
+    /// is only accurate to the minute.
+    #[cfg(feature = "chrono")]
+    pub fn started_around(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc::now() - chrono::Duration::minutes(self.minutes_ago as i64)
+    }
 }
 
 /// A HLS playlist file.
+///
+/// A playlist is either a media playlist, made up of slices of a single
+/// stream, or a master playlist, which lists a number of variant playlists
+/// for adaptive streaming.
 #[derive(Debug)]
 pub struct HlsPlaylist {
     /// The extension of the playlist metadata. Typically `M3U` or `M3U8`.
     pub extension: String,
     /// The version of the HLS specification.
     pub version: usize,
-    /// The target duration for HLS slices.
+    /// The target duration for HLS slices. Master playlists do not have a
+    /// target duration, and will have this set to `0`.
     pub target_duration: usize,
-    hls: Vec<Hls>,
+    pub(crate) hls: Vec<Hls>,
+    variants: Vec<HlsVariant>,
 }
 
 impl HlsPlaylist {
@@ -214,10 +735,43 @@ impl HlsPlaylist {
     pub fn duration(&self) -> usize {
         self.hls.iter().fold(0, |c, h| c + h.inc)
     }
+
+    /// Returns `true` if the playlist is a master playlist of variants
+    /// rather than a list of slices.
+    pub fn is_master(&self) -> bool {
+        !self.variants.is_empty()
+    }
+
+    /// Returns the variant playlists of a master playlist.
+    pub fn variants(&self) -> &[HlsVariant] {
+        &self.variants
+    }
+
+    /// Selects a variant playlist from a master playlist.
+    ///
+    /// Picks the highest bandwidth variant that does not exceed
+    /// `max_bandwidth`. If no limit is given, or every variant exceeds it, the
+    /// lowest bandwidth variant is picked. Returns `None` if the playlist has
+    /// no variants.
+    pub fn select_variant<U>(&self, max_bandwidth: U) -> Option<&HlsVariant>
+    where
+        U: Into<Option<usize>>,
+    {
+        let lowest = self.variants.iter().min_by_key(|v| v.bandwidth);
+        match max_bandwidth.into() {
+            Some(max) => self
+                .variants
+                .iter()
+                .filter(|v| v.bandwidth <= max)
+                .max_by_key(|v| v.bandwidth)
+                .or(lowest),
+            None => lowest,
+        }
+    }
 }
 
 /// A slice of a media for use in a HLS playlist.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Hls {
     /// The duration increment of the slice.
     pub inc: usize,
@@ -235,39 +789,106 @@ impl Hls {
     }
 }
 
+/// A variant stream listed in a HLS master playlist.
+#[derive(Debug, Clone)]
+pub struct HlsVariant {
+    /// The peak bit rate of the variant, in bits per second.
+    pub bandwidth: usize,
+    /// The path of the variant playlist relative to the server.
+    pub url: String,
+}
+
+/// An iterator over the raw bytes of each slice in a HLS playlist.
+///
+/// Slices are fetched lazily and in order, so a player can feed each one to a
+/// decoder as soon as it arrives. Created by [`Client::hls_segment_stream`].
+///
+/// [`Client::hls_segment_stream`]: ../struct.Client.html#method.hls_segment_stream
+#[derive(Debug)]
+pub struct HlsSegments<'a> {
+    client: &'a Client,
+    hls: ::std::vec::IntoIter<Hls>,
+}
+
+impl<'a> HlsSegments<'a> {
+    pub(crate) fn new(client: &'a Client, hls: Vec<Hls>) -> HlsSegments<'a> {
+        HlsSegments {
+            client,
+            hls: hls.into_iter(),
+        }
+    }
+}
+
+impl<'a> Iterator for HlsSegments<'a> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.hls.next().map(|h| self.client.hls_bytes(&h))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.hls.size_hint()
+    }
+}
+
 impl FromStr for HlsPlaylist {
     type Err = Error;
     fn from_str(s: &str) -> result::Result<Self, Self::Err> {
-        fn chew<'a, 'b>(s: &'a str, head: &'b str) -> result::Result<&'a str, Error> {
+        fn chew<'a>(s: &'a str, head: &str) -> result::Result<&'a str, Error> {
             if s.starts_with(head) {
-                return Ok(s.trim_start_matches(head));
+                Ok(s.trim_start_matches(head))
             } else {
                 Err(Error::Other("missing required field"))
             }
         }
 
-        let mut split = s.split('\n');
+        fn bandwidth(attrs: &str) -> result::Result<usize, Error> {
+            let bw = attrs
+                .split(',')
+                .find_map(|a| a.strip_prefix("BANDWIDTH="))
+                .ok_or(Error::Other("missing variant bandwidth"))?;
+            Ok(bw.parse::<usize>()?)
+        }
+
+        let mut split = s.lines().map(str::trim).filter(|l| !l.is_empty());
+        let mut next_line = || {
+            split
+                .next()
+                .map(str::to_owned)
+                .ok_or(Error::Other("unexpected end of playlist"))
+        };
 
-        let _ext = split.next().unwrap();
-        let extension = chew(_ext, "#EXT")?.to_owned();
-        let _ver = split.next().unwrap();
-        let version = chew(_ver, "#EXT-X-VERSION:")?.parse::<usize>()?;
-        let _tar = split.next().unwrap();
-        let target_duration = chew(_tar, "#EXT-X-TARGETDURATION:")?.parse::<usize>()?;
+        let _ext = next_line()?;
+        let extension = chew(&_ext, "#EXT")?.to_owned();
 
+        let mut version = 0;
+        let mut target_duration = 0;
         let mut hls = Vec::new();
+        let mut variants = Vec::new();
         loop {
-            let _inc = split.next().unwrap();
-            if _inc == "#EXT-X-ENDLIST" {
+            let line = match next_line() {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            if line == "#EXT-X-ENDLIST" {
                 break;
+            } else if let Some(ver) = line.strip_prefix("#EXT-X-VERSION:") {
+                version = ver.parse::<usize>()?;
+            } else if let Some(tar) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+                target_duration = tar.parse::<usize>()?;
+            } else if let Some(inc) = line.strip_prefix("#EXTINF:") {
+                let inc = inc.trim_end_matches(',').parse::<usize>()?;
+                hls.push(Hls {
+                    inc,
+                    url: next_line()?,
+                });
+            } else if let Some(attrs) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+                let bandwidth = bandwidth(attrs)?;
+                variants.push(HlsVariant {
+                    bandwidth,
+                    url: next_line()?,
+                });
             }
-            let inc = chew(_inc, "#EXTINF:")?
-                .trim_end_matches(',')
-                .parse::<usize>()?;
-            hls.push(Hls {
-                inc,
-                url: split.next().unwrap().to_owned(),
-            });
         }
 
         Ok(HlsPlaylist {
@@ -275,6 +896,7 @@ impl FromStr for HlsPlaylist {
             version,
             target_duration,
             hls,
+            variants,
         })
     }
 }
@@ -303,21 +925,18 @@ impl<'de> Deserialize<'de> for NowPlaying {
         #[serde(rename_all = "camelCase")]
         struct _NowPlaying {
             username: String,
+            #[serde(default)]
             minutes_ago: usize,
-            player_id: usize,
-            id: String,
-            // is_dir: bool,
-            // title: String,
-            // size: usize,
-            // content_type: String,
-            // suffix: String,
-            // transcoded_content_type: Option<String>,
-            // transcoded_suffix: Option<String>,
-            // path: String,
+            player_id: Option<usize>,
+            player_name: Option<String>,
+            id: Id,
+            #[serde(default)]
+            title: String,
+            album: Option<String>,
+            artist: Option<String>,
+            content_type: Option<String>,
+            #[serde(default)]
             is_video: bool,
-            // created: String,
-            // #[serde(rename = "type")]
-            // media_type: String,
         }
 
         let raw = _NowPlaying::deserialize(de)?;
@@ -326,7 +945,12 @@ impl<'de> Deserialize<'de> for NowPlaying {
             user: raw.username,
             minutes_ago: raw.minutes_ago,
             player_id: raw.player_id,
-            id: raw.id.parse().unwrap(),
+            player_name: raw.player_name,
+            id: raw.id,
+            title: raw.title,
+            album: raw.album,
+            artist: raw.artist,
+            content_type: raw.content_type,
             is_video: raw.is_video,
         })
     }
@@ -336,6 +960,154 @@ impl<'de> Deserialize<'de> for NowPlaying {
 mod tests {
     use super::*;
 
+    #[test]
+    fn image_size_presets() {
+        fn size<U: Into<Option<usize>>>(size: U) -> Option<usize> {
+            size.into()
+        }
+
+        assert_eq!(size(ImageSize::Thumbnail), Some(150));
+        assert_eq!(size(ImageSize::Large), Some(1200));
+        assert_eq!(size(ImageSize::Original), None);
+        assert_eq!(size(300), ImageSize::Small.pixels());
+    }
+
+    #[test]
+    fn now_playing_since() {
+        let parsed = serde_json::from_str::<NowPlaying>(
+            r#"{
+            "username" : "guest3",
+            "minutesAgo" : 3,
+            "playerId" : 19,
+            "id" : "27",
+            "isVideo" : false
+        }"#,
+        )
+        .unwrap();
+
+        assert!(parsed.is_song());
+        assert_eq!(parsed.since(), Duration::from_secs(180));
+    }
+
+    #[test]
+    fn parse_sparse_now_playing() {
+        let parsed = serde_json::from_str::<NowPlaying>(
+            r#"{
+            "id" : "b7f4ac1e",
+            "title" : "Bellevue Avenue",
+            "album" : "Bellevue",
+            "artist" : "Misteur Valaire",
+            "contentType" : "audio/mpeg",
+            "username" : "admin",
+            "minutesAgo" : 0,
+            "playerName" : "NavidromeUI"
+        }"#,
+        )
+        .unwrap();
+
+        assert!(parsed.is_song());
+        assert_eq!(parsed.id, "b7f4ac1e");
+        assert_eq!(parsed.title, "Bellevue Avenue");
+        assert_eq!(parsed.artist.as_deref(), Some("Misteur Valaire"));
+        assert_eq!(parsed.player_id, None);
+        assert_eq!(parsed.player_name.as_deref(), Some("NavidromeUI"));
+    }
+
+    #[test]
+    fn parse_media_items() {
+        let items = serde_json::from_str::<Vec<MediaItem>>(
+            r#"[{
+            "id" : "27",
+            "parent" : "25",
+            "isDir" : false,
+            "title" : "Bellevue Avenue",
+            "size" : 5400185,
+            "contentType" : "audio/mpeg",
+            "suffix" : "mp3",
+            "duration" : 198,
+            "bitRate" : 216,
+            "path" : "Misteur Valaire/Bellevue/01 - Bellevue Avenue.mp3",
+            "isVideo" : false,
+            "created" : "2017-03-12T11:07:27.000Z",
+            "type" : "music"
+        }, {
+            "id" : "460",
+            "parent" : "24",
+            "isDir" : false,
+            "title" : "Big Buck Bunny",
+            "size" : 52464391,
+            "contentType" : "video/mp4",
+            "suffix" : "mp4",
+            "duration" : 281,
+            "bitRate" : 1488,
+            "path" : "Movies/Big Buck Bunny.mp4",
+            "isVideo" : true,
+            "created" : "2017-03-12T11:06:30.000Z",
+            "type" : "video"
+        }]"#,
+        )
+        .unwrap();
+
+        assert_eq!(items[0].as_song().unwrap().id, "27");
+        assert!(items[1].is_video());
+        assert_eq!(items[1].as_video().unwrap().title, "Big Buck Bunny");
+    }
+
+    #[test]
+    fn parse_children() {
+        let children = serde_json::from_str::<Vec<Child>>(
+            r#"[{
+            "id" : "25",
+            "parent" : "1",
+            "isDir" : true,
+            "title" : "Bellevue",
+            "artist" : "Misteur Valaire",
+            "coverArt" : "25"
+        }, {
+            "id" : "27",
+            "parent" : "25",
+            "isDir" : false,
+            "title" : "Bellevue Avenue",
+            "size" : 5400185,
+            "contentType" : "audio/mpeg",
+            "suffix" : "mp3",
+            "path" : "Misteur Valaire/Bellevue/01 - Bellevue Avenue.mp3",
+            "type" : "music"
+        }, {
+            "id" : "460",
+            "parent" : "24",
+            "isDir" : false,
+            "title" : "Big Buck Bunny",
+            "size" : 15767431,
+            "contentType" : "video/mp4",
+            "suffix" : "mp4",
+            "duration" : 596,
+            "bitRate" : 1121,
+            "path" : "Movies/Big Buck Bunny.mp4",
+            "isVideo" : true,
+            "playCount" : 0,
+            "created" : "2017-03-12T11:07:27.000Z",
+            "type" : "video"
+        }]"#,
+        )
+        .unwrap();
+
+        assert!(children[0].is_dir());
+        assert_eq!(children[0].title(), "Bellevue");
+        assert_eq!(children[0].as_dir().unwrap().parent, Some(Id::from(1)));
+        assert!(children[1].is_song());
+        assert_eq!(children[1].id(), "27");
+        assert!(children[2].is_video());
+        assert_eq!(children[2].title(), "Big Buck Bunny");
+
+        let value = serde_json::to_value(&children).unwrap();
+        let reparsed = serde_json::from_value::<Vec<Child>>(value).unwrap();
+        assert!(reparsed[0].is_dir());
+        assert!(reparsed[1].is_song());
+        assert!(reparsed[2].is_video());
+        assert_eq!(reparsed[2].id(), "460");
+    }
+
     #[test]
     fn parse_hls() {
         let hls = hls();
@@ -345,6 +1117,29 @@ mod tests {
         assert_eq!(p.version, 1);
         assert_eq!(p.target_duration, 10);
         assert_eq!(p.hls.len(), 23);
+        assert!(!p.is_master());
+    }
+
+    #[test]
+    fn parse_hls_master() {
+        let p = "#EXTM3U
+#EXT-X-VERSION:1
+#EXT-X-STREAM-INF:PROGRAM-ID=1,BANDWIDTH=256000
+/rest/hls.m3u8?id=1887&bitRate=256
+#EXT-X-STREAM-INF:PROGRAM-ID=1,BANDWIDTH=128000
+/rest/hls.m3u8?id=1887&bitRate=128
+#EXT-X-STREAM-INF:PROGRAM-ID=1,BANDWIDTH=64000
+/rest/hls.m3u8?id=1887&bitRate=64
+"
+        .parse::<HlsPlaylist>()
+        .unwrap();
+
+        assert!(p.is_master());
+        assert!(p.is_empty());
+        assert_eq!(p.variants().len(), 3);
+        assert_eq!(p.select_variant(None).unwrap().bandwidth, 64000);
+        assert_eq!(p.select_variant(200000).unwrap().bandwidth, 128000);
+        assert_eq!(p.select_variant(1000).unwrap().bandwidth, 64000);
     }
 
     fn hls() -> &'static str {
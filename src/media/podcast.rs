@@ -1,64 +1,180 @@
 //! Podcast APIs.
 
+use std::cmp::Ordering;
 use std::result;
 
 use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
 
-use crate::query::Query;
-use crate::{Client, Result};
+use crate::query::{self, Query};
+use crate::{Client, Error, Id, Media, Result};
 
+/// A podcast the server subscribes to.
+///
+/// Servers differ in which details they report; fields that some servers
+/// leave out are optional.
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[readonly::make]
 pub struct Podcast {
-    pub id: usize,
+    pub id: Id,
     pub url: String,
     pub title: String,
     pub description: String,
-    pub cover_art: String,
-    pub image_url: String,
-    pub status: String,
+    pub cover_art: Option<String>,
+    pub image_url: Option<String>,
+    pub status: PodcastStatus,
+    #[serde(rename = "episode")]
     pub episodes: Vec<Episode>,
+    #[serde(rename = "errorMessage", skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
+/// An episode of a podcast.
+///
+/// Servers differ in which details they report, and most details of the
+/// media file are only known once the server has downloaded the episode, so
+/// many fields are optional.
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[readonly::make]
 pub struct Episode {
-    pub id: usize,
-    pub parent: usize,
+    pub id: Id,
+    pub parent: Option<Id>,
     pub is_dir: bool,
     pub title: String,
-    pub album: String,
-    pub artist: String,
-    pub year: usize,
-    pub cover_art: String,
-    pub size: usize,
-    pub content_type: String,
-    pub suffix: String,
-    pub duration: usize,
-    pub bitrate: usize,
+    pub album: Option<String>,
+    pub artist: Option<String>,
+    pub year: Option<usize>,
+    pub cover_art: Option<String>,
+    pub size: Option<usize>,
+    pub content_type: Option<String>,
+    pub suffix: Option<String>,
+    pub duration: Option<usize>,
+    #[serde(rename = "bitRate")]
+    pub bitrate: Option<usize>,
     pub is_video: bool,
-    pub created: String,
-    pub artist_id: String,
-    pub media_type: String,
-    pub stream_id: String,
-    pub channel_id: String,
+    pub created: Option<String>,
+    pub artist_id: Option<String>,
+    #[serde(rename = "type")]
+    pub media_type: Option<String>,
+    pub stream_id: Option<String>,
+    pub channel_id: Id,
     pub description: String,
-    pub status: String,
+    pub status: PodcastStatus,
     pub publish_date: String,
 }
 
+/// The download status of a podcast or episode on the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PodcastStatus {
+    /// Not yet downloaded.
+    New,
+    /// Being downloaded.
+    Downloading,
+    /// Downloaded and available to stream.
+    Completed,
+    /// The download failed.
+    Error,
+    /// Deleted from the server.
+    Deleted,
+    /// Skipped by the server, such as an episode older than the server is
+    /// configured to download.
+    Skipped,
+    /// A status not known to `sunk`, as sent by the server.
+    Unknown(String),
+}
+
+impl<'a> From<&'a str> for PodcastStatus {
+    fn from(status: &'a str) -> PodcastStatus {
+        match status {
+            "new" => PodcastStatus::New,
+            "downloading" => PodcastStatus::Downloading,
+            "completed" => PodcastStatus::Completed,
+            "error" => PodcastStatus::Error,
+            "deleted" => PodcastStatus::Deleted,
+            "skipped" => PodcastStatus::Skipped,
+            other => PodcastStatus::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl PodcastStatus {
+    /// Returns the status as the server names it, such as `"completed"`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            PodcastStatus::New => "new",
+            PodcastStatus::Downloading => "downloading",
+            PodcastStatus::Completed => "completed",
+            PodcastStatus::Error => "error",
+            PodcastStatus::Deleted => "deleted",
+            PodcastStatus::Skipped => "skipped",
+            PodcastStatus::Unknown(status) => status,
+        }
+    }
+}
+
+impl Serialize for PodcastStatus {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        se.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for PodcastStatus {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(de)?;
+        Ok(PodcastStatus::from(raw.as_str()))
+    }
+}
+
 impl Podcast {
     /// Fetches the details of a single podcast and its episodes.
-    pub fn get<U>(client: &Client, id: U) -> Result<Podcast>
+    pub fn get<I>(client: &Client, id: I) -> Result<Podcast>
     where
-        U: Into<Option<usize>>,
+        I: Into<Id>,
     {
-        let channel = client.get("getPodcasts", Query::with("id", id.into()))?;
-        Ok(get_list_as!(channel, Podcast).remove(0))
+        let mut channel = client.get_list::<Podcast>(
+            "getPodcasts",
+            Query::with("id", id.into()),
+            "podcasts",
+            "channel",
+        )?;
+        Ok(channel.remove(0))
+    }
+
+    /// Subscribes the server to the podcast at the given feed URL.
+    ///
+    /// The server fetches the feed in the background, so the new podcast may
+    /// not appear in [`list`] straight away.
+    ///
+    /// # Errors
+    ///
+    /// The user needs the podcast role to manage podcasts.
+    ///
+    /// [`list`]: #method.list
+    pub fn create(client: &Client, url: &str) -> Result<()> {
+        client.get_value("createPodcastChannel", Query::with("url", url))?;
+        Ok(())
     }
+
+    /// Unsubscribes the server from the podcast, deleting its episodes.
+    ///
+    /// # Errors
+    ///
+    /// The user needs the podcast role to manage podcasts.
+    pub fn delete(&self, client: &Client) -> Result<()> {
+        client.get_value("deletePodcastChannel", Query::with("id", self.id.as_str()))?;
+        Ok(())
+    }
+
     /// Returns a list of all podcasts the server subscribes to and,
     /// optionally, their episodes.
     pub fn list<B, U>(client: &Client, include_episodes: B) -> Result<Vec<Podcast>>
@@ -66,26 +182,277 @@ impl Podcast {
         B: Into<Option<bool>>,
         U: Into<Option<usize>>,
     {
-        let channel = client.get(
+        client.get_list(
             "getPodcasts",
             Query::with("includeEpisodes", include_episodes.into()),
-        )?;
-        Ok(get_list_as!(channel, Podcast))
+            "podcasts",
+            "channel",
+        )
+    }
+
+    /// Returns the podcast's episodes.
+    ///
+    /// Podcasts listed without their episodes (such as from [`list`] with
+    /// `include_episodes` set to `false`) have them fetched from the server.
+    /// Otherwise, the episodes already held are returned without a request.
+    ///
+    /// [`list`]: #method.list
+    pub fn episodes(&self, client: &Client) -> Result<Vec<Episode>> {
+        if !self.episodes.is_empty() {
+            return Ok(self.episodes.clone());
+        }
+        fetch_episodes(client, &self.id)
+    }
+
+    /// Returns the podcast's episodes, sorted with the most recently
+    /// published first.
+    ///
+    /// Publish dates are ISO8601 timestamps, which sort chronologically as
+    /// strings. Episodes without a publish date are placed last.
+    pub fn episodes_newest_first(&self) -> Vec<&Episode> {
+        let mut episodes = self.episodes.iter().collect::<Vec<_>>();
+        episodes.sort_by(|a, b| newest_first(a, b));
+        episodes
+    }
+}
+
+/// Fetches the episodes of the podcast with the given ID.
+fn fetch_episodes(client: &Client, id: &Id) -> Result<Vec<Episode>> {
+    let args = Query::with("id", id.as_str())
+        .arg("includeEpisodes", true)
+        .build();
+    let channel = client.get_list::<Podcast>("getPodcasts", args, "podcasts", "channel")?;
+    Ok(channel
+        .into_iter()
+        .next()
+        .map(|podcast| podcast.episodes)
+        .unwrap_or_default())
+}
+
+/// Orders episodes with the most recently published first, and those without
+/// a publish date last.
+fn newest_first(a: &Episode, b: &Episode) -> Ordering {
+    match (a.publish_date.is_empty(), b.publish_date.is_empty()) {
+        (false, false) => b.publish_date.cmp(&a.publish_date),
+        (a_missing, b_missing) => a_missing.cmp(&b_missing),
+    }
+}
+
+impl Media for Podcast {
+    fn has_cover_art(&self) -> bool {
+        self.cover_art.is_some()
+    }
+
+    fn cover_id(&self) -> Option<&str> {
+        self.cover_art.as_deref()
+    }
+
+    fn cover_art<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<Vec<u8>> {
+        let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
+        let query = Query::with("id", cover).arg("size", size.into()).build();
+
+        client.get_image("getCoverArt", query)
+    }
+
+    fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String> {
+        let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
+        client.cover_art_url(cover, size)
     }
 }
 
 impl Episode {
     /// Returns a list of the newest episodes of podcasts the server subscribes
     /// to. Optionally takes a number of episodes to maximally return.
+    ///
+    /// The server returns 20 episodes by default, and at most 500; larger
+    /// counts are clamped.
     pub fn newest<U>(client: &Client, count: U) -> Result<Vec<Episode>>
     where
         U: Into<Option<usize>>,
     {
-        let episode = client.get("getNewestPodcasts", Query::with("count", count.into()))?;
-        Ok(get_list_as!(episode, Episode))
+        client.get_list(
+            "getNewestPodcasts",
+            newest_args(count.into()),
+            "newestPodcasts",
+            "episode",
+        )
+    }
+
+    /// Creates a new builder to request the newest episodes, optionally from a
+    /// single podcast, and to page through them.
+    ///
+    /// See [`NewestEpisodes`] for more information.
+    ///
+    /// [`NewestEpisodes`]: ./struct.NewestEpisodes.html
+    pub fn newest_with(client: &Client) -> NewestEpisodes<'_> {
+        NewestEpisodes {
+            client,
+            count: None,
+            channel: None,
+        }
+    }
+
+    /// Asks the server to download the episode, so it can be streamed from
+    /// the server.
+    ///
+    /// The download happens in the background; the episode's status changes
+    /// once it starts.
+    ///
+    /// # Errors
+    ///
+    /// The user needs the podcast role to manage podcasts.
+    pub fn download_to_server(&self, client: &Client) -> Result<()> {
+        client.get_value(
+            "downloadPodcastEpisode",
+            Query::with("id", self.id.as_str()),
+        )?;
+        Ok(())
+    }
+
+    /// Deletes the episode from the server.
+    ///
+    /// # Errors
+    ///
+    /// The user needs the podcast role to manage podcasts.
+    pub fn delete(&self, client: &Client) -> Result<()> {
+        client.get_value("deletePodcastEpisode", Query::with("id", self.id.as_str()))?;
+        Ok(())
+    }
+}
+
+/// A builder for a query of the newest podcast episodes.
+///
+/// A `NewestEpisodes` is created with [`Episode::newest_with`].
+///
+/// The server's list of newest episodes can't be filtered, so episodes of a
+/// single podcast are instead fetched with the podcast itself and sorted by
+/// publish date. Neither query can be paged on the server: [`pages`] makes a
+/// single request and hands the episodes out a page at a time.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sunk::podcast::Episode;
+/// use sunk::Client;
+/// # fn run() -> sunk::Result<()> {
+/// # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+///
+/// for page in Episode::newest_with(&client).in_channel(1).pages(20)? {
+///     for episode in page {
+///         println!("{}", episode.title);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+///
+/// [`Episode::newest_with`]: ./struct.Episode.html#method.newest_with
+/// [`pages`]: #method.pages
+#[derive(Debug)]
+pub struct NewestEpisodes<'a> {
+    client: &'a Client,
+    count: Option<usize>,
+    channel: Option<Id>,
+}
+
+impl<'a> NewestEpisodes<'a> {
+    /// Sets the maximum number of episodes to return. The server returns 20
+    /// episodes by default, and at most 500; episodes of a single podcast
+    /// (see [`in_channel`]) are also limited to 20 by default, but have no
+    /// upper limit.
+    ///
+    /// [`in_channel`]: #method.in_channel
+    pub fn count(&mut self, n: usize) -> &mut NewestEpisodes<'a> {
+        self.count = Some(n);
+        self
+    }
+
+    /// Only returns episodes of the podcast with the given ID.
+    ///
+    /// The episodes are fetched with the podcast (`getPodcasts`) rather than
+    /// from the server's list of newest episodes, so episodes older than
+    /// that list reaches are included.
+    pub fn in_channel<I: Into<Id>>(&mut self, id: I) -> &mut NewestEpisodes<'a> {
+        self.channel = Some(id.into());
+        self
+    }
+
+    /// Issues the query to the Subsonic server, returning the newest episodes
+    /// first.
+    pub fn request(&self) -> Result<Vec<Episode>> {
+        let channel = match self.channel {
+            Some(ref channel) => channel,
+            None => {
+                return self.client.get_list(
+                    "getNewestPodcasts",
+                    newest_args(self.count),
+                    "newestPodcasts",
+                    "episode",
+                )
+            }
+        };
+
+        let mut episodes = fetch_episodes(self.client, channel)?;
+        episodes.sort_by(newest_first);
+        episodes.truncate(self.count.unwrap_or(DEFAULT_NEWEST_COUNT));
+        Ok(episodes)
+    }
+
+    /// Issues the query to the Subsonic server, returning an iterator over
+    /// pages of `page_size` episodes.
+    ///
+    /// All of the episodes are fetched up front in a single request; the
+    /// pages are split from that result, not requested from the server one at
+    /// a time.
+    pub fn pages(&self, page_size: usize) -> Result<EpisodePages> {
+        Ok(EpisodePages {
+            episodes: self.request()?.into_iter(),
+            page_size: page_size.max(1),
+        })
+    }
+}
+
+/// An iterator over pages of episodes, created by [`NewestEpisodes::pages`].
+///
+/// [`NewestEpisodes::pages`]: ./struct.NewestEpisodes.html#method.pages
+#[derive(Debug)]
+pub struct EpisodePages {
+    episodes: ::std::vec::IntoIter<Episode>,
+    page_size: usize,
+}
+
+impl Iterator for EpisodePages {
+    type Item = Vec<Episode>;
+
+    fn next(&mut self) -> Option<Vec<Episode>> {
+        let page = self
+            .episodes
+            .by_ref()
+            .take(self.page_size)
+            .collect::<Vec<_>>();
+        if page.is_empty() {
+            None
+        } else {
+            Some(page)
+        }
     }
 }
 
+/// The number of episodes the server returns from `getNewestPodcasts` if no
+/// count is given.
+const DEFAULT_NEWEST_COUNT: usize = 20;
+
+/// The maximum number of episodes the server will return from
+/// `getNewestPodcasts`.
+const MAX_NEWEST_COUNT: usize = 500;
+
+fn newest_args(count: Option<usize>) -> Query {
+    let count = count.map(|count| query::clamp_count("episodes", count, MAX_NEWEST_COUNT));
+
+    Query::with("count", count)
+}
+
 impl<'de> Deserialize<'de> for Podcast {
     fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
     where
@@ -94,13 +461,14 @@ impl<'de> Deserialize<'de> for Podcast {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Podcast {
-            id: String,
+            id: Id,
             url: String,
             title: String,
+            #[serde(default)]
             description: String,
-            cover_art: String,
-            image_url: String,
-            status: String,
+            cover_art: Option<String>,
+            image_url: Option<String>,
+            status: PodcastStatus,
             #[serde(default)]
             episode: Vec<Episode>,
             #[serde(default)]
@@ -110,7 +478,7 @@ impl<'de> Deserialize<'de> for Podcast {
         let raw = _Podcast::deserialize(de)?;
 
         Ok(Podcast {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             url: raw.url,
             title: raw.title,
             description: raw.description,
@@ -135,36 +503,40 @@ impl<'de> Deserialize<'de> for Episode {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Episode {
-            id: String,
-            parent: String,
+            id: Id,
+            parent: Option<Id>,
+            #[serde(default)]
             is_dir: bool,
             title: String,
-            album: String,
-            artist: String,
-            year: usize,
-            cover_art: String,
-            size: usize,
-            content_type: String,
-            suffix: String,
-            duration: usize,
-            bit_rate: usize,
+            album: Option<String>,
+            artist: Option<String>,
+            year: Option<usize>,
+            cover_art: Option<String>,
+            size: Option<usize>,
+            content_type: Option<String>,
+            suffix: Option<String>,
+            duration: Option<usize>,
+            bit_rate: Option<usize>,
+            #[serde(default)]
             is_video: bool,
-            created: String,
-            artist_id: String,
+            created: Option<String>,
+            artist_id: Option<String>,
             #[serde(rename = "type")]
-            _type: String,
-            stream_id: String,
-            channel_id: String,
+            _type: Option<String>,
+            stream_id: Option<String>,
+            channel_id: Id,
+            #[serde(default)]
             description: String,
-            status: String,
+            status: PodcastStatus,
+            #[serde(default)]
             publish_date: String,
         }
 
         let raw = _Episode::deserialize(de)?;
 
         Ok(Episode {
-            id: raw.id.parse().unwrap(),
-            parent: raw.parent.parse().unwrap(),
+            id: raw.id,
+            parent: raw.parent,
             is_dir: raw.is_dir,
             title: raw.title,
             album: raw.album,
@@ -188,3 +560,258 @@ impl<'de> Deserialize<'de> for Episode {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn manage_channels() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok.clone(), ok.clone(), ok]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        Podcast::create(&cli, "http://example.com/feed.xml").unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/createPodcastChannel?"));
+        assert!(request.contains("url=http%3A%2F%2Fexample.com%2Ffeed.xml"));
+
+        let podcast = serde_json::from_value::<Podcast>(raw()).unwrap();
+        podcast.delete(&cli).unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/deletePodcastChannel?"));
+        assert!(request.contains("id=1"));
+
+        cli.refresh_podcasts().unwrap();
+        assert!(requests
+            .recv()
+            .unwrap()
+            .starts_with("GET /rest/refreshPodcasts?"));
+    }
+
+    #[test]
+    fn manage_episodes() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok.clone(), ok]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let episode = serde_json::from_value::<Episode>(episode(34, "")).unwrap();
+
+        episode.download_to_server(&cli).unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/downloadPodcastEpisode?"));
+        assert!(request.contains("id=34"));
+
+        episode.delete(&cli).unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/deletePodcastEpisode?"));
+        assert!(request.contains("id=34"));
+    }
+
+    #[test]
+    fn parse_status() {
+        let parsed = serde_json::from_value::<Podcast>(raw()).unwrap();
+        assert_eq!(parsed.status, PodcastStatus::Completed);
+        assert_eq!(parsed.episodes[0].status, PodcastStatus::Completed);
+
+        let mut raw = episode(34, "");
+        raw["status"] = "queued".into();
+        let parsed = serde_json::from_value::<Episode>(raw).unwrap();
+        assert_eq!(parsed.status, PodcastStatus::Unknown("queued".into()));
+        assert_eq!(PodcastStatus::from("skipped"), PodcastStatus::Skipped);
+    }
+
+    #[test]
+    fn parse_sparse_podcast() {
+        let parsed = serde_json::from_str::<Podcast>(
+            r#"{
+            "id" : "2",
+            "url" : "http://example.com/feed.xml",
+            "title" : "Sparse",
+            "status" : "new",
+            "episode" : [ {
+                "id" : "40",
+                "title" : "Pilot",
+                "channelId" : "2",
+                "status" : "new"
+            } ]
+        }"#,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.cover_art, None);
+        assert_eq!(parsed.description, "");
+        let episode = &parsed.episodes[0];
+        assert_eq!(episode.stream_id, None);
+        assert_eq!(episode.size, None);
+        assert_eq!(episode.publish_date, "");
+    }
+
+    #[test]
+    fn parse_hex_ids() {
+        let parsed = serde_json::from_str::<Podcast>(
+            r#"{
+            "id" : "9c2f0e7ab41d",
+            "url" : "http://example.com/feed.xml",
+            "title" : "Hex",
+            "status" : "completed",
+            "episode" : [ {
+                "id" : "e3a1b07f",
+                "title" : "Pilot",
+                "channelId" : "9c2f0e7ab41d",
+                "status" : "completed"
+            } ]
+        }"#,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.id, "9c2f0e7ab41d");
+        assert_eq!(parsed.episodes[0].id, "e3a1b07f");
+        assert_eq!(parsed.episodes[0].channel_id, parsed.id);
+    }
+
+    #[test]
+    fn newest_in_channel_paged() {
+        let mut channel = raw();
+        channel["episode"] = serde_json::json!([
+            episode(33, "2011-01-27T14:46:43.000Z"),
+            episode(36, "2011-02-10T14:46:43.000Z"),
+            episode(32, "2011-01-20T14:46:43.000Z"),
+            episode(34, "2011-02-03T14:46:43.000Z"),
+        ]);
+        let body = serde_json::json!({
+            "subsonic-response": {
+                "status": "ok",
+                "version": "1.14.0",
+                "podcasts": { "channel": [ channel ] }
+            }
+        });
+        let (site, requests) =
+            test_util::recording_mock_server(&[test_util::json_response(&body.to_string())]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let pages = Episode::newest_with(&cli)
+            .count(3)
+            .in_channel(1)
+            .pages(2)
+            .unwrap()
+            .map(|page| page.iter().map(|e| e.id.to_string()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(pages, vec![vec!["36", "34"], vec!["33"]]);
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/getPodcasts?"));
+        assert!(request.contains("id=1&includeEpisodes=true"));
+    }
+
+    #[test]
+    fn podcast_cover_art() {
+        let cli = test_util::demo_site().unwrap();
+        let parsed = serde_json::from_value::<Podcast>(raw()).unwrap();
+
+        assert!(parsed.has_cover_art());
+        let url = parsed.cover_art_url(&cli, 300).unwrap();
+        assert!(url.contains("/rest/getCoverArt?"));
+        assert!(url.contains("id=pod-1&size=300"));
+    }
+
+    #[test]
+    fn episodes_fetched_lazily() {
+        let body = serde_json::json!({
+            "subsonic-response": {
+                "status": "ok",
+                "version": "1.14.0",
+                "podcasts": { "channel": [ raw() ] }
+            }
+        });
+        let (site, requests) =
+            test_util::recording_mock_server(&[test_util::json_response(&body.to_string())]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let mut bare = raw();
+        bare.as_object_mut().unwrap().remove("episode");
+        let bare = serde_json::from_value::<Podcast>(bare).unwrap();
+        assert_eq!(bare.episodes(&cli).unwrap().len(), 3);
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/getPodcasts?"));
+        assert!(request.contains("id=1&includeEpisodes=true"));
+
+        // Episodes already held are returned without asking the server, which
+        // has no more responses to give.
+        let full = serde_json::from_value::<Podcast>(raw()).unwrap();
+        assert_eq!(full.episodes(&cli).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn newest_count_clamped() {
+        assert!(newest_args(Some(1000)).to_string().contains("count=500"));
+        assert!(newest_args(Some(10)).to_string().contains("count=10"));
+    }
+
+    #[test]
+    fn episodes_newest_first() {
+        let parsed = serde_json::from_value::<Podcast>(raw()).unwrap();
+        let ids = parsed
+            .episodes_newest_first()
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, vec!["36", "34", "35"]);
+    }
+
+    fn episode(id: usize, publish_date: &str) -> serde_json::Value {
+        serde_json::from_str(&format!(
+            r#"{{
+            "id" : "{id}",
+            "parent" : "11",
+            "isDir" : false,
+            "title" : "Episode {id}",
+            "album" : "Dr Karl and the Naked Scientist",
+            "artist" : "BBC Radio Five live",
+            "year" : 2011,
+            "coverArt" : "24",
+            "size" : 45138316,
+            "contentType" : "audio/mpeg",
+            "suffix" : "mp3",
+            "duration" : 3146,
+            "bitRate" : 128,
+            "isVideo" : false,
+            "created" : "2011-02-03T14:46:43",
+            "artistId" : "453",
+            "type" : "podcast",
+            "streamId" : "523",
+            "channelId" : "1",
+            "description" : "Dr Karl answers all your science related questions.",
+            "status" : "completed",
+            "publishDate" : "{publish_date}"
+        }}"#
+        ))
+        .unwrap()
+    }
+
+    fn raw() -> serde_json::Value {
+        let mut raw = serde_json::from_str::<serde_json::Value>(
+            r#"{
+            "id" : "1",
+            "url" : "http://downloads.bbc.co.uk/podcasts/fivelive/drkarl/rss.xml",
+            "title" : "Dr Karl and the Naked Scientist",
+            "description" : "Dr Chris Smith aka The Naked Scientist.",
+            "coverArt" : "pod-1",
+            "originalImageUrl" : "http://downloads.bbc.co.uk/podcasts/fivelive/drkarl/image.jpg",
+            "imageUrl" : "http://downloads.bbc.co.uk/podcasts/fivelive/drkarl/image.jpg",
+            "status" : "completed"
+        }"#,
+        )
+        .unwrap();
+        raw["episode"] = serde_json::Value::Array(vec![
+            episode(34, "2011-02-03T14:46:43.000Z"),
+            episode(35, ""),
+            episode(36, "2011-02-10T14:46:43.000Z"),
+        ]);
+        raw
+    }
+}
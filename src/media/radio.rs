@@ -5,13 +5,14 @@ use std::result;
 use serde::de::{Deserialize, Deserializer};
 
 use crate::query::Query;
-use crate::{Client, Result};
+use crate::{Client, Id, Result};
 
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[readonly::make]
 pub struct RadioStation {
-    pub id: usize,
+    pub id: Id,
     pub name: String,
     pub stream_url: String,
     pub homepage_url: Option<String>,
@@ -25,14 +26,14 @@ impl<'de> Deserialize<'de> for RadioStation {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Station {
-            id: String,
+            id: Id,
             name: String,
             stream_url: String,
             homepage_url: Option<String>,
         }
         let raw = _Station::deserialize(de)?;
         Ok(RadioStation {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             name: raw.name,
             stream_url: raw.stream_url,
             homepage_url: raw.homepage_url,
@@ -42,14 +43,17 @@ impl<'de> Deserialize<'de> for RadioStation {
 
 #[allow(missing_docs)]
 impl RadioStation {
-    pub fn id(&self) -> usize {
-        self.id
+    pub fn id(&self) -> &Id {
+        &self.id
     }
 
     pub fn list(client: &Client) -> Result<Vec<RadioStation>> {
-        #[allow(non_snake_case)]
-        let internetRadioStation = client.get("getInternetRadioStations", Query::none())?;
-        Ok(get_list_as!(internetRadioStation, RadioStation))
+        client.get_list(
+            "getInternetRadioStations",
+            Query::none(),
+            "internetRadioStations",
+            "internetRadioStation",
+        )
     }
 
     pub fn create(client: &Client, name: &str, url: &str, homepage: Option<&str>) -> Result<()> {
@@ -57,22 +61,25 @@ impl RadioStation {
             .arg("streamUrl", url)
             .arg("homepageUrl", homepage)
             .build();
-        client.get("createInternetRadioStation", args)?;
+        client.get_value("createInternetRadioStation", args)?;
         Ok(())
     }
 
     pub fn update(&self, client: &Client) -> Result<()> {
-        let args = Query::with("id", self.id)
+        let args = Query::with("id", self.id.as_str())
             .arg("streamUrl", self.stream_url.as_str())
             .arg("name", self.name.as_str())
             .arg("homepageUrl", self.homepage_url.as_deref())
             .build();
-        client.get("updateInternetRadioStation", args)?;
+        client.get_value("updateInternetRadioStation", args)?;
         Ok(())
     }
 
     pub fn delete(&self, client: &Client) -> Result<()> {
-        client.get("deleteInternetRadioStation", Query::with("id", self.id))?;
+        client.get_value(
+            "deleteInternetRadioStation",
+            Query::with("id", self.id.as_str()),
+        )?;
         Ok(())
     }
 }
@@ -2,39 +2,64 @@
 
 use std::fmt;
 use std::ops::Range;
+use std::thread;
 
 use serde::de::{Deserialize, Deserializer};
-use serde_json;
 
+use crate::id;
+use crate::media::{id3, stream_bit_rate, StreamOptions};
 use crate::query::Query;
 use crate::search::SearchPage;
-use crate::{Client, Error, HlsPlaylist, Media, Result, Streamable};
+use crate::{Album, ApiError, Client, Error, HlsPlaylist, Id, Media, Result, Streamable};
 
 /// A work of music contained on a Subsonic server.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[readonly::make]
 pub struct Song {
     /// Unique identifier for the song.
-    pub id: u64,
+    pub id: Id,
+    /// The ID of the directory containing the song, if the server reports
+    /// one. Folder-based clients use it to navigate back up the directory
+    /// tree.
+    pub parent: Option<Id>,
+    /// Whether the entry is a directory rather than a file. Always `false`
+    /// for entries returned as songs by ID3-based endpoints.
+    pub is_dir: bool,
     /// Title of the song. Prefers the song's ID3 tags, but will fall back to
     /// the file name.
     pub title: String,
     /// Album the song belongs to. Reads from the song's ID3 tags.
     pub album: Option<String>,
     /// The ID of the released album.
-    pub album_id: Option<u64>,
+    pub album_id: Option<Id>,
     /// Credited artist for the song. Reads from the song's ID3 tags.
     pub artist: Option<String>,
     /// The ID of the releasing artist.
-    pub artist_id: Option<u64>,
+    pub artist_id: Option<Id>,
     /// Position of the song in the album.
     pub track: Option<u64>,
     /// Year the song was released.
     pub year: Option<u64>,
-    /// Genre of the song.
+    /// Genre of the song, as reported by the server. See [`genre_name`] for a
+    /// normalised name.
+    ///
+    /// [`genre_name`]: #method.genre_name
     pub genre: Option<String>,
+    /// The song's [MusicBrainz](https://musicbrainz.org/) recording ID, if
+    /// the server provides it.
+    #[serde(rename = "musicBrainzId")]
+    pub musicbrainz_id: Option<String>,
     /// ID of the song's cover art. Defaults to the parent album's cover.
+    #[serde(rename = "coverArt")]
     pub cover_id: Option<String>,
+    /// The average rating given to the song by all users, from 1 to 5.
+    pub average_rating: Option<f32>,
+    /// The rating given to the song by the current user, from 1 to 5.
+    pub user_rating: Option<u8>,
+    /// When the current user starred the song (as an ISO8601 timestamp), if
+    /// they have.
+    pub starred: Option<String>,
     /// File size of the song, in bytes.
     pub size: u64,
     /// An audio MIME type.
@@ -47,13 +72,18 @@ pub struct Song {
     pub transcoded_suffix: Option<String>,
     /// Duration of the song, in seconds.
     pub duration: Option<u64>,
+    /// Bit rate of the song, in Kbps, if the server reports it.
+    pub bit_rate: Option<u64>,
     /// The absolute path of the song in the server database.
     pub path: String,
     /// Will always be "song".
+    #[serde(rename = "type")]
     pub media_type: String,
     /// Bit rate the song will be downsampled to.
+    #[serde(skip)]
     pub stream_br: Option<usize>,
     /// Format the song will be transcoded to.
+    #[serde(skip)]
     pub stream_tc: Option<String>,
 }
 
@@ -64,9 +94,29 @@ impl Song {
     ///
     /// Aside from other errors the `Client` may cause, the server will return
     /// an error if there is no song matching the provided ID.
-    pub fn get(client: &Client, id: u64) -> Result<Song> {
-        let res = client.get("getSong", Query::with("id", id))?;
-        Ok(serde_json::from_value(res)?)
+    pub fn get<I: Into<Id>>(client: &Client, id: I) -> Result<Song> {
+        client.get_required("getSong", Query::with("id", id.into()), &["song"])
+    }
+
+    /// Returns whether the current user has starred the song.
+    pub fn is_starred(&self) -> bool {
+        self.starred.is_some()
+    }
+
+    /// Returns when the current user starred the song, if they have.
+    #[cfg(feature = "chrono")]
+    pub fn starred_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.starred.as_deref().and_then(|s| s.parse().ok())
+    }
+
+    /// Returns the name of the song's genre.
+    ///
+    /// Some servers pass through raw ID3 genre codes, such as `"(17)"` for
+    /// rock. These are mapped to the name of the genre; code 255, which ID3
+    /// uses for "no genre", returns `None`. Genres that aren't numeric codes
+    /// are returned as-is.
+    pub fn genre_name(&self) -> Option<&str> {
+        self.genre.as_deref().and_then(id3::genre_name)
     }
 
     /// Returns a number of random songs similar to this one.
@@ -78,12 +128,11 @@ impl Song {
     where
         U: Into<Option<usize>>,
     {
-        let args = Query::with("id", self.id)
+        let args = Query::with("id", self.id.as_str())
             .arg("count", count.into())
             .build();
 
-        let song = client.get("getSimilarSongs2", args)?;
-        Ok(get_list_as!(song, Song))
+        client.get_list("getSimilarSongs2", args, "similarSongs2", "song")
     }
 
     /// Returns a number of random songs. Optionally accepts a maximum number
@@ -97,9 +146,10 @@ impl Song {
     where
         U: Into<Option<usize>>,
     {
-        let arg = Query::with("size", size.into().unwrap_or(10));
-        let song = client.get("getRandomSongs", arg)?;
-        Ok(get_list_as!(song, Song))
+        let arg = Query::with("size", size.into().unwrap_or(10))
+            .arg("musicFolderId", client.folder_or_default(None))
+            .build();
+        client.get_list("getRandomSongs", arg, "randomSongs", "song")
     }
 
     /// Creates a new builder to request a set of random songs.
@@ -125,16 +175,15 @@ impl Song {
         folder_id: U,
     ) -> Result<Vec<Song>>
     where
-        U: Into<Option<u64>>,
+        U: Into<Option<Id>>,
     {
         let args = Query::with("genre", genre)
             .arg("count", page.count)
-            .arg("offset", page.offset)
-            .arg("musicFolderId", folder_id.into())
+            .arg("offset", page.result_offset())
+            .arg("musicFolderId", client.folder_or_default(folder_id.into()))
             .build();
 
-        let song = client.get("getSongsByGenre", args)?;
-        Ok(get_list_as!(song, Song))
+        client.get_list("getSongsByGenre", args, "songsByGenre", "song")
     }
 
     /// Creates an HLS (HTTP Live Streaming) playlist used for streaming video
@@ -152,34 +201,41 @@ impl Song {
     /// empty array) to disable adaptive streaming, or given a single value to
     /// force streaming at that bit rate.
     pub fn hls(&self, client: &Client, bit_rates: &[u64]) -> Result<HlsPlaylist> {
-        let args = Query::with("id", self.id)
+        let args = Query::with("id", self.id.as_str())
             .arg_list("bitrate", bit_rates)
             .build();
 
         let raw = client.get_raw("hls", args)?;
         raw.parse::<HlsPlaylist>()
     }
+
+    fn stream_options(&self) -> StreamOptions {
+        let mut options = StreamOptions::new();
+        if let Some(bit_rate) = self.stream_br {
+            options.max_bit_rate(bit_rate);
+        }
+        if let Some(ref format) = self.stream_tc {
+            options.format(format);
+        }
+        options
+    }
 }
 
 impl Streamable for Song {
     fn stream(&self, client: &Client) -> Result<Vec<u8>> {
-        let mut q = Query::with("id", self.id);
-        q.arg("maxBitRate", self.stream_br);
-        client.get_bytes("stream", q)
+        client.get_bytes("stream", self.stream_options().to_args(&self.id))
     }
 
     fn stream_url(&self, client: &Client) -> Result<String> {
-        let mut q = Query::with("id", self.id);
-        q.arg("maxBitRate", self.stream_br);
-        client.build_url("stream", q)
+        client.stream_url(&self.id, &self.stream_options())
     }
 
     fn download(&self, client: &Client) -> Result<Vec<u8>> {
-        client.get_bytes("download", Query::with("id", self.id))
+        client.get_bytes("download", Query::with("id", self.id.as_str()))
     }
 
     fn download_url(&self, client: &Client) -> Result<String> {
-        client.build_url("download", Query::with("id", self.id))
+        client.download_url(&self.id)
     }
 
     fn encoding(&self) -> &str {
@@ -192,6 +248,10 @@ impl Streamable for Song {
         self.stream_br = Some(bit_rate);
     }
 
+    fn bit_rate(&self) -> Option<u64> {
+        stream_bit_rate(self.bit_rate, self.stream_br)
+    }
+
     fn set_transcoding(&mut self, format: &str) {
         self.stream_tc = Some(format.to_string());
     }
@@ -206,18 +266,45 @@ impl Media for Song {
         self.cover_id.as_deref()
     }
 
+    /// Returns the raw bytes of the song's cover art.
+    ///
+    /// If the song has no cover of its own, or the server fails to provide it,
+    /// the cover of the album the song belongs to is tried instead. This costs
+    /// an additional request to look up the album.
     fn cover_art<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<Vec<u8>> {
-        let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
+        let size = size.into();
+
+        if let Some(cover) = self.cover_id() {
+            let query = Query::with("id", cover).arg("size", size).build();
+            match client.get_image("getCoverArt", query) {
+                Ok(bytes) => return Ok(bytes),
+                Err(e) => info!("Cover art {} for song {} failed: {}", cover, self.id, e),
+            }
+        }
 
-        client.get_bytes("getCoverArt", query)
+        if let Some(ref album_id) = self.album_id {
+            match Album::get(client, album_id) {
+                Ok(album) => {
+                    if let Some(cover) = album.cover_id() {
+                        let query = Query::with("id", cover).arg("size", size).build();
+                        match client.get_image("getCoverArt", query) {
+                            Ok(bytes) => return Ok(bytes),
+                            Err(e) => {
+                                info!("Cover art {} for album {} failed: {}", cover, album_id, e)
+                            }
+                        }
+                    }
+                }
+                Err(e) => info!("Album {} of song {} failed: {}", album_id, self.id, e),
+            }
+        }
+
+        Err(Error::Api(ApiError::NotFound))
     }
 
     fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String> {
         let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
-
-        client.build_url("getCoverArt", query)
+        client.cover_art_url(cover, size)
     }
 }
 
@@ -253,30 +340,35 @@ impl<'de> Deserialize<'de> for Song {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Song {
-            id: String,
-            // parent: String,
-            // is_dir: bool,
+            id: Id,
+            parent: Option<Id>,
+            #[serde(default)]
+            is_dir: bool,
             title: String,
             album: Option<String>,
             artist: Option<String>,
             track: Option<u64>,
             year: Option<u64>,
             genre: Option<String>,
+            music_brainz_id: Option<String>,
             cover_art: Option<String>,
+            average_rating: Option<f32>,
+            user_rating: Option<u8>,
+            starred: Option<String>,
             size: u64,
             content_type: String,
             suffix: String,
             transcoded_content_type: Option<String>,
             transcoded_suffix: Option<String>,
             duration: Option<u64>,
-            // bit_rate: Option<u64>,
+            bit_rate: Option<u64>,
             path: String,
             // is_video: Option<bool>,
             // play_count: u64,
             // disc_number: Option<u64>,
             // created: String,
-            album_id: Option<String>,
-            artist_id: Option<String>,
+            album_id: Option<Id>,
+            artist_id: Option<Id>,
             #[serde(rename = "type")]
             media_type: String,
         }
@@ -284,22 +376,29 @@ impl<'de> Deserialize<'de> for Song {
         let raw = _Song::deserialize(de)?;
 
         Ok(Song {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
+            parent: raw.parent,
+            is_dir: raw.is_dir,
             title: raw.title,
             album: raw.album,
-            album_id: raw.album_id.map(|i| i.parse().unwrap()),
+            album_id: raw.album_id,
             artist: raw.artist,
-            artist_id: raw.artist_id.map(|i| i.parse().unwrap()),
+            artist_id: raw.artist_id,
             cover_id: raw.cover_art,
+            average_rating: raw.average_rating,
+            user_rating: raw.user_rating,
+            starred: raw.starred,
             track: raw.track,
             year: raw.year,
             genre: raw.genre,
+            musicbrainz_id: raw.music_brainz_id,
             size: raw.size,
             content_type: raw.content_type,
             suffix: raw.suffix,
             transcoded_content_type: raw.transcoded_content_type,
             transcoded_suffix: raw.transcoded_suffix,
             duration: raw.duration,
+            bit_rate: raw.bit_rate,
             path: raw.path,
             media_type: raw.media_type,
             stream_br: None,
@@ -309,7 +408,7 @@ impl<'de> Deserialize<'de> for Song {
 }
 
 /// A struct matching a lyric search result.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Lyrics {
     /// Title of the song.
     pub title: String,
@@ -364,7 +463,7 @@ pub struct RandomSongs<'a> {
     genre: Option<&'a str>,
     from_year: Option<usize>,
     to_year: Option<usize>,
-    folder_id: Option<usize>,
+    folder_ids: Vec<Id>,
 }
 
 impl<'a> RandomSongs<'a> {
@@ -375,7 +474,7 @@ impl<'a> RandomSongs<'a> {
             genre: None,
             from_year: None,
             to_year: None,
-            folder_id: None,
+            folder_ids: Vec::new(),
         }
     }
 
@@ -419,33 +518,90 @@ impl<'a> RandomSongs<'a> {
         self
     }
 
-    /// Sets the folder index that songs must be in.
+    /// Sets the folder that songs must be in.
     ///
-    /// Music folders are zero-indexed, and there will always be index `0`
-    /// (provided the server is configured at all) . A list of music
-    /// folders can be found using the [`Client::music_folders`] method.
+    /// A list of music folders and their IDs can be found using the
+    /// [`Client::music_folders`] method.
     ///
     /// [`Client::music_folders`]: ../struct.Client.html#method.music_folders
-    pub fn in_folder(&mut self, id: usize) -> &mut RandomSongs<'a> {
-        self.folder_id = Some(id);
+    pub fn in_folder<I: Into<Id>>(&mut self, id: I) -> &mut RandomSongs<'a> {
+        self.folder_ids = vec![id.into()];
+        self
+    }
+
+    /// Sets a number of folders that songs may be in.
+    ///
+    /// The Subsonic server only accepts a single folder for random songs, so
+    /// this is a client-side union: one request is issued per folder
+    /// (concurrently), and the results are interleaved up to the requested
+    /// size.
+    ///
+    /// See [`in_folder`] for more information on music folders.
+    ///
+    /// [`in_folder`]: #method.in_folder
+    pub fn in_folders<I: Into<Id> + Clone>(&mut self, ids: &[I]) -> &mut RandomSongs<'a> {
+        self.folder_ids = id::collect(ids);
         self
     }
 
     /// Issues the query to the Subsonic server. Returns a list of random
     /// songs, modified by the builder.
     pub fn request(&mut self) -> Result<Vec<Song>> {
+        if self.folder_ids.len() <= 1 {
+            return self.request_in(self.folder_ids.first().cloned());
+        }
+
+        let this = &*self;
+        let lists = thread::scope(|s| {
+            let handles = this
+                .folder_ids
+                .iter()
+                .map(|id| s.spawn(move || this.request_in(Some(id.clone()))))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("random song request panicked"))
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        Ok(interleave(lists, self.size))
+    }
+
+    fn request_in(&self, folder_id: Option<Id>) -> Result<Vec<Song>> {
         let args = Query::with("size", self.size)
             .arg("genre", self.genre)
             .arg("fromYear", self.from_year)
             .arg("toYear", self.to_year)
-            .arg("musicFolderId", self.folder_id)
+            .arg("musicFolderId", self.client.folder_or_default(folder_id))
             .build();
 
-        let song = self.client.get("getRandomSongs", args)?;
-        Ok(get_list_as!(song, Song))
+        self.client
+            .get_list("getRandomSongs", args, "randomSongs", "song")
     }
 }
 
+/// Takes items from each list in turn until `n` items are taken or every list
+/// is exhausted.
+fn interleave<T>(lists: Vec<Vec<T>>, n: usize) -> Vec<T> {
+    let mut lists = lists.into_iter().map(Vec::into_iter).collect::<Vec<_>>();
+    let mut out = Vec::with_capacity(n);
+
+    while out.len() < n {
+        let taken = out.len();
+        for item in lists.iter_mut().filter_map(Iterator::next) {
+            out.push(item);
+            if out.len() == n {
+                break;
+            }
+        }
+        if out.len() == taken {
+            break;
+        }
+    }
+
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -455,9 +611,46 @@ mod tests {
     fn parse_song() {
         let parsed = serde_json::from_value::<Song>(raw()).unwrap();
 
-        assert_eq!(parsed.id, 27);
+        assert_eq!(parsed.id, "27");
+        assert_eq!(parsed.parent, Some(Id::from(25)));
+        assert!(!parsed.is_dir);
         assert_eq!(parsed.title, String::from("Bellevue Avenue"));
         assert_eq!(parsed.track, Some(1));
+        assert_eq!(parsed.musicbrainz_id, None);
+        assert_eq!(parsed.average_rating, Some(3.0));
+        assert_eq!(parsed.user_rating, None);
+        assert!(parsed.is_starred());
+    }
+
+    #[test]
+    fn song_genre_name() {
+        let mut raw = raw();
+        let parsed = serde_json::from_value::<Song>(raw.clone()).unwrap();
+        assert_eq!(parsed.genre.as_deref(), Some("(255)"));
+        assert_eq!(parsed.genre_name(), None);
+
+        raw["genre"] = "(17)".into();
+        let parsed = serde_json::from_value::<Song>(raw).unwrap();
+        assert_eq!(parsed.genre_name(), Some("Rock"));
+    }
+
+    #[test]
+    fn parse_song_musicbrainz_id() {
+        let mut raw = raw();
+        raw["musicBrainzId"] = "8f8ec9b4-6a5c-4a4b-9a3c-1f6e2ad1d3a2".into();
+        let parsed = serde_json::from_value::<Song>(raw).unwrap();
+
+        assert_eq!(
+            parsed.musicbrainz_id.as_deref(),
+            Some("8f8ec9b4-6a5c-4a4b-9a3c-1f6e2ad1d3a2")
+        );
+    }
+
+    #[test]
+    fn interleave_lists() {
+        let lists = vec![vec![1, 4, 6], vec![2, 5], vec![3]];
+        assert_eq!(interleave(lists.clone(), 10), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(interleave(lists, 4), vec![1, 2, 3, 4]);
     }
 
     #[test]
@@ -469,6 +662,135 @@ mod tests {
         assert_eq!(hls.len(), 20)
     }
 
+    #[test]
+    fn download_to_writer() {
+        let body = "HTTP/1.1 200 OK\r\nContent-Type: audio/mpeg\r\n\
+                    Content-Length: 4\r\nConnection: close\r\n\r\nmp3!";
+        let (site, requests) = test_util::recording_mock_server(&[body]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let song = serde_json::from_value::<Song>(raw()).unwrap();
+
+        let mut out = Vec::new();
+        let n = song.download_to(&cli, &mut out, None).unwrap();
+        assert_eq!(n, 4);
+        assert_eq!(out, b"mp3!");
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/download?"));
+        assert!(request.contains("&id=27"));
+    }
+
+    #[test]
+    fn stream_with_content_type() {
+        let body = "HTTP/1.1 200 OK\r\nContent-Type: audio/ogg\r\n\
+                    Content-Length: 4\r\nConnection: close\r\n\r\nogg!";
+        let site = test_util::mock_server(&[body]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let mut song = serde_json::from_value::<Song>(raw()).unwrap();
+        song.set_transcoding("ogg");
+
+        let res = song.stream_response(&cli).unwrap();
+        assert_eq!(res.bytes, b"ogg!");
+        assert_eq!(res.content_type.as_deref(), Some("audio/ogg"));
+        assert_eq!(res.content_length, Some(4));
+    }
+
+    #[test]
+    fn stream_url_options() {
+        let cli = Client::new("http://demo.subsonic.org", "guest3", "guest").unwrap();
+        let mut song = serde_json::from_value::<Song>(raw()).unwrap();
+        song.set_max_bit_rate(96);
+        song.set_transcoding("ogg");
+
+        let url = song.stream_url(&cli).unwrap();
+        assert!(url.starts_with("http://demo.subsonic.org/rest/stream?"));
+        assert!(url.ends_with("&id=27&maxBitRate=96&format=ogg"));
+        let url = song.download_url(&cli).unwrap();
+        assert!(url.starts_with("http://demo.subsonic.org/rest/download?"));
+        assert!(url.ends_with("&id=27"));
+    }
+
+    #[test]
+    fn cover_art_falls_back_to_album() {
+        let not_found = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "failed",
+                "version": "1.14.0",
+                "error": { "code": 70, "message": "Cover art not found" }
+            }}"#,
+        );
+        let image = "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n\
+                     Content-Length: 4\r\nConnection: close\r\n\r\nPNG!";
+        let (site, requests) = test_util::recording_mock_server(&[
+            not_found.clone(),
+            album_response(),
+            image.to_string(),
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let song = serde_json::from_value::<Song>(raw()).unwrap();
+
+        assert_eq!(song.cover_art(&cli, None).unwrap(), b"PNG!".to_vec());
+        let sent = requests.try_iter().collect::<Vec<_>>();
+        assert!(sent[0].contains("getCoverArt?") && sent[0].contains("id=25"));
+        assert!(sent[1].starts_with("GET /rest/getAlbum?"));
+        assert!(sent[2].contains("getCoverArt?") && sent[2].contains("id=al-1"));
+    }
+
+    #[test]
+    fn cover_art_not_found() {
+        let not_found = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "failed",
+                "version": "1.14.0",
+                "error": { "code": 70, "message": "Not found" }
+            }}"#,
+        );
+        let down = "HTTP/1.1 500 Internal Server Error\r\n\
+                    Content-Length: 0\r\nConnection: close\r\n\r\n";
+        let site = test_util::mock_server(&[
+            not_found.clone(),
+            down.to_string(),
+            not_found.clone(),
+            album_response(),
+            not_found.clone(),
+            not_found,
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let song = serde_json::from_value::<Song>(raw()).unwrap();
+
+        // The album can't be looked up, or has no cover either.
+        for _ in 0..2 {
+            assert!(matches!(
+                song.cover_art(&cli, None),
+                Err(Error::Api(ApiError::NotFound))
+            ));
+        }
+        // Without an album, only the song's own cover is tried.
+        let mut raw = raw();
+        raw.as_object_mut().unwrap().remove("albumId");
+        let song = serde_json::from_value::<Song>(raw).unwrap();
+        assert!(matches!(
+            song.cover_art(&cli, None),
+            Err(Error::Api(ApiError::NotFound))
+        ));
+    }
+
+    fn album_response() -> String {
+        test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "ok",
+                "version": "1.14.0",
+                "album": {
+                    "id": "1",
+                    "name": "Bellevue",
+                    "coverArt": "al-1",
+                    "songCount": 9,
+                    "duration": 1920,
+                    "created": "2017-03-12T11:07:25.000Z"
+                }
+            }}"#,
+        )
+    }
+
     fn raw() -> serde_json::Value {
         serde_json::from_str(
             r#"{
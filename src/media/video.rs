@@ -2,21 +2,24 @@
 
 use std::result;
 
-use serde::de::{Deserialize, Deserializer};
-use serde_json;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
 
+use crate::media::StreamOptions;
 use crate::query::Query;
-use crate::{Client, Error, Media, Result, Streamable};
+use crate::{Client, Error, Id, Media, Result, Streamable};
 
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[readonly::make]
 pub struct Video {
-    pub id: usize,
-    pub parent: usize,
+    pub id: Id,
+    pub parent: Id,
     pub is_dir: bool,
     pub title: String,
     pub album: Option<String>,
+    #[serde(rename = "coverArt")]
     pub cover_id: Option<String>,
     pub size: usize,
     pub content_type: String,
@@ -24,24 +27,34 @@ pub struct Video {
     pub transcoded_suffix: Option<String>,
     pub transcoded_content_type: Option<String>,
     pub duration: usize,
+    #[serde(rename = "bitRate")]
     pub bitrate: usize,
     pub path: String,
     pub is_video: bool,
     pub created: String,
     pub play_count: Option<u64>,
+    pub played: Option<String>,
+    pub average_rating: Option<f32>,
+    pub user_rating: Option<u8>,
+    #[serde(rename = "type")]
     pub media_type: String,
     pub bookmark_position: Option<u64>,
     pub original_height: Option<u64>,
     pub original_width: Option<u64>,
+    #[serde(skip)]
     pub stream_br: Option<usize>,
+    #[serde(skip)]
     pub stream_size: Option<(usize, usize)>,
+    #[serde(skip)]
     pub stream_offset: usize,
+    #[serde(skip)]
     pub stream_tc: Option<String>,
 }
 
 impl Video {
     #[allow(missing_docs)]
-    pub fn get(client: &Client, id: usize) -> Result<Video> {
+    pub fn get<I: Into<Id>>(client: &Client, id: I) -> Result<Video> {
+        let id = id.into();
         Video::list(client)?
             .into_iter()
             .find(|v| v.id == id)
@@ -50,8 +63,7 @@ impl Video {
 
     #[allow(missing_docs)]
     pub fn list(client: &Client) -> Result<Vec<Video>> {
-        let video = client.get("getVideos", Query::none())?;
-        Ok(get_list_as!(video, Video))
+        client.get_list("getVideos", Query::none(), "videos", "video")
     }
 
     #[allow(missing_docs)]
@@ -59,11 +71,10 @@ impl Video {
     where
         S: Into<Option<&'a str>>,
     {
-        let args = Query::with("id", self.id)
+        let args = Query::with("id", self.id.as_str())
             .arg("format", format.into())
             .build();
-        let res = client.get("getVideoInfo", args)?;
-        Ok(serde_json::from_value(res)?)
+        client.get_required("getVideoInfo", args, &["videoInfo"])
     }
 
     /// Returns the raw video captions.
@@ -71,7 +82,7 @@ impl Video {
     where
         S: Into<Option<&'a str>>,
     {
-        let args = Query::with("id", self.id)
+        let args = Query::with("id", self.id.as_str())
             .arg("format", format.into())
             .build();
         let res = client.get_raw("getCaptions", args)?;
@@ -91,39 +102,38 @@ impl Video {
     pub fn set_start_time(&mut self, offset: usize) {
         self.stream_offset = offset;
     }
+
+    fn stream_options(&self) -> StreamOptions {
+        let mut options = StreamOptions::new();
+        if let Some(bit_rate) = self.stream_br {
+            options.max_bit_rate(bit_rate);
+        }
+        if let Some(ref format) = self.stream_tc {
+            options.format(format);
+        }
+        if let Some((width, height)) = self.stream_size {
+            options.size(width, height);
+        }
+        options.time_offset(self.stream_offset);
+        options
+    }
 }
 
 impl Streamable for Video {
     fn stream(&self, client: &Client) -> Result<Vec<u8>> {
-        let args = Query::with("id", self.id)
-            .arg("maxBitRate", self.stream_br)
-            .arg(
-                "size",
-                self.stream_size.map(|(w, h)| format!("{}x{}", w, h)),
-            )
-            .arg("timeOffset", self.stream_offset)
-            .build();
-        client.get_bytes("stream", args)
+        client.get_bytes("stream", self.stream_options().to_args(&self.id))
     }
 
     fn stream_url(&self, client: &Client) -> Result<String> {
-        let args = Query::with("id", self.id)
-            .arg("maxBitRate", self.stream_br)
-            .arg(
-                "size",
-                self.stream_size.map(|(w, h)| format!("{}x{}", w, h)),
-            )
-            .arg("timeOffset", self.stream_offset)
-            .build();
-        client.build_url("stream", args)
+        client.stream_url(&self.id, &self.stream_options())
     }
 
     fn download(&self, client: &Client) -> Result<Vec<u8>> {
-        client.get_bytes("download", Query::with("id", self.id))
+        client.get_bytes("download", Query::with("id", self.id.as_str()))
     }
 
     fn download_url(&self, client: &Client) -> Result<String> {
-        client.build_url("download", Query::with("id", self.id))
+        client.download_url(&self.id)
     }
 
     fn encoding(&self) -> &str {
@@ -136,6 +146,11 @@ impl Streamable for Video {
         self.stream_br = Some(bit_rate);
     }
 
+    fn bit_rate(&self) -> Option<u64> {
+        let own = Some(self.bitrate as u64).filter(|&br| br > 0);
+        super::stream_bit_rate(own, self.stream_br)
+    }
+
     fn set_transcoding(&mut self, format: &str) {
         self.stream_tc = Some(format.to_string());
     }
@@ -154,14 +169,12 @@ impl Media for Video {
         let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
         let query = Query::with("id", cover).arg("size", size.into()).build();
 
-        client.get_bytes("getCoverArt", query)
+        client.get_image("getCoverArt", query)
     }
 
     fn cover_art_url<U: Into<Option<usize>>>(&self, client: &Client, size: U) -> Result<String> {
         let cover = self.cover_id().ok_or(Error::Other("no cover art found"))?;
-        let query = Query::with("id", cover).arg("size", size.into()).build();
-
-        client.build_url("getCoverArt", query)
+        client.cover_art_url(cover, size)
     }
 }
 
@@ -173,8 +186,8 @@ impl<'de> Deserialize<'de> for Video {
         #[derive(Debug, Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct _Video {
-            id: String,
-            parent: String,
+            id: Id,
+            parent: Id,
             is_dir: bool,
             title: String,
             album: Option<String>,
@@ -189,6 +202,9 @@ impl<'de> Deserialize<'de> for Video {
             path: String,
             is_video: bool,
             play_count: Option<u64>,
+            played: Option<String>,
+            average_rating: Option<f32>,
+            user_rating: Option<u8>,
             created: String,
             #[serde(rename = "type")]
             media_type: String,
@@ -200,8 +216,8 @@ impl<'de> Deserialize<'de> for Video {
         let raw = _Video::deserialize(de)?;
 
         Ok(Video {
-            id: raw.id.parse().unwrap(),
-            parent: raw.parent.parse().unwrap(),
+            id: raw.id,
+            parent: raw.parent,
             is_dir: raw.is_dir,
             title: raw.title,
             album: raw.album,
@@ -216,6 +232,9 @@ impl<'de> Deserialize<'de> for Video {
             path: raw.path,
             is_video: raw.is_video,
             play_count: raw.play_count,
+            played: raw.played,
+            average_rating: raw.average_rating,
+            user_rating: raw.user_rating,
             created: raw.created,
             media_type: raw.media_type,
             bookmark_position: raw.bookmark_position,
@@ -230,10 +249,11 @@ impl<'de> Deserialize<'de> for Video {
 }
 
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct VideoInfo {
-    pub id: usize,
+    pub id: Id,
     pub captions: Option<Captions>,
+    #[serde(rename = "audioTrack")]
     pub audio_tracks: Vec<AudioTrack>,
     pub conversion: Option<Conversion>,
 }
@@ -245,7 +265,7 @@ impl<'de> Deserialize<'de> for VideoInfo {
     {
         #[derive(Deserialize)]
         struct _VideoInfo {
-            id: String,
+            id: Id,
             captions: Option<Captions>,
             #[serde(rename = "audioTrack")]
             #[serde(default)]
@@ -254,7 +274,7 @@ impl<'de> Deserialize<'de> for VideoInfo {
         }
         let raw = _VideoInfo::deserialize(de)?;
         Ok(VideoInfo {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             captions: raw.captions,
             audio_tracks: raw.audio_tracks,
             conversion: raw.conversion,
@@ -263,10 +283,11 @@ impl<'de> Deserialize<'de> for VideoInfo {
 }
 
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct AudioTrack {
-    pub id: usize,
+    pub id: Id,
     pub name: String,
+    #[serde(rename = "languageCode")]
     pub language_code: String,
 }
 
@@ -277,14 +298,14 @@ impl<'de> Deserialize<'de> for AudioTrack {
     {
         #[derive(Deserialize)]
         struct _AudioTrack {
-            id: String,
+            id: Id,
             name: String,
             #[serde(rename = "languageCode")]
             language_code: String,
         }
         let raw = _AudioTrack::deserialize(de)?;
         Ok(AudioTrack {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             name: raw.name,
             language_code: raw.language_code,
         })
@@ -292,9 +313,9 @@ impl<'de> Deserialize<'de> for AudioTrack {
 }
 
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Captions {
-    pub id: usize,
+    pub id: Id,
     pub name: String,
 }
 
@@ -305,12 +326,12 @@ impl<'de> Deserialize<'de> for Captions {
     {
         #[derive(Deserialize)]
         struct _Captions {
-            id: String,
+            id: Id,
             name: String,
         }
         let raw = _Captions::deserialize(de)?;
         Ok(Captions {
-            id: raw.id.parse().unwrap(),
+            id: raw.id,
             name: raw.name,
         })
     }
@@ -319,7 +340,7 @@ impl<'de> Deserialize<'de> for Captions {
 #[allow(missing_docs)]
 #[derive(Debug)]
 pub struct Conversion {
-    pub id: usize,
+    pub id: Id,
     pub bitrate: usize,
 }
 
@@ -330,18 +351,38 @@ impl<'de> Deserialize<'de> for Conversion {
     {
         #[derive(Deserialize)]
         struct _Conversion {
-            id: String,
+            id: Id,
             #[serde(rename = "bitRate")]
             bitrate: String,
         }
         let raw = _Conversion::deserialize(de)?;
         Ok(Conversion {
-            id: raw.id.parse().unwrap(),
-            bitrate: raw.bitrate.parse().unwrap(),
+            id: raw.id,
+            bitrate: raw.bitrate.parse().map_err(de::Error::custom)?,
         })
     }
 }
 
+impl Serialize for Conversion {
+    fn serialize<S>(&self, se: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // The server sends the bit rate as a string.
+        #[derive(Serialize)]
+        struct _Conversion<'a> {
+            id: &'a Id,
+            #[serde(rename = "bitRate")]
+            bitrate: String,
+        }
+        _Conversion {
+            id: &self.id,
+            bitrate: self.bitrate.to_string(),
+        }
+        .serialize(se)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -350,19 +391,39 @@ mod tests {
     fn parse_video() {
         let parsed = serde_json::from_value::<Video>(raw()).unwrap();
 
-        assert_eq!(parsed.id, 460);
+        assert_eq!(parsed.id, "460");
         assert_eq!(parsed.title, "Big Buck Bunny");
         assert!(!parsed.has_cover_art());
+        assert_eq!(parsed.average_rating, None);
+    }
+
+    #[test]
+    fn parse_video_ratings() {
+        let mut raw = raw();
+        raw["averageRating"] = 4.5.into();
+        raw["userRating"] = 5.into();
+        raw["played"] = "2018-02-10T11:23:07.000Z".into();
+        let parsed = serde_json::from_value::<Video>(raw).unwrap();
+
+        assert_eq!(parsed.average_rating, Some(4.5));
+        assert_eq!(parsed.user_rating, Some(5));
+        assert_eq!(parsed.played.as_deref(), Some("2018-02-10T11:23:07.000Z"));
     }
 
     #[test]
     fn parse_video_info() {
         let parsed = serde_json::from_value::<VideoInfo>(raw_info()).unwrap();
 
-        assert_eq!(parsed.id, 7058);
+        assert_eq!(parsed.id, "7058");
         assert_eq!(parsed.audio_tracks.len(), 5);
     }
 
+    #[test]
+    fn malformed_conversion_is_error() {
+        let raw = serde_json::json!({ "id": "37", "bitRate": "fast" });
+        assert!(serde_json::from_value::<Conversion>(raw).is_err());
+    }
+
     fn raw() -> serde_json::Value {
         serde_json::from_str(
             r#"{
@@ -0,0 +1,147 @@
+//! Play queue APIs.
+
+use std::result;
+
+use serde::de::{Deserialize, Deserializer};
+
+use crate::id;
+use crate::query::Query;
+use crate::{Client, Id, MediaItem, Result};
+
+/// A user's saved play queue.
+///
+/// Saving the queue lets a user pick up playback on another device where
+/// they left off. Each user has a single saved queue, replaced each time it
+/// is saved.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayQueue {
+    /// The songs and videos in the queue, in order.
+    #[serde(rename = "entry")]
+    pub entries: Vec<MediaItem>,
+    /// The ID of the entry that was playing when the queue was saved.
+    pub current: Option<Id>,
+    /// The position in the current entry, in milliseconds.
+    pub position: Option<u64>,
+    /// The user the queue belongs to.
+    pub username: String,
+    /// When the queue was last saved (as an ISO8601 timestamp).
+    pub changed: String,
+    /// The name of the client that last saved the queue.
+    pub changed_by: String,
+}
+
+impl PlayQueue {
+    /// Fetches the current user's saved play queue, or `None` if they have
+    /// not saved one.
+    pub fn get(client: &Client) -> Result<Option<PlayQueue>> {
+        client.get("getPlayQueue", Query::none(), &["playQueue"])
+    }
+
+    /// Saves a play queue for the current user, replacing any saved before.
+    ///
+    /// `ids` are the songs and videos in the queue, in order. `current` is
+    /// the ID of the entry currently playing, and `position` the position in
+    /// it in milliseconds.
+    pub fn save<I, U, P>(client: &Client, ids: &[I], current: U, position: P) -> Result<()>
+    where
+        I: Into<Id> + Clone,
+        U: Into<Option<Id>>,
+        P: Into<Option<u64>>,
+    {
+        let args = Query::new()
+            .arg_list("id", &id::collect(ids))
+            .arg("current", current.into())
+            .arg("position", position.into())
+            .build();
+        client.get_value("savePlayQueue", args)?;
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for PlayQueue {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _PlayQueue {
+            #[serde(default)]
+            entry: Vec<MediaItem>,
+            current: Option<Id>,
+            position: Option<u64>,
+            username: String,
+            changed: String,
+            changed_by: String,
+        }
+
+        let raw = _PlayQueue::deserialize(de)?;
+        Ok(PlayQueue {
+            entries: raw.entry,
+            current: raw.current,
+            position: raw.position,
+            username: raw.username,
+            changed: raw.changed,
+            changed_by: raw.changed_by,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn get_play_queue() {
+        let queue = r#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.14.0",
+            "playQueue": {
+                "current": "28",
+                "position": 31000,
+                "username": "user",
+                "changed": "2017-06-02T08:12:05.112Z",
+                "changedBy": "phone",
+                "entry": [
+                    { "id": "27", "title": "Bellevue Avenue", "size": 5400185,
+                      "contentType": "audio/mpeg", "suffix": "mp3", "path": "a.mp3",
+                      "type": "music" },
+                    { "id": "28", "title": "Don Kiesel", "size": 4200185,
+                      "contentType": "audio/mpeg", "suffix": "mp3", "path": "b.mp3",
+                      "type": "music" }
+                ]
+            }
+        }}"#;
+        let empty = r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#;
+        let site = test_util::mock_server(&[
+            test_util::json_response(queue),
+            test_util::json_response(empty),
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let queue = PlayQueue::get(&cli).unwrap().unwrap();
+        assert_eq!(queue.current, Some(Id::from(28)));
+        assert_eq!(queue.position, Some(31_000));
+        assert_eq!(queue.changed_by, "phone");
+        let ids = queue.entries.iter().map(|e| e.id()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["27", "28"]);
+
+        assert!(PlayQueue::get(&cli).unwrap().is_none());
+    }
+
+    #[test]
+    fn save_play_queue() {
+        let ok = test_util::json_response(
+            r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        PlayQueue::save(&cli, &[27, 28], Id::from(28), 31_000).unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/savePlayQueue?"));
+        assert!(request.contains("id=27&id=28&current=28&position=31000"));
+    }
+}
@@ -0,0 +1,254 @@
+//! Multi-server APIs.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{Client, Error, Result};
+
+/// How a [`ClientPool`] picks the server to send a request to.
+///
+/// [`ClientPool`]: ./struct.ClientPool.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Selection {
+    /// Always starts with the first server added, only moving on to the next
+    /// if it can't be reached. This is the default.
+    #[default]
+    Failover,
+    /// Starts with a different server for each request, in turn, spreading
+    /// requests across all of them.
+    RoundRobin,
+}
+
+/// A value returned by a server in a [`ClientPool`], tagged with the name of
+/// the server it came from.
+///
+/// IDs are only meaningful to the server that handed them out, so the tag is
+/// needed to make follow-up requests for an entity, such as fetching an
+/// album's songs, against the right server (see [`ClientPool::get`]).
+///
+/// [`ClientPool`]: ./struct.ClientPool.html
+/// [`ClientPool::get`]: ./struct.ClientPool.html#method.get
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tagged<T> {
+    /// The name of the server the value came from.
+    pub server: String,
+    /// The value itself.
+    pub value: T,
+}
+
+/// A set of clients for several servers, such as a home server with a
+/// backup.
+///
+/// Requests made through [`call`] are sent to one server, picked by the
+/// pool's [`Selection`]. If the server can't be reached, the request is sent
+/// to the next one, until a server responds or all have been tried. Errors
+/// returned by a server, such as an item not being found, are not retried
+/// elsewhere.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sunk::pool::ClientPool;
+/// use sunk::song::Song;
+/// use sunk::{Client, Streamable};
+///
+/// # fn run() -> sunk::Result<()> {
+/// let mut pool = ClientPool::new();
+/// pool.add("home", Client::new("http://music.home.lan", "user", "password")?)
+///     .add("backup", Client::new("https://music.example.com", "user", "password")?);
+///
+/// let random = pool.call(|client| Song::random(client, 10))?;
+/// println!("Got {} songs from {}", random.value.len(), random.server);
+///
+/// let client = pool.get(&random.server).unwrap();
+/// for song in &random.value {
+///     println!("{}", song.stream_url(client)?);
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+///
+/// [`call`]: #method.call
+/// [`Selection`]: ./enum.Selection.html
+#[derive(Debug, Default)]
+pub struct ClientPool {
+    clients: Vec<(String, Client)>,
+    selection: Selection,
+    next: AtomicUsize,
+}
+
+impl ClientPool {
+    /// Creates an empty pool that fails over between servers in the order
+    /// they are added.
+    pub fn new() -> ClientPool {
+        ClientPool::default()
+    }
+
+    /// Adds a client to the pool under `name`. A client already in the pool
+    /// under the same name is replaced.
+    pub fn add(&mut self, name: &str, client: Client) -> &mut ClientPool {
+        match self.clients.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = client,
+            None => self.clients.push((name.into(), client)),
+        }
+        self
+    }
+
+    /// Removes the client with the given name from the pool, returning it.
+    pub fn remove(&mut self, name: &str) -> Option<Client> {
+        let pos = self.clients.iter().position(|(n, _)| n == name)?;
+        Some(self.clients.remove(pos).1)
+    }
+
+    /// Sets how the pool picks the server to send a request to.
+    pub fn select_by(&mut self, selection: Selection) -> &mut ClientPool {
+        self.selection = selection;
+        self
+    }
+
+    /// Returns the client with the given name.
+    pub fn get(&self, name: &str) -> Option<&Client> {
+        self.clients
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, client)| client)
+    }
+
+    /// Returns the names of the servers in the pool, in the order they were
+    /// added.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.clients.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the number of servers in the pool.
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Returns `true` if the pool has no servers.
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    /// Makes a request with `f`, using the next server picked by the pool
+    /// and moving on to the others if it can't be reached.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the last server tried if none could be reached,
+    /// or an error if the pool is empty.
+    pub fn call<T, F>(&self, f: F) -> Result<Tagged<T>>
+    where
+        F: Fn(&Client) -> Result<T>,
+    {
+        let len = self.clients.len();
+        if len == 0 {
+            return Err(Error::Other("no clients in pool"));
+        }
+
+        let start = match self.selection {
+            Selection::Failover => 0,
+            Selection::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % len,
+        };
+
+        let mut last_err = None;
+        for i in 0..len {
+            let (ref name, ref client) = self.clients[(start + i) % len];
+            match f(client) {
+                Ok(value) => {
+                    return Ok(Tagged {
+                        server: name.clone(),
+                        value,
+                    })
+                }
+                Err(e) if is_unreachable(&e) => {
+                    warn!("Server {} is unreachable: {}", name, e);
+                    last_err = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_err.unwrap_or(Error::Other("no clients in pool")))
+    }
+}
+
+/// Returns `true` if the error means the server couldn't be reached, rather
+/// than the server rejecting the request.
+pub(crate) fn is_unreachable(err: &Error) -> bool {
+    match err {
+        Error::Reqwest(_) | Error::Io(_) => true,
+        Error::Connection(status) => status.is_server_error(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use crate::ApiError;
+
+    fn ok() -> String {
+        test_util::json_response(r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#)
+    }
+
+    #[test]
+    fn round_robin() {
+        let mut pool = ClientPool::new();
+        pool.add(
+            "a",
+            Client::new(&test_util::mock_server(&[ok()]), "u", "p").unwrap(),
+        )
+        .add(
+            "b",
+            Client::new(&test_util::mock_server(&[ok()]), "u", "p").unwrap(),
+        )
+        .select_by(Selection::RoundRobin);
+
+        assert_eq!(pool.call(|cli| cli.ping()).unwrap().server, "a");
+        assert_eq!(pool.call(|cli| cli.ping()).unwrap().server, "b");
+    }
+
+    #[test]
+    fn fails_over_unreachable_server() {
+        // Nothing listens on the discard port.
+        let dead = Client::new("http://127.0.0.1:9", "u", "p").unwrap();
+        let mut pool = ClientPool::new();
+        pool.add("dead", dead).add(
+            "live",
+            Client::new(&test_util::mock_server(&[ok()]), "u", "p").unwrap(),
+        );
+
+        let res = pool.call(|cli| cli.ping()).unwrap();
+        assert_eq!(res.server, "live");
+        assert_eq!(res.value.version, Some("1.14.0".into()));
+    }
+
+    #[test]
+    fn api_errors_not_retried() {
+        let not_found = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "failed",
+                "version": "1.14.0",
+                "error": { "code": 70, "message": "Not found" }
+            }}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[ok()]);
+        let mut pool = ClientPool::new();
+        pool.add(
+            "a",
+            Client::new(&test_util::mock_server(&[not_found]), "u", "p").unwrap(),
+        )
+        .add("b", Client::new(&site, "u", "p").unwrap());
+
+        let res = pool.call(|cli| cli.ping());
+        assert!(matches!(res, Err(Error::Api(ApiError::NotFound))));
+        assert!(requests.try_recv().is_err());
+
+        assert!(matches!(
+            ClientPool::new().call(|cli| cli.ping()),
+            Err(Error::Other(_))
+        ));
+    }
+}
@@ -2,6 +2,8 @@
 
 use std::{fmt, iter};
 
+use url::form_urlencoded;
+
 /// An expandable query set for an API call.
 #[derive(Debug, PartialEq, PartialOrd)]
 pub struct Query {
@@ -106,20 +108,41 @@ impl iter::Extend<(String, Arg)> for Query {
     }
 }
 
+/// Formats the query as a URL query string. Keys and values are
+/// percent-encoded, and arguments without a value are left out.
 impl fmt::Display for Query {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (n, a) in self.inner.iter().enumerate() {
-            if a.1.is_some() {
-                write!(f, "{}={}", a.0, a.1)?;
-                if n + 1 < self.inner.len() {
-                    write!(f, "&")?;
-                }
+        let args = self
+            .inner
+            .iter()
+            .filter_map(|(k, a)| a.0.as_ref().map(|v| (k, v)));
+        for (n, (key, value)) in args.enumerate() {
+            if n > 0 {
+                write!(f, "&")?;
             }
+            write!(f, "{}={}", encode(key), encode(value))?;
         }
         Ok(())
     }
 }
 
+/// Percent-encodes a string for use as a key or value in a query string.
+pub(crate) fn encode(s: &str) -> String {
+    form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
+
+/// Limits a requested number of `name`s to the most the server will return,
+/// warning if it was over.
+pub(crate) fn clamp_count(name: &str, n: usize, max: usize) -> usize {
+    if n > max {
+        warn!(
+            "Requested {} {}, but the server returns at most {}",
+            n, name, max
+        );
+    }
+    n.min(max)
+}
+
 impl Default for Query {
     fn default() -> Query {
         Query::new()
@@ -243,4 +266,38 @@ mod tests {
         q.arg_list("id", ids);
         assert_eq!("id=1&id=2&id=3&id=4", &format!("{}", q))
     }
+
+    #[test]
+    fn trailing_optional_query() {
+        let q = Query::new().arg("id", 64).arg("album", Arg(None)).build();
+        assert_eq!("id=64", &format!("{}", q))
+    }
+
+    #[test]
+    fn reserved_characters_encoded() {
+        let q = Query::new()
+            .arg("query", "AC/DC")
+            .arg("artist", "Simon & Garfunkel")
+            .arg("title", "1+1=2?")
+            .arg("comment", "50% off #1")
+            .build();
+        assert_eq!(
+            "query=AC%2FDC&artist=Simon+%26+Garfunkel&title=1%2B1%3D2%3F&comment=50%25+off+%231",
+            &format!("{}", q)
+        )
+    }
+
+    #[test]
+    fn count_clamped() {
+        assert_eq!(clamp_count("albums", 1000, 500), 500);
+        assert_eq!(clamp_count("albums", 20, 500), 20);
+    }
+
+    #[test]
+    fn unicode_encoded() {
+        let q = Query::with("query", "Sigur Rós");
+        assert_eq!("query=Sigur+R%C3%B3s", &format!("{}", q));
+        let q = Query::with("title", "東京");
+        assert_eq!("title=%E6%9D%B1%E4%BA%AC", &format!("{}", q))
+    }
 }
@@ -1,138 +1,108 @@
 //! Search response APIs.
 
+use std::fmt;
+use std::io::Read;
+use std::marker::PhantomData;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use serde::de::{
+    self, Deserialize, DeserializeOwned, DeserializeSeed, Deserializer, IgnoredAny, MapAccess,
+    SeqAccess,
+};
 use serde_json;
 
-use crate::ApiError;
+use crate::{ApiError, Result, Version};
 
 /// A top-level response from a Subsonic server.
-#[derive(Deserialize)]
+///
+/// Responses are normally wrapped in a `subsonic-response` object, but some
+/// non-conforming servers send its fields at the top level instead; both are
+/// accepted.
+#[derive(Debug)]
 pub struct Response {
-    #[serde(rename = "subsonic-response")]
     inner: InnerResponse,
 }
 
+impl<'de> Deserialize<'de> for Response {
+    fn deserialize<D>(de: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut raw = serde_json::Value::deserialize(de)?;
+        let inner = match raw.get_mut("subsonic-response") {
+            Some(inner) => inner.take(),
+            None => raw,
+        };
+
+        InnerResponse::deserialize(inner)
+            .map(|inner| Response { inner })
+            .map_err(de::Error::custom)
+    }
+}
+
 /// A struct containing the possible responses of the Subsonic API.
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Deserialize)]
 struct InnerResponse {
-    // status: String,
-    // version: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    version: Option<String>,
     error: Option<ApiError>,
-    license: Option<serde_json::Value>,
-    music_folders: Option<serde_json::Value>,
-    indexes: Option<serde_json::Value>,
-    directory: Option<serde_json::Value>,
-    genres: Option<serde_json::Value>,
-    artists: Option<serde_json::Value>,
-    artist: Option<serde_json::Value>,
-    albums: Option<serde_json::Value>,
-    album: Option<serde_json::Value>,
-    song: Option<serde_json::Value>,
-    videos: Option<serde_json::Value>,
-    video_info: Option<serde_json::Value>,
-    artist_info: Option<serde_json::Value>,
-    artist_info2: Option<serde_json::Value>,
-    album_info: Option<serde_json::Value>,
-    similar_songs: Option<serde_json::Value>,
-    similar_songs2: Option<serde_json::Value>,
-    top_songs: Option<serde_json::Value>,
-    album_list: Option<serde_json::Value>,
-    album_list2: Option<serde_json::Value>,
-    random_songs: Option<serde_json::Value>,
-    songs_by_genre: Option<serde_json::Value>,
-    now_playing: Option<serde_json::Value>,
-    starred: Option<serde_json::Value>,
-    starred2: Option<serde_json::Value>,
-    search_result: Option<serde_json::Value>,
-    search_result2: Option<serde_json::Value>,
-    search_result3: Option<serde_json::Value>,
-    playlists: Option<serde_json::Value>,
-    playlist: Option<serde_json::Value>,
-    lyrics: Option<serde_json::Value>,
-    shares: Option<serde_json::Value>,
-    podcasts: Option<serde_json::Value>,
-    newest_podcasts: Option<serde_json::Value>,
-    jukebox_status: Option<serde_json::Value>,
-    jukebox_playlist: Option<serde_json::Value>,
-    internet_radio_stations: Option<serde_json::Value>,
-    chat_messages: Option<serde_json::Value>,
-    user: Option<serde_json::Value>,
-    users: Option<serde_json::Value>,
-    bookmarks: Option<serde_json::Value>,
-    play_queue: Option<serde_json::Value>,
-    scan_status: Option<serde_json::Value>,
+    #[serde(flatten)]
+    values: serde_json::Map<String, serde_json::Value>,
 }
 
+/// Keys that describe the server rather than hold a value, sent alongside
+/// the status and version by OpenSubsonic servers.
+const METADATA_KEYS: &[&str] = &["type", "serverVersion", "openSubsonic"];
+
 impl Response {
     /// Extracts the internal value of the response.
     ///
+    /// The value is whatever field the response holds besides its status,
+    /// version, error and server details, so values from endpoints `sunk`
+    /// doesn't know about are returned too. If the response holds more than
+    /// one value, the first by name is returned; use [`into_value_for`] when
+    /// the endpoint is known.
+    ///
     /// # Errors
     ///
     /// This method will error if the response contained an error (as defined by
     /// the [Subsonic API]).
     ///
+    /// [`into_value_for`]: #method.into_value_for
     /// [Subsonic API]: ./enum.ApiError.html
-    pub fn into_value(self) -> Option<serde_json::Value> {
-        // TODO Big time; make this not an `if ... else if ...` mess.
-        macro_rules! choose {
-            ( $($f:ident),* ) => ({ $(
-                if let Some(v)  = self.inner.$f {
-                    return Some(v)
-                }
-            )* })
+    pub fn into_value(mut self) -> Option<serde_json::Value> {
+        if self.inner.error.is_some() {
+            return None;
         }
 
+        let key = self
+            .inner
+            .values
+            .keys()
+            .find(|key| !METADATA_KEYS.contains(&key.as_str()))?
+            .clone();
+        self.inner.values.remove(&key)
+    }
+
+    /// Extracts the value of the response to a request to `endpoint`.
+    ///
+    /// Some servers include more than one value in a response, such as both
+    /// `searchResult2` and `searchResult3`; this picks the one the endpoint
+    /// is documented to return (`searchResult3` for `search3`). If the
+    /// endpoint's value isn't present, or the endpoint is unknown, this falls
+    /// back to [`into_value`].
+    ///
+    /// [`into_value`]: #method.into_value
+    pub fn into_value_for(mut self, endpoint: &str) -> Option<serde_json::Value> {
         if self.inner.error.is_some() {
             return None;
         }
 
-        choose!(
-            album,
-            album_info,
-            album_list,
-            album_list2,
-            albums,
-            artist,
-            artist_info,
-            artist_info2,
-            artists,
-            bookmarks,
-            chat_messages,
-            directory,
-            genres,
-            indexes,
-            internet_radio_stations,
-            jukebox_playlist,
-            jukebox_status,
-            license,
-            lyrics,
-            music_folders,
-            music_folders,
-            newest_podcasts,
-            now_playing,
-            play_queue,
-            playlist,
-            playlists,
-            podcasts,
-            random_songs,
-            scan_status,
-            search_result,
-            search_result2,
-            search_result3,
-            shares,
-            similar_songs,
-            similar_songs2,
-            song,
-            songs_by_genre,
-            starred,
-            starred2,
-            top_songs,
-            user,
-            users,
-            video_info,
-            videos
-        );
-        None
+        let value = response_key(endpoint).and_then(|key| self.inner.values.remove(&key));
+        value.or_else(|| self.into_value())
     }
 
     /// Extracts the error struct of the response. Returns `None` if the
@@ -141,6 +111,67 @@ impl Response {
         self.inner.error
     }
 
+    /// Returns the API version the server reported in the response, if any.
+    ///
+    /// Servers report their version even when the request failed, so this can
+    /// be used to determine the version of a server the client can't yet
+    /// authenticate with. A version that can't be parsed is treated as
+    /// missing.
+    pub fn version(&self) -> Option<Version> {
+        let raw = self.inner.version.as_ref()?;
+        match raw.parse() {
+            Ok(ver) => Some(ver),
+            Err(_) => {
+                warn!("Server reported an invalid API version: {:?}", raw);
+                None
+            }
+        }
+    }
+
+    /// Returns the name of the server software, such as `"navidrome"`. Only
+    /// reported by OpenSubsonic servers.
+    pub fn server_type(&self) -> Option<&str> {
+        self.inner.values.get("type").and_then(|v| v.as_str())
+    }
+
+    /// Returns the version of the server software, as opposed to the API
+    /// version it implements. Only reported by OpenSubsonic servers.
+    pub fn server_version(&self) -> Option<&str> {
+        self.inner
+            .values
+            .get("serverVersion")
+            .and_then(|v| v.as_str())
+    }
+
+    /// Returns `true` if the server supports OpenSubsonic extensions to the
+    /// API.
+    pub fn is_open_subsonic(&self) -> bool {
+        self.inner
+            .values
+            .get("openSubsonic")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Returns the status the server reported, normally `"ok"` or `"failed"`.
+    ///
+    /// Unlike [`is_ok`], this is exactly what the server sent, which may be
+    /// empty if the server left it out.
+    ///
+    /// [`is_ok`]: #method.is_ok
+    pub fn status(&self) -> &str {
+        &self.inner.status
+    }
+
+    /// Returns every field of the response other than its status, version
+    /// and error, as sent by the server.
+    ///
+    /// This includes fields that `sunk` doesn't otherwise read, such as those
+    /// added by newer API versions or server extensions.
+    pub fn values(&self) -> &serde_json::Map<String, serde_json::Value> {
+        &self.inner.values
+    }
+
     /// Returns `true` if the response is `"ok"`.
     pub fn is_ok(&self) -> bool {
         self.inner.error.is_none()
@@ -156,6 +187,285 @@ impl Response {
     // self.into_value().is_none() }
 }
 
+/// Returns the key of the value the Subsonic API documents `endpoint` as
+/// returning, such as `albumList2` for `getAlbumList2`.
+fn response_key(endpoint: &str) -> Option<String> {
+    let key = match endpoint {
+        "getMusicDirectory" => "directory",
+        "search" => "searchResult",
+        "search2" => "searchResult2",
+        "search3" => "searchResult3",
+        "startScan" => "scanStatus",
+        "createShare" => "shares",
+        _ => {
+            let name = endpoint.strip_prefix("get")?;
+            let mut chars = name.chars();
+            let first = chars.next()?;
+            return Some(first.to_lowercase().chain(chars).collect());
+        }
+    };
+    Some(key.to_string())
+}
+
+/// Deserializes the value found by following `path` through the keys of the
+/// `subsonic-response` object in `body`.
+///
+/// Unlike parsing a [`Response`] and converting the extracted value, the body
+/// is only parsed once, and anything outside the path is skipped without
+/// being stored. Returns `None` if the path isn't present, or an [`ApiError`]
+/// if the response is a failure. As with [`Response`], the fields may also be
+/// at the top level rather than within `subsonic-response`.
+///
+/// [`Response`]: ./struct.Response.html
+/// [`ApiError`]: ../enum.ApiError.html
+pub(crate) fn from_slice_at<T>(body: &[u8], path: &[&str]) -> Result<Option<T>>
+where
+    T: DeserializeOwned,
+{
+    let mut de = serde_json::Deserializer::from_slice(body);
+    let (error, value) = Envelope::new(path, PhantomData::<T>).deserialize(&mut de)?;
+    de.end()?;
+
+    match error {
+        Some(err) => Err(err.into()),
+        None => Ok(value),
+    }
+}
+
+/// Reads a response, passing each element of the list at `path` to `f` as it
+/// is parsed, rather than collecting the list.
+///
+/// Parsing continues to the end of the response even if `f` returns `false`,
+/// but no further elements are passed to it.
+pub(crate) fn for_each_at<R, T, F>(reader: R, path: &[&str], f: F) -> Result<()>
+where
+    R: Read,
+    T: DeserializeOwned,
+    F: FnMut(T) -> bool,
+{
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let leaf = ForEach {
+        f,
+        _marker: PhantomData,
+    };
+    let (error, _) = Envelope::new(path, leaf).deserialize(&mut de)?;
+    de.end()?;
+
+    match error {
+        Some(err) => Err(err.into()),
+        None => Ok(()),
+    }
+}
+
+/// An iterator over a list in a response, parsed as it is read from the
+/// server.
+///
+/// Items are parsed on a background thread and handed over one at a time, so
+/// only a handful are held in memory at once. If parsing fails part way
+/// through, the error is returned as the last item.
+#[derive(Debug)]
+pub struct ListStream<T> {
+    rx: Receiver<Result<T>>,
+}
+
+/// The number of parsed items buffered ahead of the consumer.
+const STREAM_BUFFER: usize = 16;
+
+impl<T: DeserializeOwned + Send + 'static> ListStream<T> {
+    pub(crate) fn spawn<R>(reader: R, path: &'static [&'static str]) -> ListStream<T>
+    where
+        R: Read + Send + 'static,
+    {
+        let (tx, rx) = mpsc::sync_channel(STREAM_BUFFER);
+        thread::spawn(move || {
+            let res = for_each_at(reader, path, |item| tx.send(Ok(item)).is_ok());
+            if let Err(err) = res {
+                let _ = tx.send(Err(err));
+            }
+        });
+        ListStream { rx }
+    }
+}
+
+impl<T> Iterator for ListStream<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        self.rx.recv().ok()
+    }
+}
+
+/// Seed for the response object, capturing any API error alongside the value
+/// at the path. At the top level, the fields are looked for both directly and
+/// within the `subsonic-response` wrapper.
+struct Envelope<'a, S> {
+    path: &'a [&'a str],
+    leaf: Option<S>,
+    inner: bool,
+}
+
+impl<'a, S> Envelope<'a, S> {
+    fn new(path: &'a [&'a str], leaf: S) -> Self {
+        Envelope {
+            path,
+            leaf: Some(leaf),
+            inner: false,
+        }
+    }
+}
+
+impl<'de, 'a, S: DeserializeSeed<'de>> DeserializeSeed<'de> for Envelope<'a, S> {
+    type Value = (Option<ApiError>, Option<S::Value>);
+
+    fn deserialize<D>(self, de: D) -> ::std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a, S: DeserializeSeed<'de>> de::Visitor<'de> for Envelope<'a, S> {
+    type Value = (Option<ApiError>, Option<S::Value>);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a Subsonic response")
+    }
+
+    fn visit_map<M>(mut self, mut map: M) -> ::std::result::Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut error = None;
+        let mut value = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            if !self.inner && key == "subsonic-response" && self.leaf.is_some() {
+                let inner = Envelope {
+                    path: self.path,
+                    leaf: self.leaf.take(),
+                    inner: true,
+                };
+                let (e, v) = map.next_value_seed(inner)?;
+                error = e;
+                value = v;
+            } else if key == "error" {
+                error = Some(map.next_value()?);
+            } else if self.path.first() == Some(&key.as_str()) && self.leaf.is_some() {
+                let leaf = self.leaf.take().unwrap();
+                value = map.next_value_seed(Path::new(&self.path[1..], leaf))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        Ok((error, value))
+    }
+}
+
+/// Seed following the remaining keys of a path down to the target value.
+struct Path<'a, S> {
+    path: &'a [&'a str],
+    leaf: Option<S>,
+}
+
+impl<'a, S> Path<'a, S> {
+    fn new(path: &'a [&'a str], leaf: S) -> Self {
+        Path {
+            path,
+            leaf: Some(leaf),
+        }
+    }
+}
+
+impl<'de, 'a, S: DeserializeSeed<'de>> DeserializeSeed<'de> for Path<'a, S> {
+    type Value = Option<S::Value>;
+
+    fn deserialize<D>(mut self, de: D) -> ::std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if self.path.is_empty() {
+            let leaf = self.leaf.take().unwrap();
+            leaf.deserialize(de).map(Some)
+        } else {
+            de.deserialize_map(self)
+        }
+    }
+}
+
+impl<'de, 'a, S: DeserializeSeed<'de>> de::Visitor<'de> for Path<'a, S> {
+    type Value = Option<S::Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an object containing `{}`", self.path[0])
+    }
+
+    fn visit_map<M>(mut self, mut map: M) -> ::std::result::Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut value = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == self.path[0] && self.leaf.is_some() {
+                let leaf = self.leaf.take().unwrap();
+                value = map.next_value_seed(Path::new(&self.path[1..], leaf))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// Seed passing each element of a list to a callback.
+struct ForEach<T, F> {
+    f: F,
+    _marker: PhantomData<T>,
+}
+
+impl<'de, T, F> DeserializeSeed<'de> for ForEach<T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> bool,
+{
+    type Value = ();
+
+    fn deserialize<D>(self, de: D) -> ::std::result::Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de.deserialize_seq(self)
+    }
+}
+
+impl<'de, T, F> de::Visitor<'de> for ForEach<T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> bool,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list")
+    }
+
+    fn visit_seq<A>(mut self, mut seq: A) -> ::std::result::Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut wanted = true;
+        while wanted {
+            match seq.next_element::<T>()? {
+                Some(item) => wanted = (self.f)(item),
+                None => return Ok(()),
+            }
+        }
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,4 +490,137 @@ mod tests {
         let success = serde_json::from_str::<Response>(success).unwrap();
         assert!(success.into_error().is_none());
     }
+
+    #[test]
+    fn value_for_endpoint() {
+        let both = r#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.14.0",
+            "searchResult2": { "song": [ { "id": "2" } ] },
+            "searchResult3": { "song": [ { "id": "3" } ] }
+        }}"#;
+        let parse = || serde_json::from_str::<Response>(both).unwrap();
+
+        let value = parse().into_value_for("search3").unwrap();
+        assert_eq!(value["song"][0]["id"], "3");
+        let value = parse().into_value_for("search2").unwrap();
+        assert_eq!(value["song"][0]["id"], "2");
+        let value = parse().into_value_for("jukeboxControl").unwrap();
+        assert_eq!(value["song"][0]["id"], "2");
+    }
+
+    #[test]
+    fn endpoint_keys() {
+        assert_eq!(response_key("getAlbumList2").as_deref(), Some("albumList2"));
+        assert_eq!(
+            response_key("getMusicDirectory").as_deref(),
+            Some("directory")
+        );
+        assert_eq!(response_key("search3").as_deref(), Some("searchResult3"));
+        assert_eq!(response_key("scrobble"), None);
+    }
+
+    #[test]
+    fn unmodelled_values() {
+        let res = r#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.16.1",
+            "type": "navidrome",
+            "openSubsonic": true
+        }}"#;
+        let res = serde_json::from_str::<Response>(res).unwrap();
+        assert_eq!(res.status(), "ok");
+        assert_eq!(res.values()["type"], "navidrome");
+        assert_eq!(res.values()["openSubsonic"], true);
+        assert!(!res.values().contains_key("status"));
+        assert!(res.into_value().is_none());
+    }
+
+    #[test]
+    fn value_from_unknown_endpoint() {
+        let res = r#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.16.1",
+            "type": "navidrome",
+            "serverVersion": "0.53.3",
+            "openSubsonic": true,
+            "lyricsList": { "structuredLyrics": [] }
+        }}"#;
+        let parse = || serde_json::from_str::<Response>(res).unwrap();
+
+        let value = parse().into_value().unwrap();
+        assert!(value["structuredLyrics"].is_array());
+        let value = parse().into_value_for("getLyricsBySongId").unwrap();
+        assert!(value["structuredLyrics"].is_array());
+    }
+
+    #[test]
+    fn reports_version() {
+        let fail = r#"{"subsonic-response": {
+            "status": "failed",
+            "version": "1.12.0",
+            "error": {
+                "code": 40,
+                "message": "Wrong username or password"
+            }
+        }}"#;
+        let fail = serde_json::from_str::<Response>(fail).unwrap();
+        assert_eq!(fail.version(), Some(Version::from("1.12.0")));
+
+        let odd =
+            serde_json::from_str::<Response>(r#"{"status":"ok","version":"1.16.1-beta"}"#).unwrap();
+        assert_eq!(odd.version(), Some(Version::from("1.16.1")));
+        let garbage = serde_json::from_str::<Response>(r#"{"status":"ok","version":""}"#).unwrap();
+        assert_eq!(garbage.version(), None);
+    }
+
+    #[test]
+    fn extract_at_path() {
+        let body = br#"{"subsonic-response": {
+            "status": "ok",
+            "version": "1.14.0",
+            "musicFolders": {
+                "musicFolder": [ { "id": 1, "name": "Music" }, { "id": 2, "name": "Podcasts" } ]
+            }
+        }}"#;
+
+        let ids = from_slice_at::<Vec<serde_json::Value>>(body, &["musicFolders", "musicFolder"])
+            .unwrap()
+            .unwrap()
+            .iter()
+            .map(|f| f["id"].as_u64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 2]);
+
+        let missing = from_slice_at::<Vec<serde_json::Value>>(body, &["genres", "genre"]).unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn extract_at_path_error() {
+        let body = br#"{"subsonic-response": {
+            "status": "failed",
+            "version": "1.14.0",
+            "error": { "code": 70, "message": "Requested resource not found" }
+        }}"#;
+
+        let res = from_slice_at::<serde_json::Value>(body, &["album"]);
+        assert!(matches!(res, Err(crate::Error::Api(ApiError::NotFound))));
+    }
+
+    #[test]
+    fn unwrapped_response() {
+        let ok = serde_json::from_str::<Response>(r#"{"status":"ok","version":"1.16.1"}"#).unwrap();
+        assert!(ok.is_ok());
+        assert_eq!(ok.version(), Some(Version::from("1.16.1")));
+
+        let fail = br#"{
+            "status": "failed",
+            "version": "1.16.1",
+            "error": { "code": 70, "message": "Requested resource not found" }
+        }"#;
+        assert!(serde_json::from_slice::<Response>(fail).unwrap().is_err());
+        let res = from_slice_at::<serde_json::Value>(fail, &["album"]);
+        assert!(matches!(res, Err(crate::Error::Api(ApiError::NotFound))));
+    }
 }
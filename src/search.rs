@@ -3,7 +3,10 @@
 //! The Subsonic API works on the concept of paging, something not uncommon in
 //! RESTful APIs. A search will return a number of results up to a
 //! specification. The client then has a virtual "page" number they will send,
-//! to offset a search by a multiple of the return number.
+//! to offset a search by a multiple of the return number. A page can also
+//! start at an arbitrary result with [`SearchPage::from_result_offset`].
+//!
+//! [`SearchPage::from_result_offset`]: ./struct.SearchPage.html#method.from_result_offset
 //!
 //! # Example
 //!
@@ -22,23 +25,23 @@
 //! let mut page = SearchPage::new();
 //! let list = ListType::default();
 //!
-//! let results = Album::list(&client, list, page, 0)?;
+//! let results = Album::list(&client, list, page, None)?;
 //! assert_eq!(results.len(), 20);
 //! #
 //! # page.next();
-//! # let more_results = Album::list(&client, list, page, 0)?;
+//! # let more_results = Album::list(&client, list, page, None)?;
 //! # assert_eq!(more_results.len(), 20);
 //! #
 //! # page.next();
-//! # let last_results = Album::list(&client, list, page, 0)?;
+//! # let last_results = Album::list(&client, list, page, None)?;
 //! # assert_eq!(last_results.len(), 10);
 //! #
 //! # let exact = SearchPage::new().with_size(50);
-//! # let exact_results = Album::list(&client, list, exact, 0)?;
+//! # let exact_results = Album::list(&client, list, exact, None)?;
 //! # assert_eq!(exact_results.len(), 50);
 //! #
 //! # let all = search::ALL;
-//! # let all_results = Album::list(&client, list, all, 0)?;
+//! # let all_results = Album::list(&client, list, all, None)?;
 //! # assert_eq!(all_results.len(), 50);
 //! #
 //! # Ok(())
@@ -61,23 +64,23 @@
 //! # let mut page = SearchPage::new();
 //! # let list = ListType::default();
 //! #
-//! # let results = Album::list(&client, list, page, 0)?;
+//! # let results = Album::list(&client, list, page, None)?;
 //! # assert_eq!(results.len(), 20);
 //! #
 //! page.next();
-//! let more_results = Album::list(&client, list, page, 0)?;
+//! let more_results = Album::list(&client, list, page, None)?;
 //! assert_eq!(more_results.len(), 20);
 //!
 //! page.next();
-//! let last_results = Album::list(&client, list, page, 0)?;
+//! let last_results = Album::list(&client, list, page, None)?;
 //! assert_eq!(last_results.len(), 10);
 //! #
 //! # let exact = SearchPage::new().with_size(50);
-//! # let exact_results = Album::list(&client, list, exact, 0)?;
+//! # let exact_results = Album::list(&client, list, exact, None)?;
 //! # assert_eq!(exact_results.len(), 50);
 //! #
 //! # let all = search::ALL;
-//! # let all_results = Album::list(&client, list, all, 0)?;
+//! # let all_results = Album::list(&client, list, all, None)?;
 //! # assert_eq!(all_results.len(), 50);
 //! #
 //! # Ok(())
@@ -104,23 +107,23 @@
 //! # let mut page = SearchPage::new();
 //! # let list = ListType::default();
 //! #
-//! # let results = Album::list(&client, list, page, 0)?;
+//! # let results = Album::list(&client, list, page, None)?;
 //! # assert_eq!(results.len(), 20);
 //! #
 //! # page.next();
-//! # let more_results = Album::list(&client, list, page, 0)?;
+//! # let more_results = Album::list(&client, list, page, None)?;
 //! # assert_eq!(more_results.len(), 20);
 //! #
 //! # page.next();
-//! # let last_results = Album::list(&client, list, page, 0)?;
+//! # let last_results = Album::list(&client, list, page, None)?;
 //! # assert_eq!(last_results.len(), 10);
 //! #
 //! let exact = SearchPage::new().with_size(50);
-//! let exact_results = Album::list(&client, list, exact, 0)?;
+//! let exact_results = Album::list(&client, list, exact, None)?;
 //! assert_eq!(exact_results.len(), 50);
 //! #
 //! # let all = search::ALL;
-//! # let all_results = Album::list(&client, list, all, 0)?;
+//! # let all_results = Album::list(&client, list, all, None)?;
 //! # assert_eq!(all_results.len(), 50);
 //! #
 //! # Ok(())
@@ -146,23 +149,23 @@
 //! # let mut page = SearchPage::new();
 //! # let list = ListType::default();
 //! #
-//! # let results = Album::list(&client, list, page, 0)?;
+//! # let results = Album::list(&client, list, page, None)?;
 //! # assert_eq!(results.len(), 20);
 //! #
 //! # page.next();
-//! # let more_results = Album::list(&client, list, page, 0)?;
+//! # let more_results = Album::list(&client, list, page, None)?;
 //! # assert_eq!(more_results.len(), 20);
 //! #
 //! # page.next();
-//! # let last_results = Album::list(&client, list, page, 0)?;
+//! # let last_results = Album::list(&client, list, page, None)?;
 //! # assert_eq!(last_results.len(), 10);
 //! #
 //! # let exact = SearchPage::new().with_size(50);
-//! # let exact_results = Album::list(&client, list, exact, 0)?;
+//! # let exact_results = Album::list(&client, list, exact, None)?;
 //! # assert_eq!(exact_results.len(), 50);
 //! #
 //! let all = search::ALL;
-//! let all_results = Album::list(&client, list, all, 0)?;
+//! let all_results = Album::list(&client, list, all, None)?;
 //! assert_eq!(all_results.len(), 50);
 //! #
 //! # Ok(())
@@ -173,29 +176,46 @@
 use std::fmt;
 
 use crate::song::Song;
-use crate::{Album, Artist};
+use crate::{Album, Artist, Child};
 
 /// The maximum number of results most searches will accept.
 pub const ALL: SearchPage = SearchPage {
     count: 500,
     offset: 0,
+    start: 0,
 };
 
 /// Effectively makes a search ignore the field.
 pub const NONE: SearchPage = SearchPage {
     count: 0,
     offset: 0,
+    start: 0,
 };
 
 /// A holding struct for a search configuration.
 ///
+/// A page is described by its size and its page index, `offset`. The first
+/// result returned is at [`result_offset`], which is the page index
+/// multiplied by the size, plus the starting result given to
+/// [`from_result_offset`] (if any).
+///
 /// See the [module-level documentation](./index.html) for more information.
+///
+/// A page can't be built with a struct literal, as the starting result is
+/// kept privately; use [`at_page`] and [`with_size`] instead, such as
+/// `SearchPage::at_page(2).with_size(10)` for the third page of 10 results.
+///
+/// [`result_offset`]: #method.result_offset
+/// [`from_result_offset`]: #method.from_result_offset
+/// [`at_page`]: #method.at_page
+/// [`with_size`]: #method.with_size
 #[derive(Debug, Copy, Clone)]
 pub struct SearchPage {
     /// The number of results to return.
     pub count: usize,
-    /// The page offset.
+    /// The page index. This is a number of pages, not results.
     pub offset: usize,
+    start: usize,
 }
 
 impl SearchPage {
@@ -204,22 +224,45 @@ impl SearchPage {
         SearchPage {
             offset: 0,
             count: 20,
+            start: 0,
         }
     }
 
-    /// Creates the configuration at the provided page.
+    /// Creates the configuration at the provided page index.
+    ///
+    /// With the default size of 20, `at_page(2)` starts at the 40th result.
     pub fn at_page(offset: usize) -> SearchPage {
-        SearchPage { offset, count: 20 }
+        SearchPage {
+            offset,
+            count: 20,
+            start: 0,
+        }
     }
 
-    /// Sets the configuration to the given size.
-    pub fn with_size(self, count: usize) -> SearchPage {
+    /// Creates the configuration starting at the provided result, regardless
+    /// of the page size.
+    ///
+    /// `from_result_offset(30)` starts at the 30th result; advancing the page
+    /// moves on by the page size from there.
+    pub fn from_result_offset(offset: usize) -> SearchPage {
         SearchPage {
-            offset: self.offset,
-            count,
+            offset: 0,
+            count: 20,
+            start: offset,
         }
     }
 
+    /// Sets the configuration to the given size.
+    pub fn with_size(self, count: usize) -> SearchPage {
+        SearchPage { count, ..self }
+    }
+
+    /// Returns the offset of the first result in the page, as sent to the
+    /// server.
+    pub fn result_offset(&self) -> usize {
+        self.start + self.count * self.offset
+    }
+
     /// Advances the page.
     pub fn next(&mut self) {
         self.offset += 1;
@@ -239,17 +282,17 @@ impl Default for SearchPage {
 
 impl fmt::Display for SearchPage {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "search range {}-{}",
-            self.count * self.offset,
-            (self.count + 1) * self.offset - 1
-        )
+        let first = self.result_offset();
+        if self.count == 0 {
+            write!(f, "empty search range at {}", first)
+        } else {
+            write!(f, "search range {}-{}", first, first + self.count - 1)
+        }
     }
 }
 
 /// A holder struct for a search result.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct SearchResult {
     /// Artists found in the search.
     #[serde(rename = "artist")]
@@ -264,3 +307,116 @@ pub struct SearchResult {
     #[serde(default)]
     pub songs: Vec<Song>,
 }
+
+impl SearchResult {
+    /// Appends the artists, albums, and songs of another result to this one.
+    ///
+    /// Useful for accumulating a full set of results when paging through a
+    /// search.
+    pub fn extend(&mut self, other: SearchResult) {
+        self.artists.extend(other.artists);
+        self.albums.extend(other.albums);
+        self.songs.extend(other.songs);
+    }
+}
+
+/// A holder struct for the result of a search of the server's folder
+/// structure, with [`Client::search2`].
+///
+/// Folder-based results list songs and videos together, as [`Child`]
+/// entries.
+///
+/// [`Client::search2`]: ../struct.Client.html#method.search2
+/// [`Child`]: ../enum.Child.html
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct FolderSearchResult {
+    /// Artists found in the search.
+    #[serde(rename = "artist")]
+    #[serde(default)]
+    pub artists: Vec<Artist>,
+    /// Albums found in the search.
+    #[serde(rename = "album")]
+    #[serde(default)]
+    pub albums: Vec<Album>,
+    /// Songs and videos found in the search.
+    #[serde(rename = "song")]
+    #[serde(default)]
+    pub songs: Vec<Child>,
+}
+
+impl FolderSearchResult {
+    /// Appends the artists, albums, and songs of another result to this one.
+    ///
+    /// Useful for accumulating a full set of results when paging through a
+    /// search.
+    pub fn extend(&mut self, other: FolderSearchResult) {
+        self.artists.extend(other.artists);
+        self.albums.extend(other.albums);
+        self.songs.extend(other.songs);
+    }
+}
+
+impl From<FolderSearchResult> for SearchResult {
+    /// Keeps the songs of a folder-based result, dropping any videos or
+    /// directories.
+    fn from(result: FolderSearchResult) -> SearchResult {
+        SearchResult {
+            artists: result.artists,
+            albums: result.albums,
+            songs: result
+                .songs
+                .into_iter()
+                .filter_map(|child| match child {
+                    Child::Song(song) => Some(song),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_offsets() {
+        let mut page = SearchPage::at_page(2);
+        assert_eq!(page.result_offset(), 40);
+        assert_eq!(page.to_string(), "search range 40-59");
+
+        page.next();
+        assert_eq!(page.result_offset(), 60);
+
+        let mut page = SearchPage::from_result_offset(30).with_size(10);
+        assert_eq!(page.result_offset(), 30);
+        assert_eq!(page.to_string(), "search range 30-39");
+
+        page.next();
+        assert_eq!(page.result_offset(), 40);
+    }
+
+    #[test]
+    fn extend_result() {
+        let page = || {
+            serde_json::from_str::<SearchResult>(
+                r#"{
+                "album" : [ {
+                    "id" : "1",
+                    "name" : "Bellevue",
+                    "songCount" : 9,
+                    "duration" : 1920
+                } ]
+            }"#,
+            )
+            .unwrap()
+        };
+
+        let mut result = page();
+        result.extend(page());
+
+        assert!(result.artists.is_empty());
+        assert_eq!(result.albums.len(), 2);
+        assert!(result.songs.is_empty());
+    }
+}
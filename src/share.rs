@@ -0,0 +1,199 @@
+//! Sharing APIs.
+
+use std::result;
+
+use serde::de::{Deserialize, Deserializer};
+
+use crate::id;
+use crate::query::Query;
+use crate::{Child, Client, Error, Id, Result};
+
+/// A public link to media on the server.
+///
+/// Shares allow people without an account on the server to play the shared
+/// songs or videos through the link.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Share {
+    /// Unique identifier for the share.
+    pub id: Id,
+    /// The public URL of the share.
+    pub url: String,
+    /// A description of the share.
+    pub description: Option<String>,
+    /// The user who created the share.
+    pub username: String,
+    /// When the share was created (as an ISO8601 timestamp).
+    pub created: String,
+    /// When the share expires (as an ISO8601 timestamp), if it does.
+    pub expires: Option<String>,
+    /// When the share was last visited (as an ISO8601 timestamp), if it has
+    /// been.
+    pub last_visited: Option<String>,
+    /// How many times the share has been visited.
+    pub visit_count: u64,
+    /// The shared songs, videos and directories.
+    #[serde(rename = "entry")]
+    pub entries: Vec<Child>,
+}
+
+impl Share {
+    /// Lists the shares the current user can see.
+    pub fn list(client: &Client) -> Result<Vec<Share>> {
+        client.get_list("getShares", Query::none(), "shares", "share")
+    }
+
+    /// Shares the songs, videos, albums or directories with the given IDs.
+    ///
+    /// `expires` is when the share should stop working, in milliseconds since
+    /// the Unix epoch; if `None`, the share doesn't expire.
+    ///
+    /// # Errors
+    ///
+    /// The user needs the share role to create shares.
+    pub fn create<'a, I, S, U>(
+        client: &Client,
+        ids: &[I],
+        description: S,
+        expires: U,
+    ) -> Result<Share>
+    where
+        I: Into<Id> + Clone,
+        S: Into<Option<&'a str>>,
+        U: Into<Option<u64>>,
+    {
+        let args = Query::new()
+            .arg_list("id", &id::collect(ids))
+            .arg("description", description.into())
+            .arg("expires", expires.into())
+            .build();
+        client
+            .get_list("createShare", args, "shares", "share")?
+            .into_iter()
+            .next()
+            .ok_or(Error::Other("empty response"))
+    }
+
+    /// Changes the share's description and expiry on the server.
+    ///
+    /// `expires` is in milliseconds since the Unix epoch. Passing `None` for
+    /// either leaves it unchanged.
+    pub fn update<'a, S, U>(&self, client: &Client, description: S, expires: U) -> Result<()>
+    where
+        S: Into<Option<&'a str>>,
+        U: Into<Option<u64>>,
+    {
+        let args = Query::with("id", self.id.as_str())
+            .arg("description", description.into())
+            .arg("expires", expires.into())
+            .build();
+        client.get_value("updateShare", args)?;
+        Ok(())
+    }
+
+    /// Removes the share from the server. Its link stops working.
+    pub fn delete(&self, client: &Client) -> Result<()> {
+        client.get_value("deleteShare", Query::with("id", self.id.as_str()))?;
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for Share {
+    fn deserialize<D>(de: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct _Share {
+            id: Id,
+            url: String,
+            description: Option<String>,
+            username: String,
+            created: String,
+            expires: Option<String>,
+            last_visited: Option<String>,
+            #[serde(default)]
+            visit_count: u64,
+            #[serde(default)]
+            entry: Vec<Child>,
+        }
+
+        let raw = _Share::deserialize(de)?;
+        Ok(Share {
+            id: raw.id,
+            url: raw.url,
+            description: raw.description,
+            username: raw.username,
+            created: raw.created,
+            expires: raw.expires,
+            last_visited: raw.last_visited,
+            visit_count: raw.visit_count,
+            entries: raw.entry,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn parse_share() {
+        let parsed = serde_json::from_str::<Share>(&raw()).unwrap();
+
+        assert_eq!(parsed.id, "12");
+        assert_eq!(parsed.url, "http://localhost:8080/share/12");
+        assert_eq!(parsed.description.as_deref(), Some("Road trip"));
+        assert_eq!(parsed.expires, None);
+        assert_eq!(parsed.visit_count, 4);
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries[0].id(), "27");
+    }
+
+    #[test]
+    fn create_returns_share() {
+        let body = format!(
+            r#"{{"subsonic-response": {{
+                "status": "ok",
+                "version": "1.14.0",
+                "shares": {{ "share": [{}] }}
+            }}}}"#,
+            raw()
+        );
+        let (site, requests) = test_util::recording_mock_server(&[test_util::json_response(&body)]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let share = Share::create(&cli, &[27], "Road trip", None).unwrap();
+        assert_eq!(share.id, "12");
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/createShare?"));
+        assert!(request.contains("id=27&description=Road"));
+        assert!(!request.contains("expires="));
+    }
+
+    fn raw() -> String {
+        r#"{
+            "id" : "12",
+            "url" : "http://localhost:8080/share/12",
+            "description" : "Road trip",
+            "username" : "user",
+            "created" : "2017-06-01T19:48:25.635Z",
+            "lastVisited" : "2017-06-02T08:12:05.112Z",
+            "visitCount" : 4,
+            "entry" : [ {
+                "id" : "27",
+                "parent" : "25",
+                "isDir" : false,
+                "title" : "Bellevue Avenue",
+                "size" : 5400185,
+                "contentType" : "audio/mpeg",
+                "suffix" : "mp3",
+                "path" : "Misteur Valaire/Bellevue/01 - Misteur Valaire - Bellevue Avenue.mp3",
+                "type" : "music"
+            } ]
+        }"#
+        .to_string()
+    }
+}
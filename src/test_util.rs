@@ -1,3 +1,8 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
 use crate::client;
 use crate::error;
 
@@ -7,3 +12,43 @@ pub fn demo_site() -> error::Result<client::Client> {
     let password = "guest";
     client::Client::new(site, user, password)
 }
+
+/// Serves the canned HTTP responses in order on a local port, one per
+/// connection. Returns the address of the server.
+pub fn mock_server<S: AsRef<str>>(responses: &[S]) -> String {
+    recording_mock_server(responses).0
+}
+
+/// Like `mock_server`, but also returns a channel that receives the raw
+/// request sent on each connection.
+pub fn recording_mock_server<S: AsRef<str>>(responses: &[S]) -> (String, Receiver<String>) {
+    let (tx, rx) = mpsc::channel();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = format!("http://{}", listener.local_addr().unwrap());
+    let responses = responses
+        .iter()
+        .map(|r| r.as_ref().to_string())
+        .collect::<Vec<_>>();
+
+    thread::spawn(move || {
+        for (stream, response) in listener.incoming().zip(responses) {
+            let mut stream = stream.unwrap();
+            let mut buf = [0; 4096];
+            let n = stream.read(&mut buf).unwrap_or(0);
+            let _ = tx.send(String::from_utf8_lossy(&buf[..n]).into_owned());
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    (addr, rx)
+}
+
+/// Wraps a JSON body in a successful HTTP response for `mock_server`.
+pub fn json_response(body: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
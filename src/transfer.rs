@@ -0,0 +1,1305 @@
+//! Media transfer APIs.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::pool::is_unreachable;
+use crate::{Client, Error, Result, Streamable};
+
+/// The size of each chunk read from a response body during a transfer.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// The size of each range fetched by a `SeekableStream`.
+const RANGE_SIZE: u64 = 256 * 1024;
+
+/// The default maximum number of bytes a `SeekableStream` keeps in memory.
+const DEFAULT_CACHE_LIMIT: usize = 16 * 1024 * 1024;
+
+/// The default number of bytes a `Prefetcher` buffers of the next track.
+const DEFAULT_PREFETCH_SIZE: usize = 512 * 1024;
+
+/// The default number of downloads a `Downloader` runs at once.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// The default number of times a `Downloader` retries a failed download.
+const DEFAULT_DOWNLOAD_RETRIES: usize = 2;
+
+/// A handle to cancel an in-progress transfer.
+///
+/// Tokens are cheap to clone, and all clones share the same state; a clone can
+/// be handed to a UI thread to cancel a download running elsewhere.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::thread;
+///
+/// use sunk::song::Song;
+/// use sunk::transfer::CancellationToken;
+/// use sunk::{Client, Streamable};
+///
+/// # fn run() -> sunk::Result<()> {
+/// # let site = "http://demo.subsonic.org";
+/// # let user = "guest3";
+/// # let password = "guest";
+/// let client = Client::new(site, user, password)?;
+/// let song = Song::get(&client, 27)?;
+///
+/// let token = CancellationToken::new();
+/// let stop = token.clone();
+/// thread::spawn(move || {
+///     // Pressing "stop downloading" cancels the transfer.
+///     stop.cancel();
+/// });
+///
+/// let mut file = File::create("song.mp3")?;
+/// match song.download_to(&client, &mut file, &token) {
+///     Ok(n) => println!("Downloaded {} bytes", n),
+///     Err(sunk::Error::Cancelled) => std::fs::remove_file("song.mp3")?,
+///     Err(e) => return Err(e),
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a new token that has not been cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancels any transfer using the token.
+    ///
+    /// A transfer checks the token between chunks, so it will stop shortly
+    /// after rather than immediately.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Options for a single transfer, such as streaming or downloading media.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::time::Duration;
+///
+/// use sunk::song::Song;
+/// use sunk::transfer::{CancellationToken, TransferOptions};
+/// use sunk::{Client, Streamable};
+///
+/// # fn run() -> sunk::Result<()> {
+/// # let site = "http://demo.subsonic.org";
+/// # let user = "guest3";
+/// # let password = "guest";
+/// let client = Client::new(site, user, password)?;
+/// let song = Song::get(&client, 27)?;
+/// let token = CancellationToken::new();
+///
+/// let mut file = File::create("song.mp3")?;
+/// song.download_with(
+///     &client,
+///     &mut file,
+///     TransferOptions::new()
+///         .timeout(Duration::from_secs(120))
+///         .cancel_with(&token)
+///         .progress(|got, total| match total {
+///             Some(total) => println!("{}%", got * 100 / total.max(1)),
+///             None => println!("{} bytes", got),
+///         }),
+/// )?;
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TransferOptions {
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) cancel: Option<CancellationToken>,
+    pub(crate) progress: Option<ProgressFn>,
+    pub(crate) max_rate: Option<u64>,
+}
+
+impl TransferOptions {
+    /// Creates options with no timeout and no way to cancel.
+    pub fn new() -> TransferOptions {
+        TransferOptions::default()
+    }
+
+    /// Sets how long the whole transfer may take, from sending the request to
+    /// receiving the last byte. A transfer running over fails with an
+    /// [`Error::Io`] of kind `TimedOut`.
+    ///
+    /// Like cancelling, the timeout is checked between chunks, so a server
+    /// that stops sending data entirely is only given up on once the
+    /// client's transfer timeout (see [`ClientBuilder::transfer_timeout`])
+    /// passes.
+    ///
+    /// [`Error::Io`]: ../enum.Error.html#variant.Io
+    /// [`ClientBuilder::transfer_timeout`]: ../struct.ClientBuilder.html#method.transfer_timeout
+    pub fn timeout(&mut self, timeout: Duration) -> &mut TransferOptions {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Stops the transfer when `token` is cancelled.
+    pub fn cancel_with(&mut self, token: &CancellationToken) -> &mut TransferOptions {
+        self.cancel = Some(token.clone());
+        self
+    }
+
+    /// Limits the transfer to `kib_per_sec` KiB per second, overriding the
+    /// client's limit (see [`ClientBuilder::max_transfer_rate`]).
+    ///
+    /// [`ClientBuilder::max_transfer_rate`]: ../struct.ClientBuilder.html#method.max_transfer_rate
+    pub fn max_rate(&mut self, kib_per_sec: u64) -> &mut TransferOptions {
+        self.max_rate = Some(kib_per_sec * 1024);
+        self
+    }
+
+    /// Reports the progress of the transfer to a callback.
+    ///
+    /// The callback is called as each chunk is received with the number of
+    /// bytes received so far and the total size of the media. The total is
+    /// `None` if the server didn't report a `Content-Length`, as is usual
+    /// for transcoded streams.
+    pub fn progress<F>(&mut self, f: F) -> &mut TransferOptions
+    where
+        F: Fn(u64, Option<u64>) + Send + Sync + 'static,
+    {
+        self.progress = Some(ProgressFn::new(f));
+        self
+    }
+}
+
+impl<'a> From<&'a CancellationToken> for TransferOptions {
+    fn from(token: &'a CancellationToken) -> TransferOptions {
+        TransferOptions {
+            timeout: None,
+            cancel: Some(token.clone()),
+            progress: None,
+            max_rate: None,
+        }
+    }
+}
+
+/// A callback reporting the progress of a download, given the number of bytes
+/// received so far and the total length, if the server reported one.
+#[derive(Clone)]
+pub(crate) struct ProgressFn(Arc<dyn Fn(u64, Option<u64>) + Send + Sync>);
+
+impl ProgressFn {
+    pub(crate) fn new<F>(f: F) -> ProgressFn
+    where
+        F: Fn(u64, Option<u64>) + Send + Sync + 'static,
+    {
+        ProgressFn(Arc::new(f))
+    }
+}
+
+impl fmt::Debug for ProgressFn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ProgressFn")
+    }
+}
+
+/// A reader reporting the number of bytes read through it to a callback.
+pub(crate) struct ProgressReader<R> {
+    inner: R,
+    read: u64,
+    total: Option<u64>,
+    progress: ProgressFn,
+}
+
+impl<R: Read> ProgressReader<R> {
+    pub(crate) fn new(inner: R, total: Option<u64>, progress: ProgressFn) -> ProgressReader<R> {
+        ProgressReader {
+            inner,
+            read: 0,
+            total,
+            progress,
+        }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.read += n as u64;
+            (self.progress.0)(self.read, self.total);
+        }
+        Ok(n)
+    }
+}
+
+/// A reader limiting the rate bytes are read through it, in bytes per
+/// second, by sleeping whenever reads get ahead of the rate.
+#[derive(Debug)]
+pub(crate) struct ThrottledReader<R> {
+    inner: R,
+    rate: Option<u64>,
+    start: Option<Instant>,
+    read: u64,
+}
+
+impl<R: Read> ThrottledReader<R> {
+    pub(crate) fn new(inner: R, rate: Option<u64>) -> ThrottledReader<R> {
+        ThrottledReader {
+            inner,
+            rate: rate.map(|r| r.max(1)),
+            start: None,
+            read: 0,
+        }
+    }
+
+    pub(crate) fn get_ref(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: Read> Read for ThrottledReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let rate = match self.rate {
+            Some(rate) => rate,
+            None => return self.inner.read(buf),
+        };
+        let start = *self.start.get_or_insert_with(Instant::now);
+
+        // Small reads keep the rate smooth, rather than bursting and then
+        // sleeping for a long time.
+        let len = buf.len().min(CHUNK_SIZE);
+        let n = self.inner.read(&mut buf[..len])?;
+        self.read += n as u64;
+
+        let due = Duration::from_secs_f64(self.read as f64 / rate as f64);
+        let elapsed = start.elapsed();
+        if due > elapsed {
+            thread::sleep(due - elapsed);
+        }
+        Ok(n)
+    }
+}
+
+/// Copies a reader into a writer in chunks, stopping early if the token is
+/// cancelled or the deadline passes. The writer is flushed whether or not the
+/// copy completes.
+pub(crate) fn copy<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    cancel: Option<&CancellationToken>,
+    deadline: Option<Instant>,
+) -> Result<u64>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    let mut buf = [0; CHUNK_SIZE];
+    let mut written = 0;
+
+    loop {
+        if cancel.is_some_and(|c| c.is_cancelled()) {
+            writer.flush()?;
+            return Err(Error::Cancelled);
+        }
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            writer.flush()?;
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "transfer timed out").into());
+        }
+
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n])?;
+        written += n as u64;
+    }
+
+    writer.flush()?;
+    Ok(written)
+}
+
+/// A reader over a media response, yielding the body as it arrives from the
+/// server.
+///
+/// Only the bytes read so far are held in memory, so even large files can be
+/// passed through with little memory. Unlike [`SeekableStream`], the reader
+/// makes a single request and can't seek.
+///
+/// Created by [`Streamable::stream_reader`] and
+/// [`Streamable::download_reader`].
+///
+/// [`SeekableStream`]: ./struct.SeekableStream.html
+/// [`Streamable::stream_reader`]: ../trait.Streamable.html#method.stream_reader
+/// [`Streamable::download_reader`]: ../trait.Streamable.html#method.download_reader
+#[derive(Debug)]
+pub struct MediaReader {
+    inner: ThrottledReader<reqwest::Response>,
+}
+
+impl MediaReader {
+    pub(crate) fn new(inner: reqwest::Response, max_rate: Option<u64>) -> MediaReader {
+        MediaReader {
+            inner: ThrottledReader::new(inner, max_rate),
+        }
+    }
+
+    /// Returns the length of the media in bytes, if the server reported it.
+    ///
+    /// Servers usually don't know the length of transcoded media ahead of
+    /// time; see [`StreamOptions::estimate_content_length`].
+    ///
+    /// [`StreamOptions::estimate_content_length`]: ../struct.StreamOptions.html#method.estimate_content_length
+    pub fn content_length(&self) -> Option<u64> {
+        self.inner.get_ref().content_length()
+    }
+
+    /// Returns the MIME type of the media, if the server reported it.
+    pub fn content_type(&self) -> Option<&str> {
+        self.inner
+            .get_ref()
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|t| t.to_str().ok())
+    }
+}
+
+impl Read for MediaReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+/// A reader over remote media that fetches byte ranges as they are needed.
+///
+/// A `SeekableStream` implements both `Read` and `Seek`. Rather than
+/// downloading the media up front, it requests fixed-size ranges from the
+/// server when they are first read. Fetched ranges are kept in memory so
+/// seeking back does not fetch them again; once the cache grows past its limit
+/// (16 MiB by default), the oldest ranges are dropped.
+///
+/// Created by [`Streamable::stream_seekable`]. Opening the stream fetches the
+/// first range of the media to determine its length.
+///
+/// [`Streamable::stream_seekable`]: ../trait.Streamable.html#method.stream_seekable
+#[derive(Debug)]
+pub struct SeekableStream<'a> {
+    client: &'a Client,
+    url: String,
+    len: u64,
+    pos: u64,
+    range_size: u64,
+    cache_limit: usize,
+    cache: HashMap<u64, Vec<u8>>,
+    cache_order: VecDeque<u64>,
+}
+
+impl<'a> SeekableStream<'a> {
+    pub(crate) fn open(client: &'a Client, url: String) -> Result<SeekableStream<'a>> {
+        SeekableStream::with_range_size(client, url, RANGE_SIZE)
+    }
+
+    fn with_range_size(
+        client: &'a Client,
+        url: String,
+        range_size: u64,
+    ) -> Result<SeekableStream<'a>> {
+        let (mut first, len) = client.get_range(&url, 0, range_size - 1)?;
+        let end = range_size.min(len);
+        if (first.len() as u64) < end {
+            first.extend(fetch_rest(client, &url, first.len() as u64, end - 1)?);
+        }
+        let mut stream = SeekableStream {
+            client,
+            url,
+            len,
+            pos: 0,
+            range_size,
+            cache_limit: DEFAULT_CACHE_LIMIT,
+            cache: HashMap::new(),
+            cache_order: VecDeque::new(),
+        };
+        stream.insert(0, first);
+        Ok(stream)
+    }
+
+    /// Returns the total length of the media, in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the media has no content.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Sets the maximum number of bytes of fetched ranges to keep in memory.
+    ///
+    /// The most recently fetched range is always kept, regardless of the limit.
+    pub fn set_cache_limit(&mut self, bytes: usize) {
+        self.cache_limit = bytes;
+        self.evict();
+    }
+
+    fn insert(&mut self, index: u64, bytes: Vec<u8>) {
+        self.cache.insert(index, bytes);
+        self.cache_order.push_back(index);
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        let mut size = self.cache.values().map(Vec::len).sum::<usize>();
+        while size > self.cache_limit && self.cache_order.len() > 1 {
+            if let Some(old) = self.cache_order.pop_front() {
+                size -= self.cache.remove(&old).map_or(0, |b| b.len());
+            }
+        }
+    }
+
+    fn range(&mut self, index: u64) -> Result<&[u8]> {
+        if !self.cache.contains_key(&index) {
+            let start = index * self.range_size;
+            let end = (start + self.range_size).min(self.len) - 1;
+            let bytes = fetch_rest(self.client, &self.url, start, end)?;
+            self.insert(index, bytes);
+        }
+        Ok(&self.cache[&index])
+    }
+}
+
+impl<'a> Read for SeekableStream<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let index = self.pos / self.range_size;
+        let offset = (self.pos % self.range_size) as usize;
+        let range = match self.range(index) {
+            Ok(range) => range,
+            Err(Error::Io(e)) => return Err(e),
+            Err(e) => return Err(io::Error::other(e.to_string())),
+        };
+
+        let n = range.len().saturating_sub(offset).min(buf.len());
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "stream ended before its reported length",
+            ));
+        }
+        buf[..n].copy_from_slice(&range[offset..offset + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+/// Fetches the bytes from `start` to `end` (inclusive), re-requesting the rest
+/// of the range if the server sends it short.
+fn fetch_rest(client: &Client, url: &str, start: u64, end: u64) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    while start + (bytes.len() as u64) <= end {
+        let (more, _) = client.get_range(url, start + bytes.len() as u64, end)?;
+        if more.is_empty() {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "server sent an empty range",
+            )));
+        }
+        bytes.extend(more);
+    }
+    Ok(bytes)
+}
+
+impl<'a> Seek for SeekableStream<'a> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => self.len.checked_add_signed(n),
+            SeekFrom::Current(n) => self.pos.checked_add_signed(n),
+        };
+        match pos {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            )),
+        }
+    }
+}
+
+/// The progress of a batch of downloads, reported by a [`Downloader`].
+///
+/// [`Downloader`]: ./struct.Downloader.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchProgress {
+    /// The number of items in the batch.
+    pub total: usize,
+    /// The number of items downloaded successfully.
+    pub completed: usize,
+    /// The number of items that failed to download, after retrying.
+    pub failed: usize,
+    /// The number of bytes written by successful downloads.
+    pub bytes: u64,
+}
+
+impl BatchProgress {
+    /// Returns `true` once every item has either been downloaded or failed.
+    pub fn is_finished(&self) -> bool {
+        self.completed + self.failed == self.total
+    }
+}
+
+/// A callback reporting the progress of a batch of downloads.
+#[derive(Clone)]
+struct BatchProgressFn(Arc<dyn Fn(&BatchProgress) + Send + Sync>);
+
+impl fmt::Debug for BatchProgressFn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("BatchProgressFn")
+    }
+}
+
+/// Downloads a batch of media, such as an album or playlist, several items
+/// at a time.
+///
+/// Each item is written to a writer opened for it by the caller. A download
+/// that fails because the server couldn't be reached is retried, opening a
+/// new writer for the item, so the writer should replace any partial data
+/// (as `File::create` does). Errors returned by the server, such as the item
+/// not being found, are not retried. One item failing does not stop the
+/// others.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+///
+/// use sunk::transfer::Downloader;
+/// use sunk::{Album, Client};
+///
+/// # fn run() -> sunk::Result<()> {
+/// # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+/// let album = Album::get(&client, 1)?;
+/// let songs = album.songs(&client)?;
+///
+/// let results = Downloader::new(&client)
+///     .concurrency(3)
+///     .progress(|p| println!("{}/{} songs", p.completed, p.total))
+///     .download_all(&songs, |_, song| {
+///         Ok(File::create(format!("{} - {}.{}", song.track.unwrap_or(0), song.title, song.suffix))?)
+///     });
+///
+/// for (song, result) in songs.iter().zip(results) {
+///     if let Err(e) = result {
+///         println!("Failed to download {}: {}", song.title, e);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+#[derive(Debug)]
+pub struct Downloader<'a> {
+    client: &'a Client,
+    concurrency: usize,
+    retries: usize,
+    options: TransferOptions,
+    progress: Option<BatchProgressFn>,
+}
+
+impl<'a> Downloader<'a> {
+    /// Creates a downloader making four downloads at a time, retrying each
+    /// up to twice.
+    pub fn new(client: &'a Client) -> Downloader<'a> {
+        Downloader {
+            client,
+            concurrency: DEFAULT_CONCURRENCY,
+            retries: DEFAULT_DOWNLOAD_RETRIES,
+            options: TransferOptions::new(),
+            progress: None,
+        }
+    }
+
+    /// Sets the number of downloads to run at once. Values below one are
+    /// raised to one.
+    pub fn concurrency(&mut self, downloads: usize) -> &mut Downloader<'a> {
+        self.concurrency = downloads.max(1);
+        self
+    }
+
+    /// Sets the number of times a download is retried if the server can't be
+    /// reached.
+    pub fn retries(&mut self, retries: usize) -> &mut Downloader<'a> {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets the timeout and cancellation token used for each download. See
+    /// [`TransferOptions`].
+    ///
+    /// [`TransferOptions`]: ./struct.TransferOptions.html
+    pub fn options(&mut self, options: &TransferOptions) -> &mut Downloader<'a> {
+        self.options = options.clone();
+        self
+    }
+
+    /// Reports the progress of the batch to a callback each time an item
+    /// finishes downloading or fails.
+    pub fn progress<F>(&mut self, f: F) -> &mut Downloader<'a>
+    where
+        F: Fn(&BatchProgress) + Send + Sync + 'static,
+    {
+        self.progress = Some(BatchProgressFn(Arc::new(f)));
+        self
+    }
+
+    /// Downloads every item, writing each to the writer returned by `open`
+    /// for its index and item.
+    ///
+    /// Blocks until every item has been downloaded or has failed, then
+    /// returns the number of bytes written for each item, in the order
+    /// given. If the transfer is cancelled, the items not yet finished fail
+    /// with [`Error::Cancelled`].
+    ///
+    /// [`Error::Cancelled`]: ../enum.Error.html#variant.Cancelled
+    pub fn download_all<T, W, F>(&self, items: &[T], open: F) -> Vec<Result<u64>>
+    where
+        T: Streamable + Sync,
+        W: Write,
+        F: Fn(usize, &T) -> Result<W> + Sync,
+    {
+        let next = AtomicUsize::new(0);
+        let results: Vec<Mutex<Option<Result<u64>>>> =
+            items.iter().map(|_| Mutex::new(None)).collect();
+        let progress = Mutex::new(BatchProgress {
+            total: items.len(),
+            completed: 0,
+            failed: 0,
+            bytes: 0,
+        });
+
+        thread::scope(|s| {
+            for _ in 0..self.concurrency.min(items.len()) {
+                s.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    if i >= items.len() {
+                        break;
+                    }
+                    let res = self.download_one(i, &items[i], &open);
+
+                    if let Ok(mut p) = progress.lock() {
+                        match res {
+                            Ok(n) => {
+                                p.completed += 1;
+                                p.bytes += n;
+                            }
+                            Err(_) => p.failed += 1,
+                        }
+                        if let Some(ref f) = self.progress {
+                            (f.0)(&p);
+                        }
+                    }
+                    if let Ok(mut slot) = results[i].lock() {
+                        *slot = Some(res);
+                    }
+                });
+            }
+        });
+
+        results
+            .into_iter()
+            .map(|slot| {
+                slot.into_inner()
+                    .ok()
+                    .flatten()
+                    .unwrap_or(Err(Error::Other("download did not run")))
+            })
+            .collect()
+    }
+
+    fn download_one<T, W, F>(&self, index: usize, item: &T, open: &F) -> Result<u64>
+    where
+        T: Streamable,
+        W: Write,
+        F: Fn(usize, &T) -> Result<W>,
+    {
+        let mut attempt = 0;
+        loop {
+            if self
+                .options
+                .cancel
+                .as_ref()
+                .is_some_and(|c| c.is_cancelled())
+            {
+                return Err(Error::Cancelled);
+            }
+
+            let mut writer = open(index, item)?;
+            match item.download_with(self.client, &mut writer, &self.options) {
+                Err(ref e) if attempt < self.retries && is_unreachable(e) => {
+                    warn!("Download {} failed, retrying: {}", index, e);
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+/// A stream of media with its beginning already buffered, handed out by a
+/// [`Prefetcher`].
+///
+/// Reading yields the buffered bytes first, then the rest of the media as it
+/// arrives from the server.
+///
+/// [`Prefetcher`]: ./struct.Prefetcher.html
+#[derive(Debug)]
+pub struct PrefetchedStream {
+    head: io::Cursor<Vec<u8>>,
+    rest: MediaReader,
+}
+
+impl PrefetchedStream {
+    /// Returns the number of bytes buffered ahead of time and not yet read.
+    pub fn buffered(&self) -> usize {
+        let head = self.head.get_ref();
+        head.len() - (self.head.position() as usize).min(head.len())
+    }
+
+    /// Returns the length of the media in bytes, if the server reported it.
+    pub fn content_length(&self) -> Option<u64> {
+        self.rest.content_length()
+    }
+
+    /// Returns the MIME type of the media, if the server reported it.
+    pub fn content_type(&self) -> Option<&str> {
+        self.rest.content_type()
+    }
+}
+
+impl Read for PrefetchedStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.head.read(buf)? {
+            0 => self.rest.read(buf),
+            n => Ok(n),
+        }
+    }
+}
+
+/// Plays through a queue of media, buffering the beginning of the next item
+/// while the current one plays.
+///
+/// Gapless players need the next track ready the moment the current one
+/// ends. The prefetcher hands each item's stream to a callback in order; while
+/// the callback is playing one item, the next is requested and its first
+/// part (512 KiB by default) is read into memory, so playback of it can
+/// start without waiting on the server.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::Read;
+///
+/// use sunk::transfer::Prefetcher;
+/// use sunk::{Album, Client};
+///
+/// # fn run() -> sunk::Result<()> {
+/// # let client = Client::new("http://demo.subsonic.org", "guest3", "guest")?;
+/// let songs = Album::get(&client, 1)?.songs(&client)?;
+///
+/// Prefetcher::new(&client)
+///     .size(1024 * 1024)
+///     .play(&songs, |_, song, stream| {
+///         let mut stream = match stream {
+///             Ok(stream) => stream,
+///             // Skip songs that fail to load.
+///             Err(_) => return Ok(()),
+///         };
+///         println!("Playing {}", song.title);
+///         let mut buf = Vec::new();
+///         stream.read_to_end(&mut buf)?; // Decode and play.
+///         Ok(())
+///     })?;
+/// # Ok(())
+/// # }
+/// # fn main() { }
+/// ```
+#[derive(Debug)]
+pub struct Prefetcher<'a> {
+    client: &'a Client,
+    size: usize,
+    duration: Option<Duration>,
+}
+
+impl<'a> Prefetcher<'a> {
+    /// Creates a prefetcher buffering 512 KiB of each upcoming item.
+    pub fn new(client: &'a Client) -> Prefetcher<'a> {
+        Prefetcher {
+            client,
+            size: DEFAULT_PREFETCH_SIZE,
+            duration: None,
+        }
+    }
+
+    /// Sets the number of bytes to buffer of each upcoming item.
+    ///
+    /// If a [`duration`] is also set, this is only used for items whose bit
+    /// rate is unknown.
+    ///
+    /// [`duration`]: #method.duration
+    pub fn size(&mut self, bytes: usize) -> &mut Prefetcher<'a> {
+        self.size = bytes;
+        self
+    }
+
+    /// Sets the length of time to buffer of each upcoming item.
+    ///
+    /// The number of bytes is worked out from the item's [`bit_rate`]; items
+    /// with an unknown bit rate buffer the [`size`] instead.
+    ///
+    /// [`bit_rate`]: ../trait.Streamable.html#method.bit_rate
+    /// [`size`]: #method.size
+    pub fn duration(&mut self, duration: Duration) -> &mut Prefetcher<'a> {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Calls `f` with the index, item and stream of each item in `queue`, in
+    /// order, prefetching the next item while `f` runs.
+    ///
+    /// The stream is an error if the item couldn't be requested, in which
+    /// case `f` can decide whether to skip it or stop. Playback stops early
+    /// if `f` returns an error, which is then returned.
+    pub fn play<T, F>(&self, queue: &[T], mut f: F) -> Result<()>
+    where
+        T: Streamable + Sync,
+        F: FnMut(usize, &T, Result<PrefetchedStream>) -> Result<()>,
+    {
+        let first = match queue.first() {
+            Some(item) => self.prefetch(item),
+            None => return Ok(()),
+        };
+
+        thread::scope(|s| {
+            let mut current = first;
+            for (i, item) in queue.iter().enumerate() {
+                let next = queue
+                    .get(i + 1)
+                    .map(|next| s.spawn(move || self.prefetch(next)));
+                f(i, item, current)?;
+                current = match next {
+                    Some(handle) => handle
+                        .join()
+                        .unwrap_or(Err(Error::Other("prefetch failed"))),
+                    None => break,
+                };
+            }
+            Ok(())
+        })
+    }
+
+    fn prefetch<T: Streamable>(&self, item: &T) -> Result<PrefetchedStream> {
+        let size = self.size_of(item);
+        let mut rest = item.stream_reader(self.client)?;
+        let mut head = Vec::with_capacity(size);
+        (&mut rest).take(size as u64).read_to_end(&mut head)?;
+        Ok(PrefetchedStream {
+            head: io::Cursor::new(head),
+            rest,
+        })
+    }
+
+    /// Returns the number of bytes to prefetch of `item`.
+    fn size_of<T: Streamable>(&self, item: &T) -> usize {
+        match (self.duration, item.bit_rate()) {
+            (Some(duration), Some(kbps)) => (duration.as_millis() as u64 * kbps / 8) as usize,
+            _ => self.size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::Query;
+    use crate::test_util;
+
+    #[test]
+    fn copy_all() {
+        let data = vec![7; CHUNK_SIZE * 2 + 5];
+        let mut out = Vec::new();
+        let token = CancellationToken::new();
+
+        let n = copy(&mut data.as_slice(), &mut out, Some(&token), None).unwrap();
+        assert_eq!(n, data.len() as u64);
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn copy_cancelled() {
+        let data = vec![7; CHUNK_SIZE * 2];
+        let mut out = Vec::new();
+        let token = CancellationToken::new();
+        token.clone().cancel();
+
+        let res = copy(&mut data.as_slice(), &mut out, Some(&token), None);
+        assert!(matches!(res, Err(Error::Cancelled)));
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn copy_timed_out() {
+        let data = vec![7; CHUNK_SIZE * 2];
+        let mut out = Vec::new();
+
+        let res = copy(&mut data.as_slice(), &mut out, None, Some(Instant::now()));
+        assert!(matches!(res, Err(Error::Io(ref e)) if e.kind() == io::ErrorKind::TimedOut));
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn transfer_with_options() {
+        let body = "HTTP/1.1 200 OK\r\nContent-Type: audio/mpeg\r\n\
+                    Content-Length: 4\r\nConnection: close\r\n\r\nmp3!";
+        let site = test_util::mock_server(&[body, body]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let url = cli.build_url("stream", Query::with("id", 1)).unwrap();
+
+        let mut out = Vec::new();
+        let n = cli
+            .copy_url_to(&url, &mut out, &TransferOptions::new())
+            .unwrap();
+        assert_eq!(n, 4);
+        assert_eq!(out, b"mp3!");
+
+        let mut out = Vec::new();
+        let res = cli.copy_url_to(
+            &url,
+            &mut out,
+            TransferOptions::new().timeout(Duration::from_secs(0)),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn download_batch() {
+        let unavailable = "HTTP/1.1 503 Service Unavailable\r\n\
+                           Content-Length: 0\r\nConnection: close\r\n\r\n";
+        let ok = "HTTP/1.1 200 OK\r\nContent-Type: audio/mpeg\r\n\
+                  Content-Length: 4\r\nConnection: close\r\n\r\nmp3!";
+        let not_found = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "failed",
+                "version": "1.14.0",
+                "error": { "code": 70, "message": "Not found" }
+            }}"#,
+        );
+        let (site, requests) = test_util::recording_mock_server(&[unavailable, ok, &not_found, ok]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let songs = songs(3);
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let seen = reports.clone();
+        let files = Mutex::new(vec![Vec::new(); songs.len()]);
+        let results = Downloader::new(&cli)
+            .concurrency(1)
+            .progress(move |p| seen.lock().unwrap().push(*p))
+            .download_all(&songs, |i, _| {
+                Ok(FileSlot {
+                    files: &files,
+                    index: i,
+                })
+            });
+
+        assert_eq!(results[0].as_ref().unwrap(), &4);
+        assert!(matches!(
+            results[1],
+            Err(Error::Api(crate::ApiError::NotFound))
+        ));
+        assert_eq!(results[2].as_ref().unwrap(), &4);
+        assert_eq!(files.lock().unwrap()[0], b"mp3!");
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 3);
+        let last = reports[2];
+        assert!(last.is_finished());
+        assert_eq!((last.completed, last.failed, last.bytes), (2, 1, 8));
+
+        let ids: Vec<_> = requests
+            .try_iter()
+            .map(|r| r.split("&id=").nth(1).unwrap().chars().next().unwrap())
+            .collect();
+        assert_eq!(ids, vec!['1', '1', '2', '3']);
+    }
+
+    fn songs(n: usize) -> Vec<crate::song::Song> {
+        (1..=n)
+            .map(|id| {
+                let raw = format!(
+                    r#"{{ "id": "{id}", "title": "Track {id}", "size": 4, "path": "{id}.mp3",
+                          "contentType": "audio/mpeg", "suffix": "mp3", "type": "music" }}"#,
+                    id = id
+                );
+                serde_json::from_str(&raw).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn prefetch_queue() {
+        let track = |body: &str| {
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: audio/mpeg\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        };
+        let unavailable = "HTTP/1.1 503 Service Unavailable\r\n\
+                           Content-Length: 0\r\nConnection: close\r\n\r\n";
+        let (site, requests) = test_util::recording_mock_server(&[
+            track("first track"),
+            track("second track"),
+            unavailable.to_string(),
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        let mut played = Vec::new();
+        Prefetcher::new(&cli)
+            .size(6)
+            .play(&songs(3), |i, song, stream| {
+                if i == 1 {
+                    // The next track is requested while this one plays.
+                    assert!(requests.recv().is_ok());
+                    assert!(requests.recv().is_ok());
+                    assert!(requests.recv().unwrap().contains("&id=3"));
+                }
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        assert!(matches!(e, Error::Connection(_)));
+                        played.push(format!("skipped {}", song.id));
+                        return Ok(());
+                    }
+                };
+                assert_eq!(stream.buffered(), 6);
+                let mut body = String::new();
+                stream.read_to_string(&mut body).unwrap();
+                played.push(body);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(played, vec!["first track", "second track", "skipped 3"]);
+
+        let res = Prefetcher::new(&cli).play(&songs(2), |_, _, _| Err(Error::Other("stop")));
+        assert!(matches!(res, Err(Error::Other("stop"))));
+    }
+
+    #[test]
+    fn prefetch_duration() {
+        let track = "HTTP/1.1 200 OK\r\nContent-Type: audio/mpeg\r\n\
+                     Content-Length: 10\r\nConnection: close\r\n\r\n0123456789";
+        let site = test_util::mock_server(&[track, track, track]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+
+        // 16 Kbps is 2 bytes per millisecond; the second track is capped to
+        // 8 Kbps, and the third has no known bit rate.
+        let song = |id: usize, bit_rate: &str| -> crate::song::Song {
+            let raw = format!(
+                r#"{{ "id": "{id}", "title": "Track {id}", "size": 10, "path": "{id}.mp3",
+                      "contentType": "audio/mpeg", "suffix": "mp3", "type": "music"{br} }}"#,
+                id = id,
+                br = bit_rate
+            );
+            serde_json::from_str(&raw).unwrap()
+        };
+        let mut queue = vec![
+            song(1, r#", "bitRate": 16"#),
+            song(2, r#", "bitRate": 16"#),
+            song(3, ""),
+        ];
+        queue[1].set_max_bit_rate(8);
+
+        let mut buffered = Vec::new();
+        Prefetcher::new(&cli)
+            .size(5)
+            .duration(Duration::from_millis(3))
+            .play(&queue, |_, _, stream| {
+                buffered.push(stream?.buffered());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(buffered, vec![6, 3, 5]);
+    }
+
+    /// A writer into one of a set of shared buffers, replacing its contents.
+    struct FileSlot<'a> {
+        files: &'a Mutex<Vec<Vec<u8>>>,
+        index: usize,
+    }
+
+    impl<'a> Write for FileSlot<'a> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.files.lock().unwrap()[self.index].extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn throttled_read() {
+        let data = vec![7; 10_000];
+        let mut out = Vec::new();
+
+        let start = Instant::now();
+        let mut reader = ThrottledReader::new(data.as_slice(), Some(40_000));
+        let n = copy(&mut reader, &mut out, None, None).unwrap();
+        assert_eq!(n, 10_000);
+        assert!(start.elapsed() >= Duration::from_millis(240));
+
+        let start = Instant::now();
+        let mut reader = ThrottledReader::new(data.as_slice(), None);
+        copy(&mut reader, &mut out, None, None).unwrap();
+        assert!(start.elapsed() < Duration::from_millis(240));
+    }
+
+    #[test]
+    fn transfer_progress() {
+        let body = "HTTP/1.1 200 OK\r\nContent-Type: audio/mpeg\r\n\
+                    Content-Length: 4\r\nConnection: close\r\n\r\nmp3!";
+        let site = test_util::mock_server(&[body]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let url = cli.build_url("stream", Query::with("id", 1)).unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = std::sync::Mutex::new(tx);
+        let mut options = TransferOptions::new();
+        options.progress(move |got, total| tx.lock().unwrap().send((got, total)).unwrap());
+
+        let mut out = Vec::new();
+        cli.copy_url_to(&url, &mut out, &options).unwrap();
+        let reports: Vec<_> = rx.try_iter().collect();
+        assert_eq!(reports.last(), Some(&(4, Some(4))));
+    }
+
+    #[test]
+    fn media_reader() {
+        let error = test_util::json_response(
+            r#"{"subsonic-response": {
+                "status": "failed",
+                "version": "1.14.0",
+                "error": { "code": 70, "message": "Not found" }
+            }}"#,
+        );
+        let body = "HTTP/1.1 200 OK\r\nContent-Type: audio/flac\r\n\
+                    Content-Length: 5\r\nConnection: close\r\n\r\nflac!";
+        let site = test_util::mock_server(&[body, &error]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let url = cli.build_url("download", Query::with("id", 1)).unwrap();
+
+        let mut reader = cli.open_url(&url).unwrap();
+        assert_eq!(reader.content_length(), Some(5));
+        assert_eq!(reader.content_type(), Some("audio/flac"));
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"flac!");
+
+        assert!(matches!(
+            cli.open_url(&url),
+            Err(Error::Api(crate::ApiError::NotFound))
+        ));
+    }
+
+    #[test]
+    fn seek_fetches_ranges() {
+        let site = test_util::mock_server(&[
+            "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 0-3/10\r\n\
+             Content-Length: 4\r\nConnection: close\r\n\r\n0123",
+            "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 8-9/10\r\n\
+             Content-Length: 2\r\nConnection: close\r\n\r\n89",
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let url = cli.build_url("stream", Query::with("id", 1)).unwrap();
+        let mut stream = SeekableStream::with_range_size(&cli, url, 4).unwrap();
+        assert_eq!(stream.len(), 10);
+
+        let mut buf = Vec::new();
+        stream.seek(SeekFrom::End(-2)).unwrap();
+        stream.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"89");
+
+        // Served from the cache; the server has no more responses.
+        let mut buf = [0; 3];
+        stream.seek(SeekFrom::Start(1)).unwrap();
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"123");
+    }
+
+    #[test]
+    fn short_range_refetched() {
+        let partial = |range: &str, body: &str| {
+            format!(
+                "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}/10\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                range,
+                body.len(),
+                body
+            )
+        };
+        let (site, requests) = test_util::recording_mock_server(&[
+            partial("0-3", "0123"),
+            partial("4-5", "45"),
+            partial("6-7", "67"),
+            partial("8-9", "89"),
+            partial("0-3", "0123"),
+            partial("4-7", ""),
+        ]);
+        let cli = Client::new(&site, "user", "pass").unwrap();
+        let url = cli.build_url("stream", Query::with("id", 1)).unwrap();
+
+        let mut stream = SeekableStream::with_range_size(&cli, url.clone(), 4).unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"0123456789");
+        let ranges = requests
+            .try_iter()
+            .map(|r| {
+                r.lines()
+                    .find(|l| l.to_lowercase().starts_with("range:"))
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ranges[2].to_lowercase(), "range: bytes=6-7");
+
+        // A server that stops sending data doesn't end the stream early.
+        let mut stream = SeekableStream::with_range_size(&cli, url, 4).unwrap();
+        let mut buf = Vec::new();
+        let err = stream.read_to_end(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -1,12 +1,12 @@
 //! User access and management APIs.
 
-use serde_json;
-
+use crate::id;
 use crate::query::Query;
-use crate::{Client, Result};
+use crate::response::ListStream;
+use crate::{ApiError, Client, Error, Id, Result};
 
 /// A struct representing a Subsonic user.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct User {
     /// A user's name.
     pub username: String,
@@ -69,16 +69,66 @@ pub struct User {
     pub avatar_last_changed: String,
     /// The list of media folders the user has access to.
     #[serde(rename = "folder")]
-    pub folders: Vec<u64>,
-    #[serde(default)]
+    pub folders: Vec<Id>,
+    #[serde(skip)]
     _private: bool,
 }
 
+/// A permission that can be granted to or revoked from a user.
+///
+/// Used with [`Client::set_user_roles`] to change some of a user's roles
+/// without resending the rest of their settings.
+///
+/// [`Client::set_user_roles`]: ./struct.Client.html#method.set_user_roles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Administrative rights.
+    Admin,
+    /// Managing personal settings and changing their own password.
+    Settings,
+    /// Playing media.
+    Stream,
+    /// Controlling the jukebox.
+    Jukebox,
+    /// Downloading media.
+    Download,
+    /// Uploading media.
+    Upload,
+    /// Changing cover art and media tags.
+    CoverArt,
+    /// Creating and editing comments and ratings.
+    Comment,
+    /// Administrating podcasts.
+    Podcast,
+    /// Sharing content.
+    Share,
+    /// Starting video conversions.
+    VideoConversion,
+}
+
+impl Role {
+    /// The name of the query parameter setting the role.
+    pub(crate) fn param(self) -> &'static str {
+        match self {
+            Role::Admin => "adminRole",
+            Role::Settings => "settingsRole",
+            Role::Stream => "streamRole",
+            Role::Jukebox => "jukeboxRole",
+            Role::Download => "downloadRole",
+            Role::Upload => "uploadRole",
+            Role::CoverArt => "coverArtRole",
+            Role::Comment => "commentRole",
+            Role::Podcast => "podcastRole",
+            Role::Share => "shareRole",
+            Role::VideoConversion => "videoConversionRole",
+        }
+    }
+}
+
 impl User {
     /// Fetches a single user's information from the server.
     pub fn get(client: &Client, username: &str) -> Result<User> {
-        let res = client.get("getUser", Query::with("username", username))?;
-        Ok(serde_json::from_value::<User>(res)?)
+        client.get_required("getUser", Query::with("username", username), &["user"])
     }
 
     /// Lists all users on the server.
@@ -90,8 +140,39 @@ impl User {
     ///
     /// [`NotAuthorized`]: ./enum.ApiError.html#variant.NotAuthorized
     pub fn list(client: &Client) -> Result<Vec<User>> {
-        let user = client.get("getUsers", Query::none())?;
-        Ok(get_list_as!(user, User))
+        client.get_list("getUsers", Query::none(), "users", "user")
+    }
+
+    /// Lists all users on the server, parsing them as they are received.
+    ///
+    /// The Subsonic API doesn't page users, so on a large installation
+    /// [`list`] holds a large response in memory at once. The iterator
+    /// returned here instead yields users as the response is read, holding
+    /// only a few at a time.
+    ///
+    /// # Errors
+    ///
+    /// As with [`list`], the method will error for non-administrative users.
+    /// An error in the response after some users have been read is returned
+    /// from the iterator.
+    ///
+    /// [`list`]: #method.list
+    pub fn list_iter(client: &Client) -> Result<ListStream<User>> {
+        client.get_stream("getUsers", Query::none(), &["users", "user"])
+    }
+
+    /// Returns whether a user with the given name exists on the server.
+    ///
+    /// # Errors
+    ///
+    /// Errors other than the user not being found are returned, such as
+    /// attempting to look up another user without administrative rights.
+    pub fn exists(client: &Client, username: &str) -> Result<bool> {
+        match User::get(client, username) {
+            Ok(_) => Ok(true),
+            Err(Error::Api(ApiError::NotFound)) => Ok(false),
+            Err(e) => Err(e),
+        }
     }
 
     /// Changes the user's password.
@@ -104,7 +185,7 @@ impl User {
         let args = Query::with("username", self.username.as_str())
             .arg("password", password)
             .build();
-        client.get("changePassword", args)?;
+        client.get_value("changePassword", args)?;
         Ok(())
     }
 
@@ -113,7 +194,7 @@ impl User {
     /// The method makes no guarantee as to the encoding of the image, but does
     /// guarantee that it is a valid image file.
     pub fn avatar(&self, client: &Client) -> Result<Vec<u8>> {
-        client.get_bytes("getAvatar", Query::with("username", self.username.as_str()))
+        client.get_image("getAvatar", Query::with("username", self.username.as_str()))
     }
 
     /// Creates a new local user to be pushed to the server.
@@ -125,9 +206,19 @@ impl User {
         UserBuilder::new(username, password, email)
     }
 
+    /// Begins editing an existing user on the server.
+    ///
+    /// Only the fields set on the returned [`UserUpdate`] are sent to the
+    /// server; everything else about the user is left as it is.
+    ///
+    /// [`UserUpdate`]: struct.UserUpdate.html
+    pub fn edit(username: &str) -> UserUpdate {
+        UserUpdate::new(username)
+    }
+
     /// Removes the user from the Subsonic server.
     pub fn delete(&self, client: &Client) -> Result<()> {
-        client.get(
+        client.get_value(
             "deleteUser",
             Query::with("username", self.username.as_str()),
         )?;
@@ -136,6 +227,10 @@ impl User {
 
     /// Pushes any changes made to the user to the server.
     ///
+    /// Every field of the user is sent, so a field that was missing from the
+    /// server's response (and so defaulted locally) will overwrite the
+    /// server's value. Use [`User::edit`] to change only some fields.
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -168,15 +263,113 @@ impl User {
             .arg("jukeboxRole", self.jukebox_role)
             .arg("downloadRole", self.download_role)
             .arg("uploadRole", self.upload_role)
-            .arg("coverArt_role", self.cover_art_role)
+            .arg("coverArtRole", self.cover_art_role)
             .arg("commentRole", self.comment_role)
             .arg("podcastRole", self.podcast_role)
             .arg("shareRole", self.share_role)
             .arg("videoConversionRole", self.video_conversion_role)
-            .arg_list("musicFolderId", &self.folders.clone())
+            .arg_list("musicFolderId", &self.folders)
             .arg("maxBitRate", self.max_bit_rate)
             .build();
-        client.get("updateUser", args)?;
+        client.get_value("updateUser", args)?;
+        Ok(())
+    }
+}
+
+/// A set of changes to an existing user.
+///
+/// Fields that are not set are left out of the update entirely, rather than
+/// reset to a default.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate sunk;
+/// use sunk::{Client, Role, User};
+///
+/// # fn run() -> sunk::Result<()> {
+/// let client = Client::new("http://demo.subsonic.org", "admin", "admin")?;
+///
+/// User::edit("guest")
+///     .email("guest@example.com")
+///     .role(Role::Comment, false)
+///     .update(&client)?;
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// #     run().unwrap();
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UserUpdate {
+    username: String,
+    password: Option<String>,
+    email: Option<String>,
+    ldap_authenticated: Option<bool>,
+    roles: Vec<(Role, bool)>,
+    folders: Option<Vec<Id>>,
+    max_bit_rate: Option<u64>,
+}
+
+macro_rules! update {
+    ($f:ident: $t:ty) => {
+        #[allow(missing_docs)]
+        pub fn $f(&mut self, $f: $t) -> &mut UserUpdate {
+            self.$f = Some($f.into());
+            self
+        }
+    };
+}
+
+impl UserUpdate {
+    /// Begins an update to the named user.
+    fn new(username: &str) -> UserUpdate {
+        UserUpdate {
+            username: username.to_string(),
+            ..UserUpdate::default()
+        }
+    }
+
+    // Sets the user's password.
+    update!(password: &str);
+    // Sets the user's email.
+    update!(email: &str);
+    // Sets whether the user is authenticated in LDAP.
+    update!(ldap_authenticated: bool);
+    // Sets the maximum bit rate (in Kbps) the user is allowed to stream at.
+    update!(max_bit_rate: u64);
+
+    /// Sets the IDs of the music folders the user is allowed to access.
+    pub fn folders<I: Into<Id> + Clone>(&mut self, ids: &[I]) -> &mut UserUpdate {
+        self.folders = Some(id::collect(ids));
+        self
+    }
+
+    /// Grants or revokes a role.
+    pub fn role(&mut self, role: Role, enabled: bool) -> &mut UserUpdate {
+        self.roles.retain(|&(r, _)| r != role);
+        self.roles.push((role, enabled));
+        self
+    }
+
+    /// Sends the changes to the server.
+    ///
+    /// # Errors
+    ///
+    /// Updating a user requires administrative rights.
+    pub fn update(&self, client: &Client) -> Result<()> {
+        let mut args = Query::with("username", self.username.as_str());
+        args.arg("password", self.password.as_deref())
+            .arg("email", self.email.as_deref())
+            .arg("ldapAuthenticated", self.ldap_authenticated)
+            .arg("maxBitRate", self.max_bit_rate);
+        for &(role, enabled) in &self.roles {
+            args.arg(role.param(), enabled);
+        }
+        if let Some(ref folders) = self.folders {
+            args.arg_list("musicFolderId", folders);
+        }
+        client.get_value("updateUser", args.build())?;
         Ok(())
     }
 }
@@ -199,7 +392,7 @@ pub struct UserBuilder {
     podcast_role: bool,
     share_role: bool,
     video_conversion_role: bool,
-    folders: Vec<u64>,
+    folders: Vec<Id>,
     max_bit_rate: u64,
 }
 
@@ -254,14 +447,21 @@ impl UserBuilder {
     build!(share_role: bool);
     // Allows the user to start video coversions.
     build!(video_conversion_role: bool);
-    // IDs of the music folders the user is allowed to access.
-    build!(folders: &[u64]);
     // The maximum bit rate (in Kbps) the user is allowed to stream at. Higher
     // bit rate streams will be downsampled to their limit.
     build!(max_bit_rate: u64);
 
+    /// Sets the IDs of the music folders the user is allowed to access.
+    pub fn folders<I: Into<Id> + Clone>(&mut self, ids: &[I]) -> &mut UserBuilder {
+        self.folders = id::collect(ids);
+        self
+    }
+
     /// Pushes a defined new user to the Subsonic server.
-    pub fn create(&self, client: &Client) -> Result<()> {
+    ///
+    /// Returns the user as the server reports it after creation, which may
+    /// differ from the builder if the server applies its own defaults.
+    pub fn create(&self, client: &Client) -> Result<User> {
         let args = Query::with("username", self.username.as_ref())
             .arg("password", self.password.as_ref())
             .arg("email", self.email.as_ref())
@@ -272,7 +472,7 @@ impl UserBuilder {
             .arg("jukeboxRole", self.jukebox_role)
             .arg("downloadRole", self.download_role)
             .arg("uploadRole", self.upload_role)
-            .arg("coverArt_role", self.cover_art_role)
+            .arg("coverArtRole", self.cover_art_role)
             .arg("commentRole", self.comment_role)
             .arg("podcastRole", self.podcast_role)
             .arg("shareRole", self.share_role)
@@ -280,8 +480,8 @@ impl UserBuilder {
             .arg_list("musicFolderId", &self.folders)
             .arg("maxBitRate", self.max_bit_rate)
             .build();
-        client.get("createUser", args)?;
-        Ok(())
+        client.get_value("createUser", args)?;
+        User::get(client, &self.username)
     }
 }
 
@@ -299,4 +499,177 @@ mod tests {
         assert!(guest.stream_role);
         assert!(!guest.admin_role);
     }
+
+    #[test]
+    fn list_users_streamed() {
+        let body = format!(
+            r#"{{"subsonic-response": {{
+                "status": "ok",
+                "version": "1.14.0",
+                "users": {{ "user": [{}, {}] }}
+            }}}}"#,
+            raw("admin"),
+            raw("guest")
+        );
+        let site = test_util::mock_server(&[test_util::json_response(&body)]);
+        let cli = Client::new(&site, "admin", "pass").unwrap();
+
+        let names = User::list_iter(&cli)
+            .unwrap()
+            .map(|u| u.unwrap().username)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["admin", "guest"]);
+    }
+
+    #[test]
+    fn user_exists() {
+        let found = format!(
+            r#"{{"subsonic-response": {{"status": "ok", "version": "1.14.0", "user": {}}}}}"#,
+            raw("guest")
+        );
+        let missing = r#"{"subsonic-response": {
+            "status": "failed",
+            "version": "1.14.0",
+            "error": { "code": 70, "message": "User not found" }
+        }}"#;
+        let site = test_util::mock_server(&[
+            test_util::json_response(&found),
+            test_util::json_response(missing),
+        ]);
+        let cli = Client::new(&site, "admin", "pass").unwrap();
+
+        assert!(User::exists(&cli, "guest").unwrap());
+        assert!(!User::exists(&cli, "nobody").unwrap());
+    }
+
+    #[test]
+    fn create_returns_user() {
+        let ok = r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#;
+        let user = format!(
+            r#"{{"subsonic-response": {{"status": "ok", "version": "1.14.0", "user": {}}}}}"#,
+            raw("guest")
+        );
+        let (site, requests) = test_util::recording_mock_server(&[
+            test_util::json_response(ok),
+            test_util::json_response(&user),
+        ]);
+        let cli = Client::new(&site, "admin", "pass").unwrap();
+
+        let created = User::create("guest", "secret", "guest@example.com")
+            .stream_role(true)
+            .cover_art_role(true)
+            .create(&cli)
+            .unwrap();
+        assert_eq!(created.username, "guest");
+        assert!(created.stream_role);
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/createUser?"));
+        assert!(request.contains("streamRole=true"));
+        assert!(request.contains("coverArtRole=true"));
+        assert!(!request.contains("coverArt_role"));
+        assert!(requests.recv().unwrap().starts_with("GET /rest/getUser?"));
+    }
+
+    #[test]
+    fn update_sends_roles() {
+        let ok = r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#;
+        let (site, requests) = test_util::recording_mock_server(&[test_util::json_response(ok)]);
+        let cli = Client::new(&site, "admin", "pass").unwrap();
+
+        let mut user = serde_json::from_str::<User>(&raw("guest")).unwrap();
+        user.cover_art_role = true;
+        user.update(&cli).unwrap();
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/updateUser?"));
+        assert!(request.contains("username=guest"));
+        assert!(request.contains("downloadRole=true"));
+        assert!(request.contains("coverArtRole=true"));
+        assert!(!request.contains("coverArt_role"));
+    }
+
+    #[test]
+    fn set_roles_only() {
+        let ok = r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#;
+        let (site, requests) = test_util::recording_mock_server(&[test_util::json_response(ok)]);
+        let cli = Client::new(&site, "admin", "pass").unwrap();
+
+        cli.set_user_roles("guest", &[(Role::Admin, true), (Role::CoverArt, false)])
+            .unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /rest/updateUser?"));
+        assert!(request.contains("username=guest"));
+        assert!(request.contains("adminRole=true"));
+        assert!(request.contains("coverArtRole=false"));
+        assert!(!request.contains("email="));
+        assert!(!request.contains("streamRole="));
+    }
+
+    #[test]
+    fn update_only_set_fields() {
+        let ok = r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#;
+        let (site, requests) = test_util::recording_mock_server(&[test_util::json_response(ok)]);
+        let cli = Client::new(&site, "admin", "pass").unwrap();
+
+        User::edit("guest")
+            .email("guest@example.com")
+            .role(Role::Stream, false)
+            .role(Role::Stream, true)
+            .update(&cli)
+            .unwrap();
+        let request = requests.recv().unwrap();
+        let query = request.split_whitespace().nth(1).unwrap();
+        assert!(query.starts_with("/rest/updateUser?"));
+        assert!(query.contains("username=guest"));
+        assert!(query.contains("email=guest%40example.com"));
+        assert_eq!(query.matches("streamRole=").count(), 1);
+        assert!(query.contains("streamRole=true"));
+        assert!(!query.contains("adminRole="));
+        assert!(!query.contains("maxBitRate="));
+        assert!(!query.contains("musicFolderId="));
+        assert!(!query.contains("password="));
+    }
+
+    #[test]
+    fn folder_ids() {
+        let ok = r#"{"subsonic-response": {"status": "ok", "version": "1.14.0"}}"#;
+        let (site, requests) = test_util::recording_mock_server(&[test_util::json_response(ok)]);
+        let cli = Client::new(&site, "admin", "pass").unwrap();
+
+        let user = serde_json::from_str::<User>(&raw("guest")).unwrap();
+        assert_eq!(user.folders, vec![Id::from(0)]);
+
+        User::edit("guest")
+            .folders(&["0", "mf-a1b2"])
+            .update(&cli)
+            .unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.contains("musicFolderId=0&musicFolderId=mf-a1b2"));
+    }
+
+    fn raw(username: &str) -> String {
+        format!(
+            r#"{{
+            "username" : "{}",
+            "email" : "{}@example.com",
+            "scrobblingEnabled" : false,
+            "adminRole" : false,
+            "settingsRole" : true,
+            "downloadRole" : true,
+            "uploadRole" : false,
+            "playlistRole" : true,
+            "coverArtRole" : false,
+            "commentRole" : false,
+            "podcastRole" : false,
+            "streamRole" : true,
+            "jukeboxRole" : false,
+            "shareRole" : false,
+            "videoConversionRole" : false,
+            "avatarLastChanged" : "2017-03-12T11:07:27.000Z",
+            "folder" : [ 0 ]
+        }}"#,
+            username, username
+        )
+    }
 }
@@ -1,35 +1,77 @@
 //! Subsonic API version APIs.
 
-use std::{convert, fmt};
+use std::{convert, fmt, str};
 
-#[allow(missing_docs)]
+use crate::{Error, Result};
+
+/// A Subsonic API version, such as `1.16.1`.
+///
+/// Versions are usually written as string literals, converted with `From`,
+/// which panics on an invalid version. Versions from elsewhere, such as a
+/// server's response, should be parsed with [`str::parse`] instead:
+///
+/// ```
+/// use sunk::Version;
+///
+/// assert_eq!("1.16".parse::<Version>().unwrap(), Version::from("1.16.0"));
+/// assert_eq!("1.16.1-beta".parse::<Version>().unwrap(), Version::from("1.16.1"));
+/// assert!("".parse::<Version>().is_err());
+/// ```
+///
+/// [`str::parse`]: https://doc.rust-lang.org/std/primitive.str.html#method.parse
 #[derive(PartialEq, PartialOrd, Eq, Ord, Copy, Clone)]
 pub struct Version(u8, u8, u8);
 
-impl convert::From<String> for Version {
-    fn from(s: String) -> Version {
-        let mut spl = s.split('.');
+impl str::FromStr for Version {
+    type Err = Error;
 
-        macro_rules! ver {
-            ($v:ident) => {
-                let $v = match spl.next() {
-                    Some(n) => n.parse::<u8>().unwrap(),
-                    None => 0,
-                };
-            };
+    /// Parses a version of one to three dot-separated numbers. Missing
+    /// numbers are taken as zero, and a suffix after a `-` or `+` (as in
+    /// `1.16.1-beta`) is ignored.
+    fn from_str(s: &str) -> Result<Version> {
+        let s = s.trim();
+        let s = s.split(['-', '+']).next().unwrap_or(s);
+        if s.is_empty() {
+            return Err(Error::Other("empty version"));
         }
 
-        ver!(major);
-        ver!(minor);
-        ver!(inc);
+        let mut parts = [0; 3];
+        for (i, n) in s.split('.').enumerate() {
+            let part = parts.get_mut(i).ok_or(Error::Other("invalid version"))?;
+            *part = n.parse().map_err(|_| Error::Other("invalid version"))?;
+        }
+        Ok(Version(parts[0], parts[1], parts[2]))
+    }
+}
 
-        Version(major, minor, inc)
+impl convert::From<String> for Version {
+    /// Converts a version string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string is not a valid version; see [`Version`] for
+    /// parsing versions fallibly.
+    ///
+    /// [`Version`]: ./struct.Version.html
+    fn from(s: String) -> Version {
+        Version::from(s.as_str())
     }
 }
 
 impl<'a> convert::From<&'a str> for Version {
+    /// Converts a version string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string is not a valid version; see [`Version`] for
+    /// parsing versions fallibly.
+    ///
+    /// [`Version`]: ./struct.Version.html
     fn from(s: &'a str) -> Version {
-        Version::from(s.to_string())
+        match s.parse() {
+            Ok(ver) => ver,
+            Err(_) => panic!("invalid API version: {:?}", s),
+        }
     }
 }
 
@@ -66,4 +108,16 @@ mod tests {
         assert_eq!(v.1, 12);
         assert_eq!(v.2, 0);
     }
+
+    #[test]
+    fn test_parse_api_invalid() {
+        assert!("".parse::<Version>().is_err());
+        assert!("one.two".parse::<Version>().is_err());
+        assert!("1.2.3.4".parse::<Version>().is_err());
+        assert!("1.300".parse::<Version>().is_err());
+        assert_eq!(
+            "1.16.1-beta".parse::<Version>().unwrap(),
+            Version::from("1.16.1")
+        );
+    }
 }